@@ -0,0 +1,21 @@
+use cosmwasm_schema::{export_schema_with_title, schema_for, write_api};
+use cw721_nft_marketplace::msg::{
+    Cw721CustomMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, SudoMsg,
+};
+use std::env::current_dir;
+
+fn main() {
+    write_api! {
+        instantiate: InstantiateMsg,
+        execute: ExecuteMsg,
+        query: QueryMsg,
+        migrate: MigrateMsg,
+        sudo: SudoMsg,
+    }
+
+    // Sent inside `Cw721ExecuteMsg::SendNft { msg }` rather than to the contract directly, so it
+    // is not part of the generated API.
+    let mut out_dir = current_dir().unwrap();
+    out_dir.push("schema");
+    export_schema_with_title(&schema_for!(Cw721CustomMsg), &out_dir, "Cw721CustomMsg");
+}
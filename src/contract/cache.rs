@@ -0,0 +1,121 @@
+use cosmwasm_std::{Order, Record, Storage};
+use std::{collections::BTreeMap, iter::Peekable, ops::Bound};
+
+/// Buffered writes in key order, as `(key, value)` with `None` for removed keys.
+type WriteIter<'b> = Box<dyn Iterator<Item = (&'b Vec<u8>, &'b Option<Vec<u8>>)> + 'b>;
+
+/// Buffers writes on top of another storage, so that a batch can run each item against its own
+/// cache and only keep the writes of the items that succeeded.
+pub struct StorageCache<'a> {
+    base: &'a dyn Storage,
+    writes: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+}
+
+/// The writes buffered by a `StorageCache`. A `None` value removes the key.
+pub struct StorageWrites(BTreeMap<Vec<u8>, Option<Vec<u8>>>);
+
+impl<'a> StorageCache<'a> {
+    pub fn new(base: &'a dyn Storage) -> Self {
+        StorageCache {
+            base,
+            writes: BTreeMap::new(),
+        }
+    }
+
+    /// Ends the cache, handing back its writes for `StorageWrites::commit`.
+    pub fn into_writes(self) -> StorageWrites {
+        StorageWrites(self.writes)
+    }
+}
+
+impl StorageWrites {
+    pub fn commit(self, storage: &mut dyn Storage) {
+        for (key, value) in self.0 {
+            match value {
+                Some(value) => storage.set(&key, &value),
+                None => storage.remove(&key),
+            }
+        }
+    }
+}
+
+impl Storage for StorageCache<'_> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        match self.writes.get(key) {
+            Some(value) => value.clone(),
+            None => self.base.get(key),
+        }
+    }
+
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'b> {
+        // `BTreeMap::range` panics on an inverted range, where storage yields nothing.
+        if let (Some(start), Some(end)) = (start, end) {
+            if start >= end {
+                return Box::new(std::iter::empty());
+            }
+        }
+        let bounds = (
+            start.map_or(Bound::Unbounded, |start| Bound::Included(start.to_vec())),
+            end.map_or(Bound::Unbounded, |end| Bound::Excluded(end.to_vec())),
+        );
+        let writes: WriteIter = match order {
+            Order::Ascending => Box::new(self.writes.range(bounds)),
+            Order::Descending => Box::new(self.writes.range(bounds).rev()),
+        };
+        Box::new(MergedRange {
+            base: self.base.range(start, end, order).peekable(),
+            writes: writes.peekable(),
+            order,
+        })
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.writes.insert(key.to_vec(), Some(value.to_vec()));
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.writes.insert(key.to_vec(), None);
+    }
+}
+
+/// Walks the base storage and the buffered writes in key order, letting writes shadow the
+/// records they overwrite or remove.
+struct MergedRange<'b> {
+    base: Peekable<Box<dyn Iterator<Item = Record> + 'b>>,
+    writes: Peekable<WriteIter<'b>>,
+    order: Order,
+}
+
+impl Iterator for MergedRange<'_> {
+    type Item = Record;
+
+    fn next(&mut self) -> Option<Record> {
+        loop {
+            let Some((write_key, _)) = self.writes.peek() else {
+                return self.base.next();
+            };
+            if let Some((base_key, _)) = self.base.peek() {
+                let cmp = base_key.as_slice().cmp(write_key.as_slice());
+                let cmp = match self.order {
+                    Order::Ascending => cmp,
+                    Order::Descending => cmp.reverse(),
+                };
+                if cmp.is_lt() {
+                    return self.base.next();
+                }
+                if cmp.is_eq() {
+                    self.base.next();
+                }
+            }
+            let (key, value) = self.writes.next()?;
+            if let Some(value) = value {
+                return Some((key.clone(), value.clone()));
+            }
+        }
+    }
+}
@@ -0,0 +1,506 @@
+//! Typed events emitted by the auction, bundle, raffle and rental handlers.
+//!
+//! The chain prefixes custom event types with `wasm-`, so indexers see `wasm-auction_created`,
+//! `wasm-auction_bid` and so on. Auction events carry `auction_id`, bundle events `bundle_id`,
+//! raffle events `raffle_id`, and rental events `token_address` and `token_id`; the remaining
+//! keys are listed on each variant and only change together with this module. Amounts are plain
+//! integers in the sale's `denom` unless documented as a coin string (e.g. `100ufee`).
+//! Timestamps are unix seconds.
+
+use crate::state::{
+    BundleItem, IbcOrigin, NFTAuctionState, PendingBundle, Raffle, RandomnessSource, Rental,
+};
+use cosmwasm_std::{Addr, Coin, Event, Timestamp, Uint128};
+
+pub enum AuctionEvent {
+    /// `auction_created`: `auction_id`, `seller`, `token_address`, `token_id`, `lot_size`,
+    /// `start_time`, `end_time`, `denom`, and `min_bid` / `reserve_price` / `accepted_denoms`
+    /// (comma separated) / `timer_duration` when set. Auctions with a `timer_duration` have no
+    /// end yet and report `OPEN_END` as their `end_time`.
+    Created { auction: NFTAuctionState },
+    /// `auction_updated`: `auction_id`, `denom`, and `min_bid` / `reserve_price` /
+    /// `accepted_denoms` when set.
+    Updated { auction: NFTAuctionState },
+    /// `auction_bid`: `auction_id`, `bidder`, `high_bidder`, `high_bid`, `denom`. The high
+    /// bidder differs from the bidder when a proxy bid kept the lead. `denom` is the one the high
+    /// bid was paid in.
+    Bid {
+        auction: NFTAuctionState,
+        bidder: Addr,
+    },
+    /// `auction_outbid`: `auction_id`, `bidder` (who lost the lead), `high_bidder`.
+    Outbid {
+        auction_id: Uint128,
+        bidder: Addr,
+        high_bidder: Addr,
+    },
+    /// `auction_proxy_outbid`: `auction_id`, `bidder`, `high_bidder`. The bid never took the lead:
+    /// the high bidder's proxy maximum covered it and raised past it straight away.
+    ProxyOutbid {
+        auction_id: Uint128,
+        bidder: Addr,
+        high_bidder: Addr,
+    },
+    /// `auction_timer_started`: `auction_id`, `end_time`. A bid met the reserve price of an
+    /// auction that starts on its first bid.
+    TimerStarted {
+        auction_id: Uint128,
+        end_time: Timestamp,
+    },
+    /// `auction_extended`: `auction_id`, `previous_end_time`, `end_time`. A bid close to the end
+    /// of an auction with an anti-sniping `extension` pushed the end back.
+    Extended {
+        auction_id: Uint128,
+        previous_end_time: Timestamp,
+        end_time: Timestamp,
+    },
+    /// `auction_rescheduled`: `auction_id`, `previous_end_time`, `end_time`. The seller moved the
+    /// end, which may be earlier or later than before.
+    Rescheduled {
+        auction_id: Uint128,
+        previous_end_time: Timestamp,
+        end_time: Timestamp,
+    },
+    /// `auction_cancelled`: `auction_id`, `seller`, `had_bids`, `penalty`.
+    Cancelled {
+        auction: NFTAuctionState,
+        penalty: Uint128,
+    },
+    /// `auction_settled`: `auction_id`, `seller`, `recipient`, `winning_bid`, `denom` (of the
+    /// winning bid), `reserve_met`, `keeper`, and `keeper_reward` (coin string) when one was
+    /// paid.
+    Settled {
+        auction: NFTAuctionState,
+        recipient: String,
+        reserve_met: bool,
+        keeper: Addr,
+        keeper_reward: Option<Coin>,
+    },
+    /// `auction_invalidated`: `auction_id`, `seller`, `had_bids`. The NFT of an approval-based
+    /// auction left the seller's wallet or lost the marketplace's approval, or the marketplace
+    /// is no longer the minter of a lazy auction's collection.
+    Invalidated { auction: NFTAuctionState },
+    /// `auction_settle_skipped`: `auction_id`, `reason`.
+    SettleSkipped { auction_id: Uint128, reason: String },
+    /// `auction_delivery_failed`: `auction_id`, `token_address`, `token_id`, `recipient`,
+    /// `error`. Transferring, minting or sending back an NFT on settlement failed, so it stays
+    /// with the marketplace until claimed.
+    DeliveryFailed {
+        auction_id: Uint128,
+        item: BundleItem,
+        recipient: String,
+        error: String,
+    },
+    /// `auction_refunded`: `auction_id`, `recipient`, `amount`, `denom`, `reason`, and
+    /// `ibc_channel`, `ibc_recipient` when the refund goes back to a hook bidder over IBC.
+    Refunded {
+        auction_id: Uint128,
+        recipient: Addr,
+        amount: Coin,
+        reason: RefundReason,
+        ibc_origin: Option<IbcOrigin>,
+    },
+    /// `auction_refund_credited`: `auction_id`, `recipient`, `amount`, `denom`. An IBC refund
+    /// failed or timed out, so it was credited to the balance of the bidder's local recipient.
+    RefundCredited {
+        auction_id: Uint128,
+        recipient: Addr,
+        amount: Coin,
+    },
+}
+
+/// Why bid funds were sent back, reported in `auction_refunded` events.
+pub enum RefundReason {
+    /// The bidder lost the lead.
+    Outbid,
+    /// The bid was beaten by the high bidder's proxy maximum as soon as it was made.
+    ProxyOutbid,
+    /// The seller cancelled the auction. The amount includes any cancellation penalty.
+    Cancelled,
+    /// The auction ended below its reserve price.
+    ReserveNotMet,
+    /// The unused part of the winner's proxy maximum.
+    ProxyExcess,
+    /// The NFT of an approval-based auction is no longer available.
+    Invalidated,
+}
+
+impl RefundReason {
+    fn as_str(&self) -> &'static str {
+        match self {
+            RefundReason::Outbid => "outbid",
+            RefundReason::ProxyOutbid => "proxy_outbid",
+            RefundReason::Cancelled => "cancelled",
+            RefundReason::ReserveNotMet => "reserve_not_met",
+            RefundReason::ProxyExcess => "proxy_excess",
+            RefundReason::Invalidated => "invalidated",
+        }
+    }
+}
+
+fn auction_event(ty: &str, auction_id: Uint128) -> Event {
+    Event::new(ty).add_attribute("auction_id", auction_id)
+}
+
+fn with_terms(mut event: Event, auction: &NFTAuctionState) -> Event {
+    event = event.add_attribute("denom", &auction.coin_denomination);
+    if let Some(min_bid) = auction.min_bid {
+        event = event.add_attribute("min_bid", min_bid);
+    }
+    if let Some(reserve_price) = auction.reserve_price {
+        event = event.add_attribute("reserve_price", reserve_price);
+    }
+    if !auction.accepted_denoms.is_empty() {
+        event = event.add_attribute("accepted_denoms", auction.accepted_denoms.join(","));
+    }
+    event
+}
+
+impl From<AuctionEvent> for Event {
+    fn from(event: AuctionEvent) -> Self {
+        match event {
+            AuctionEvent::Created { auction } => {
+                let event = auction_event("auction_created", auction.auction_id)
+                    .add_attribute("seller", &auction.owner)
+                    .add_attribute("token_address", &auction.token_address)
+                    .add_attribute("token_id", &auction.token_id)
+                    .add_attribute("lot_size", auction.items().len().to_string())
+                    .add_attribute("start_time", auction.start.seconds().to_string())
+                    .add_attribute("end_time", auction.end.seconds().to_string());
+                let event = match auction.timer_duration {
+                    Some(duration) => event.add_attribute("timer_duration", duration.to_string()),
+                    None => event,
+                };
+                with_terms(event, &auction)
+            }
+            AuctionEvent::Updated { auction } => with_terms(
+                auction_event("auction_updated", auction.auction_id),
+                &auction,
+            ),
+            AuctionEvent::Bid { auction, bidder } => {
+                auction_event("auction_bid", auction.auction_id)
+                    .add_attribute("bidder", bidder)
+                    .add_attribute("high_bidder", &auction.high_bidder_addr)
+                    .add_attribute("high_bid", auction.high_bidder_amount)
+                    .add_attribute("denom", auction.bid_denom())
+            }
+            AuctionEvent::Outbid {
+                auction_id,
+                bidder,
+                high_bidder,
+            } => auction_event("auction_outbid", auction_id)
+                .add_attribute("bidder", bidder)
+                .add_attribute("high_bidder", high_bidder),
+            AuctionEvent::ProxyOutbid {
+                auction_id,
+                bidder,
+                high_bidder,
+            } => auction_event("auction_proxy_outbid", auction_id)
+                .add_attribute("bidder", bidder)
+                .add_attribute("high_bidder", high_bidder),
+            AuctionEvent::TimerStarted {
+                auction_id,
+                end_time,
+            } => auction_event("auction_timer_started", auction_id)
+                .add_attribute("end_time", end_time.seconds().to_string()),
+            AuctionEvent::Extended {
+                auction_id,
+                previous_end_time,
+                end_time,
+            } => auction_event("auction_extended", auction_id)
+                .add_attribute("previous_end_time", previous_end_time.seconds().to_string())
+                .add_attribute("end_time", end_time.seconds().to_string()),
+            AuctionEvent::Rescheduled {
+                auction_id,
+                previous_end_time,
+                end_time,
+            } => auction_event("auction_rescheduled", auction_id)
+                .add_attribute("previous_end_time", previous_end_time.seconds().to_string())
+                .add_attribute("end_time", end_time.seconds().to_string()),
+            AuctionEvent::Cancelled { auction, penalty } => {
+                auction_event("auction_cancelled", auction.auction_id)
+                    .add_attribute("seller", auction.owner)
+                    .add_attribute(
+                        "had_bids",
+                        (!auction.high_bidder_amount.is_zero()).to_string(),
+                    )
+                    .add_attribute("penalty", penalty)
+            }
+            AuctionEvent::Invalidated { auction } => {
+                auction_event("auction_invalidated", auction.auction_id)
+                    .add_attribute("seller", auction.owner)
+                    .add_attribute(
+                        "had_bids",
+                        (!auction.high_bidder_amount.is_zero()).to_string(),
+                    )
+            }
+            AuctionEvent::Settled {
+                auction,
+                recipient,
+                reserve_met,
+                keeper,
+                keeper_reward,
+            } => {
+                let winning_bid = if reserve_met {
+                    auction.high_bidder_amount
+                } else {
+                    Uint128::zero()
+                };
+                let event = auction_event("auction_settled", auction.auction_id)
+                    .add_attribute("seller", &auction.owner)
+                    .add_attribute("recipient", recipient)
+                    .add_attribute("winning_bid", winning_bid)
+                    .add_attribute("denom", auction.bid_denom())
+                    .add_attribute("reserve_met", reserve_met.to_string())
+                    .add_attribute("keeper", keeper);
+                match keeper_reward {
+                    Some(reward) => event.add_attribute("keeper_reward", reward.to_string()),
+                    None => event,
+                }
+            }
+            AuctionEvent::SettleSkipped { auction_id, reason } => {
+                auction_event("auction_settle_skipped", auction_id).add_attribute("reason", reason)
+            }
+            AuctionEvent::DeliveryFailed {
+                auction_id,
+                item,
+                recipient,
+                error,
+            } => auction_event("auction_delivery_failed", auction_id)
+                .add_attribute("token_address", item.token_address)
+                .add_attribute("token_id", item.token_id)
+                .add_attribute("recipient", recipient)
+                .add_attribute("error", error),
+            AuctionEvent::Refunded {
+                auction_id,
+                recipient,
+                amount,
+                reason,
+                ibc_origin,
+            } => {
+                let mut event = auction_event("auction_refunded", auction_id)
+                    .add_attribute("recipient", recipient)
+                    .add_attribute("amount", amount.amount)
+                    .add_attribute("denom", amount.denom)
+                    .add_attribute("reason", reason.as_str());
+                if let Some(origin) = ibc_origin {
+                    event = event
+                        .add_attribute("ibc_channel", origin.channel_id)
+                        .add_attribute("ibc_recipient", origin.sender);
+                }
+                event
+            }
+            AuctionEvent::RefundCredited {
+                auction_id,
+                recipient,
+                amount,
+            } => auction_event("auction_refund_credited", auction_id)
+                .add_attribute("recipient", recipient)
+                .add_attribute("amount", amount.amount)
+                .add_attribute("denom", amount.denom),
+        }
+    }
+}
+
+/// Events of bundle lots waiting for their NFTs. Once complete, a bundle opens as an auction
+/// with the bundle ID as its auction ID and emits `auction_created`.
+pub enum BundleEvent {
+    /// `bundle_created`: `bundle_id`, `seller`, `lot_size`.
+    Created { bundle: PendingBundle },
+    /// `bundle_item_escrowed`: `bundle_id`, `token_address`, `token_id`, `escrowed` (items
+    /// escrowed so far), `lot_size`.
+    ItemEscrowed {
+        bundle: PendingBundle,
+        item: BundleItem,
+    },
+    /// `bundle_cancelled`: `bundle_id`, `seller`, `returned` (number of NFTs sent back).
+    Cancelled { bundle: PendingBundle },
+}
+
+impl From<BundleEvent> for Event {
+    fn from(event: BundleEvent) -> Self {
+        match event {
+            BundleEvent::Created { bundle } => bundle_event("bundle_created", &bundle)
+                .add_attribute("seller", &bundle.owner)
+                .add_attribute("lot_size", bundle.items.len().to_string()),
+            BundleEvent::ItemEscrowed { bundle, item } => {
+                bundle_event("bundle_item_escrowed", &bundle)
+                    .add_attribute("token_address", item.token_address)
+                    .add_attribute("token_id", item.token_id)
+                    .add_attribute("escrowed", bundle.escrowed.len().to_string())
+                    .add_attribute("lot_size", bundle.items.len().to_string())
+            }
+            BundleEvent::Cancelled { bundle } => bundle_event("bundle_cancelled", &bundle)
+                .add_attribute("seller", &bundle.owner)
+                .add_attribute("returned", bundle.escrowed.len().to_string()),
+        }
+    }
+}
+
+fn bundle_event(ty: &str, bundle: &PendingBundle) -> Event {
+    Event::new(ty).add_attribute("bundle_id", bundle.bundle_id)
+}
+
+pub enum RaffleEvent {
+    /// `raffle_created`: `raffle_id`, `seller`, `token_address`, `token_id`, `start_time`,
+    /// `end_time`, `denom`, `ticket_price`, `max_tickets`, `randomness_source`
+    /// (`commit_reveal` or `beacon`), and `max_tickets_per_address` when set.
+    Created { raffle: Raffle },
+    /// `raffle_tickets_bought`: `raffle_id`, `buyer`, `tickets`, `tickets_held`,
+    /// `tickets_sold`.
+    TicketsBought {
+        raffle: Raffle,
+        buyer: Addr,
+        tickets: u32,
+        tickets_held: u32,
+    },
+    /// `raffle_drawn`: `raffle_id`, `winner`, `tickets_sold`, and `winning_ticket` when tickets
+    /// were sold. The winner is the seller when none were.
+    Drawn { raffle: Raffle },
+    /// `raffle_refunded`: `raffle_id`, `seller`. The raffle was not drawn in time; ticket holders
+    /// can claim their refunds.
+    Refunded { raffle: Raffle },
+    /// `raffle_refund_claimed`: `raffle_id`, `holder`, `tickets`, `amount`, `denom`.
+    RefundClaimed {
+        raffle_id: Uint128,
+        holder: Addr,
+        tickets: u32,
+        amount: Coin,
+    },
+}
+
+impl From<RaffleEvent> for Event {
+    fn from(event: RaffleEvent) -> Self {
+        match event {
+            RaffleEvent::Created { raffle } => {
+                let source = match raffle.randomness_source {
+                    RandomnessSource::CommitReveal { .. } => "commit_reveal",
+                    RandomnessSource::Beacon {} => "beacon",
+                };
+                let event = raffle_event("raffle_created", raffle.raffle_id)
+                    .add_attribute("seller", raffle.owner)
+                    .add_attribute("token_address", raffle.token_address)
+                    .add_attribute("token_id", raffle.token_id)
+                    .add_attribute("start_time", raffle.start.seconds().to_string())
+                    .add_attribute("end_time", raffle.end.seconds().to_string())
+                    .add_attribute("denom", raffle.denom)
+                    .add_attribute("ticket_price", raffle.ticket_price)
+                    .add_attribute("max_tickets", raffle.max_tickets.to_string())
+                    .add_attribute("randomness_source", source);
+                match raffle.max_tickets_per_address {
+                    Some(cap) => event.add_attribute("max_tickets_per_address", cap.to_string()),
+                    None => event,
+                }
+            }
+            RaffleEvent::TicketsBought {
+                raffle,
+                buyer,
+                tickets,
+                tickets_held,
+            } => raffle_event("raffle_tickets_bought", raffle.raffle_id)
+                .add_attribute("buyer", buyer)
+                .add_attribute("tickets", tickets.to_string())
+                .add_attribute("tickets_held", tickets_held.to_string())
+                .add_attribute("tickets_sold", raffle.tickets_sold.to_string()),
+            RaffleEvent::Drawn { raffle } => {
+                let event = raffle_event("raffle_drawn", raffle.raffle_id)
+                    .add_attribute("tickets_sold", raffle.tickets_sold.to_string());
+                match raffle.draw {
+                    Some(draw) => {
+                        let event = event.add_attribute("winner", draw.winner);
+                        match draw.winning_ticket {
+                            Some(ticket) => {
+                                event.add_attribute("winning_ticket", ticket.to_string())
+                            }
+                            None => event,
+                        }
+                    }
+                    None => event,
+                }
+            }
+            RaffleEvent::Refunded { raffle } => raffle_event("raffle_refunded", raffle.raffle_id)
+                .add_attribute("seller", raffle.owner),
+            RaffleEvent::RefundClaimed {
+                raffle_id,
+                holder,
+                tickets,
+                amount,
+            } => raffle_event("raffle_refund_claimed", raffle_id)
+                .add_attribute("holder", holder)
+                .add_attribute("tickets", tickets.to_string())
+                .add_attribute("amount", amount.amount)
+                .add_attribute("denom", amount.denom),
+        }
+    }
+}
+
+fn raffle_event(ty: &str, raffle_id: Uint128) -> Event {
+    Event::new(ty).add_attribute("raffle_id", raffle_id)
+}
+
+pub enum RentalEvent {
+    /// `rental_listed`: `token_address`, `token_id`, `owner`, `denom`, `price_per_day`,
+    /// `collateral`, `max_days`.
+    Listed { rental: Rental },
+    /// `rental_rented`: `token_address`, `token_id`, `renter`, `fee`, `expires`.
+    Rented { rental: Rental, fee: Uint128 },
+    /// `rental_returned`: `token_address`, `token_id`, `renter`, `returned_by`. The holder
+    /// who sent the NFT back may differ from the renter, who gets the collateral.
+    Returned {
+        rental: Rental,
+        renter: Addr,
+        returned_by: String,
+    },
+    /// `rental_collateral_claimed`: `token_address`, `token_id`, `owner`, `collateral`,
+    /// `denom`. The NFT was not returned in time and the listing is closed.
+    CollateralClaimed { rental: Rental },
+    /// `rental_delisted`: `token_address`, `token_id`, `owner`.
+    Delisted { rental: Rental },
+}
+
+impl From<RentalEvent> for Event {
+    fn from(event: RentalEvent) -> Self {
+        match event {
+            RentalEvent::Listed { rental } => rental_event("rental_listed", &rental)
+                .add_attribute("owner", &rental.owner)
+                .add_attribute("denom", &rental.denom)
+                .add_attribute("price_per_day", rental.price_per_day)
+                .add_attribute("collateral", rental.collateral)
+                .add_attribute("max_days", rental.max_days.to_string()),
+            RentalEvent::Rented { rental, fee } => {
+                let event = rental_event("rental_rented", &rental);
+                let event = match &rental.renter {
+                    Some(renter) => event.add_attribute("renter", renter),
+                    None => event,
+                };
+                let event = event.add_attribute("fee", fee);
+                match rental.expires {
+                    Some(expires) => event.add_attribute("expires", expires.seconds().to_string()),
+                    None => event,
+                }
+            }
+            RentalEvent::Returned {
+                rental,
+                renter,
+                returned_by,
+            } => rental_event("rental_returned", &rental)
+                .add_attribute("renter", renter)
+                .add_attribute("returned_by", returned_by),
+            RentalEvent::CollateralClaimed { rental } => {
+                rental_event("rental_collateral_claimed", &rental)
+                    .add_attribute("owner", &rental.owner)
+                    .add_attribute("collateral", rental.collateral)
+                    .add_attribute("denom", &rental.denom)
+            }
+            RentalEvent::Delisted { rental } => {
+                rental_event("rental_delisted", &rental).add_attribute("owner", &rental.owner)
+            }
+        }
+    }
+}
+
+fn rental_event(ty: &str, rental: &Rental) -> Event {
+    Event::new(ty)
+        .add_attribute("token_address", &rental.token_address)
+        .add_attribute("token_id", &rental.token_id)
+}
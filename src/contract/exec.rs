@@ -1,19 +1,37 @@
 use crate::{
+    contract::cache::StorageCache,
+    contract::events::{AuctionEvent, BundleEvent, RefundReason},
     contract::helper::{
-        fetch_and_update_next_auction_id, fetch_latest_auction_state_for_token, query_token_owner,
+        checked_plus_seconds, delivery_reply_id, fetch_and_update_next_auction_id,
+        fetch_latest_auction_state_for_token, ibc_hook_sender, mint_nft_msg,
+        parse_delivery_reply_id, query_holds_token, query_is_operator, query_minter,
+        query_token_owner, transfer_nft_msg,
     },
+    contract::ibc_refund::ibc_refund_msg,
+    contract::ics721::{query_ics721_class, send_back_msg},
+    contract::raffle::start_raffle,
+    contract::rental::{list_for_rent, return_rental},
     error::ContractError,
-    msg::Cw721CustomMsg,
+    merkle::{self, MerkleProof},
+    msg::{Cw721CustomMsg, StartMode},
     state::{
-        load_auction_details, load_bids, save_auction_details, save_bids, save_nft_auction_state,
-        Bid, NFTAuctionState,
+        decrease_balance, exchange_rate_in_use, increase_balance, load_auction_details,
+        load_bid_escrow, load_bids, load_config, load_exchange_rate, load_nft_auction_state,
+        lock_balance, nft_auction_states, nft_recipient, read_expired_auction_ids, record_sale,
+        release_locked_balance, save_auction_details, save_bids, save_config,
+        save_nft_auction_state, unlock_balance, AuctionTemplate, Bid, BidderAccess, BundleItem,
+        CancelPolicy, ExchangeRate, IbcOrigin, Ics721Class, LazyMint, NFTAuctionState,
+        PendingBundle, BID_ESCROWS, CLAIMABLE_NFTS, EXCHANGE_RATES, IBC_BIDDERS, ICS721_RECEIVERS,
+        ICS721_SENT_BACK, MAX_BPS, MAX_KEEPER_REWARD_BPS, MAX_TEMPLATE_NAME_LENGTH, OPEN_END,
+        OPERATORS, PENDING_BUNDLES, SALES, TEMPLATES,
     },
 };
 use cosmwasm_std::{
-    attr, coins, ensure, from_json, to_json_binary, Addr, BankMsg, Coin, CosmosMsg, DepsMut, Env,
-    MessageInfo, Response, Timestamp, Uint128, WasmMsg,
+    attr, coin, coins, ensure, from_json, Addr, BankMsg, Coin, CosmosMsg, Deps, DepsMut, Env,
+    Event, MessageInfo, Order, Reply, Response, StdResult, Storage, SubMsg, SubMsgResult,
+    Timestamp, Uint128,
 };
-use cw721::{Cw721ExecuteMsg, Cw721ReceiveMsg};
+use cw721::{Cw721ReceiveMsg, Expiration, OwnerOfResponse};
 
 pub fn handle_cw721_auction_start(
     deps: DepsMut,
@@ -24,9 +42,13 @@ pub fn handle_cw721_auction_start(
     match from_json(&msg.msg)? {
         Cw721CustomMsg::InitializeCW721TokenAuction {
             start_time,
+            start_mode,
             duration,
             coin_denomination,
             min_bid,
+            reserve_price,
+            access,
+            accepted_denoms,
         } => initialize_cw721_token_auction(
             deps,
             env,
@@ -34,9 +56,97 @@ pub fn handle_cw721_auction_start(
             msg.token_id,
             info.sender.to_string(),
             start_time,
+            start_mode,
             duration,
             coin_denomination,
             min_bid,
+            reserve_price,
+            access,
+            accepted_denoms.unwrap_or_default(),
+            None,
+            None,
+        ),
+        Cw721CustomMsg::StartFromTemplate {
+            name,
+            start_time,
+            start_mode,
+            overrides,
+        } => {
+            let seller = deps.api.addr_validate(&msg.sender)?;
+            let template = TEMPLATES
+                .may_load(deps.storage, (&seller, &name))?
+                .ok_or(ContractError::TemplateDoesNotExist {})?;
+            let overrides = overrides.unwrap_or_default();
+            initialize_cw721_token_auction(
+                deps,
+                env,
+                msg.sender,
+                msg.token_id,
+                info.sender.to_string(),
+                start_time,
+                start_mode,
+                overrides.duration.unwrap_or(template.duration),
+                overrides
+                    .coin_denomination
+                    .unwrap_or(template.coin_denomination),
+                overrides.min_bid.or(template.min_bid),
+                overrides.reserve_price.or(template.reserve_price),
+                overrides.access.or(template.access),
+                overrides
+                    .accepted_denoms
+                    .or(template.accepted_denoms)
+                    .unwrap_or_default(),
+                overrides.bid_increment.or(template.bid_increment),
+                overrides.extension.or(template.extension),
+            )
+        }
+        Cw721CustomMsg::AddToBundle { bundle_id } => add_to_bundle(
+            deps,
+            &env,
+            msg.sender,
+            msg.token_id,
+            info.sender.to_string(),
+            bundle_id,
+        ),
+        Cw721CustomMsg::ListForRent {
+            denom,
+            price_per_day,
+            collateral,
+            max_days,
+        } => list_for_rent(
+            deps,
+            msg.sender,
+            msg.token_id,
+            info.sender.to_string(),
+            denom,
+            price_per_day,
+            collateral,
+            max_days,
+        ),
+        Cw721CustomMsg::ReturnRental {} => {
+            return_rental(deps, env, msg.sender, msg.token_id, info.sender.to_string())
+        }
+        Cw721CustomMsg::StartRaffle {
+            start_time,
+            duration,
+            denom,
+            ticket_price,
+            max_tickets,
+            max_tickets_per_address,
+            randomness_source,
+        } => start_raffle(
+            deps,
+            env,
+            msg.sender,
+            msg.token_id,
+            info.sender.to_string(),
+            start_time,
+            duration,
+            denom,
+            ticket_price,
+            max_tickets,
+            max_tickets_per_address,
+            randomness_source,
         ),
     }
 }
@@ -49,70 +159,789 @@ fn initialize_cw721_token_auction(
     token_id: String,
     token_address: String,
     start_time: u64,
+    start_mode: Option<StartMode>,
+    duration: u64,
+    coin_denomination: String,
+    min_bid: Option<Uint128>,
+    reserve_price: Option<Uint128>,
+    access: Option<BidderAccess>,
+    accepted_denoms: Vec<String>,
+    bid_increment: Option<Uint128>,
+    extension: Option<u64>,
+) -> Result<Response, ContractError> {
+    let (start_timestamp, end_timestamp, timer_duration) =
+        resolve_auction_time(&env, start_time, start_mode, duration)?;
+    if let Some(access) = &access {
+        validate_bidder_access(deps.as_ref(), access)?;
+    }
+    validate_bid_increment(bid_increment)?;
+    validate_accepted_denoms(deps.storage, &coin_denomination, &accepted_denoms)?;
+    let owner = deps.api.addr_validate(&sender)?;
+    let ics721_class = query_ics721_class(deps.as_ref(), &token_address)?;
+    let (keeper_fee, listing_deposit, keeper_reward_bps) =
+        escrow_seller_fees(deps.storage, &owner)?;
+    let auction_id = fetch_and_update_next_auction_id(deps.storage)?;
+
+    open_auction(
+        deps,
+        auction_id,
+        owner,
+        keeper_fee,
+        listing_deposit,
+        keeper_reward_bps,
+        vec![BundleItem {
+            token_address,
+            token_id,
+        }],
+        start_timestamp,
+        end_timestamp,
+        coin_denomination,
+        min_bid,
+        reserve_price,
+        access,
+        accepted_denoms,
+        None,
+        false,
+        ics721_class,
+        timer_duration,
+        bid_increment,
+        extension,
+    )
+}
+
+/// Opens an auction for a token that does not exist yet. Only the winner gets it minted, so
+/// nothing is escrowed and an unsold auction leaves the token unminted.
+#[allow(clippy::too_many_arguments)]
+pub fn create_lazy_auction(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_address: String,
+    token_id: String,
+    token_uri: Option<String>,
+    start_time: u64,
+    duration: u64,
+    coin_denomination: String,
+    min_bid: Option<Uint128>,
+    reserve_price: Option<Uint128>,
+    accepted_denoms: Option<Vec<String>>,
+) -> Result<Response, ContractError> {
+    let (start_timestamp, end_timestamp) = validate_auction_time(&env, start_time, duration)?;
+    let accepted_denoms = accepted_denoms.unwrap_or_default();
+    validate_accepted_denoms(deps.storage, &coin_denomination, &accepted_denoms)?;
+    let token_address = deps.api.addr_validate(&token_address)?.to_string();
+    let collection = deps.querier.query_wasm_contract_info(&token_address)?;
+    ensure!(
+        info.sender == collection.creator
+            || collection.admin.is_some_and(|admin| info.sender == admin),
+        ContractError::Unauthorized {}
+    );
+    ensure!(
+        query_minter(deps.querier, token_address.clone())?
+            .is_some_and(|minter| minter == env.contract.address),
+        ContractError::InvalidLazyMint {
+            msg: "The marketplace is not the minter of the collection".to_string(),
+        }
+    );
+    ensure!(
+        query_token_owner(deps.querier, token_address.clone(), token_id.clone()).is_err(),
+        ContractError::InvalidLazyMint {
+            msg: "Token has already been minted".to_string(),
+        }
+    );
+    ensure!(
+        fetch_latest_auction_state_for_token(deps.storage, &token_id, &token_address)
+            .map_or(true, |auction| !auction.is_open()),
+        ContractError::InvalidLazyMint {
+            msg: "Token is already being auctioned".to_string(),
+        }
+    );
+    let (keeper_fee, listing_deposit, keeper_reward_bps) =
+        escrow_seller_fees(deps.storage, &info.sender)?;
+    let auction_id = fetch_and_update_next_auction_id(deps.storage)?;
+
+    open_auction(
+        deps,
+        auction_id,
+        info.sender,
+        keeper_fee,
+        listing_deposit,
+        keeper_reward_bps,
+        vec![BundleItem {
+            token_address,
+            token_id,
+        }],
+        start_timestamp,
+        end_timestamp,
+        coin_denomination,
+        min_bid,
+        reserve_price,
+        None,
+        accepted_denoms,
+        Some(LazyMint { token_uri }),
+        false,
+        None,
+        None,
+        None,
+        None,
+    )
+}
+
+/// Opens an auction for an NFT that stays in the seller's wallet. The seller must have approved
+/// the marketplace for the token, which moves it to the winner on settlement.
+#[allow(clippy::too_many_arguments)]
+pub fn create_approved_auction(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_address: String,
+    token_id: String,
+    start_time: u64,
     duration: u64,
     coin_denomination: String,
     min_bid: Option<Uint128>,
+    reserve_price: Option<Uint128>,
+    access: Option<BidderAccess>,
+    accepted_denoms: Option<Vec<String>>,
 ) -> Result<Response, ContractError> {
+    let (start_timestamp, end_timestamp) = validate_auction_time(&env, start_time, duration)?;
+    if let Some(access) = &access {
+        validate_bidder_access(deps.as_ref(), access)?;
+    }
+    let accepted_denoms = accepted_denoms.unwrap_or_default();
+    validate_accepted_denoms(deps.storage, &coin_denomination, &accepted_denoms)?;
+    let token_address = deps.api.addr_validate(&token_address)?.to_string();
+    let token = query_token_owner(deps.querier, token_address.clone(), token_id.clone())?;
+    ensure!(token.owner == info.sender, ContractError::Unauthorized {});
+    ensure!(
+        marketplace_is_approved(deps.as_ref(), &env, &token_address, &token)?,
+        ContractError::NftNotApproved {}
+    );
+    ensure!(
+        fetch_latest_auction_state_for_token(deps.storage, &token_id, &token_address)
+            .map_or(true, |auction| !auction.is_open()),
+        ContractError::AuctionAlreadyOpen {}
+    );
+    let (keeper_fee, listing_deposit, keeper_reward_bps) =
+        escrow_seller_fees(deps.storage, &info.sender)?;
+    let auction_id = fetch_and_update_next_auction_id(deps.storage)?;
+
+    open_auction(
+        deps,
+        auction_id,
+        info.sender,
+        keeper_fee,
+        listing_deposit,
+        keeper_reward_bps,
+        vec![BundleItem {
+            token_address,
+            token_id,
+        }],
+        start_timestamp,
+        end_timestamp,
+        coin_denomination,
+        min_bid,
+        reserve_price,
+        access,
+        accepted_denoms,
+        None,
+        true,
+        None,
+        None,
+        None,
+        None,
+    )
+}
+
+/// Whether the marketplace may transfer `token` out of its owner's wallet, either through an
+/// approval for the token or as an operator of all the owner's tokens (`ApproveAll`).
+fn marketplace_is_approved(
+    deps: Deps,
+    env: &Env,
+    token_address: &str,
+    token: &OwnerOfResponse,
+) -> Result<bool, ContractError> {
+    if token
+        .approvals
+        .iter()
+        .any(|approval| approval.spender == env.contract.address)
+    {
+        return Ok(true);
+    }
+    query_is_operator(
+        deps.querier,
+        token_address.to_owned(),
+        token.owner.clone(),
+        env.contract.address.to_string(),
+    )
+}
+
+/// Whether the seller of an approval-based auction still owns its NFT and the marketplace is
+/// still approved to transfer it.
+fn seller_still_holds_nft(deps: Deps, env: &Env, auction: &NFTAuctionState) -> bool {
+    query_token_owner(
+        deps.querier,
+        auction.token_address.clone(),
+        auction.token_id.clone(),
+    )
+    .is_ok_and(|token| {
+        token.owner == auction.owner
+            && marketplace_is_approved(deps, env, &auction.token_address, &token).unwrap_or(false)
+    })
+}
+
+/// Whether the marketplace is still the minter of a lazy auction's collection.
+fn marketplace_still_mints(deps: Deps, env: &Env, auction: &NFTAuctionState) -> bool {
+    query_minter(deps.querier, auction.token_address.clone())
+        .is_ok_and(|minter| minter.is_some_and(|minter| minter == env.contract.address))
+}
+
+/// Ends an approval-based or lazy auction whose NFT can no longer be delivered, refunding the
+/// high bid. As on a cancel, the
+/// listing deposit is slashed if there were bids and the other seller fees are returned.
+fn invalidate_auction(
+    storage: &mut dyn Storage,
+    env: &Env,
+    mut auction: NFTAuctionState,
+) -> Result<(Vec<SubMsg>, Vec<Event>), ContractError> {
+    let config = load_config(storage)?;
+    let owner = Addr::unchecked(auction.owner.clone());
+    let has_bids = !auction.high_bidder_amount.is_zero();
+    let mut messages: Vec<SubMsg> = vec![];
+    let mut events: Vec<Event> = vec![];
+    let mut listing_deposit = auction.listing_deposit.clone();
+    if has_bids {
+        let escrow = load_bid_escrow(storage, &auction)?;
+        BID_ESCROWS.remove(storage, auction.auction_id.u128());
+        let (refund_msg, refund_event) = refund_bid(
+            storage,
+            env,
+            auction.auction_id,
+            &auction.high_bidder_addr,
+            coin(escrow.u128(), auction.bid_denom()),
+            RefundReason::Invalidated,
+        )?;
+        messages.push(refund_msg);
+        events.push(refund_event);
+        if let Some(deposit) = listing_deposit.take() {
+            release_locked_balance(storage, &owner, &deposit)?;
+            messages.push(SubMsg::new(BankMsg::Send {
+                to_address: config.owner.to_string(),
+                amount: vec![deposit],
+            }));
+        }
+    }
+    refund_seller_fees(storage, &owner, &auction.keeper_fee, &listing_deposit)?;
+
+    auction.is_cancelled = true;
+    save_nft_auction_state(storage, auction.auction_id.u128(), auction.clone())?;
+    events.insert(0, AuctionEvent::Invalidated { auction }.into());
+    Ok((messages, events))
+}
+
+/// Checks that the extra denoms of a multi-denom auction are distinct and that every denom it
+/// accepts has an exchange rate.
+fn validate_accepted_denoms(
+    storage: &dyn Storage,
+    coin_denomination: &str,
+    accepted_denoms: &[String],
+) -> Result<(), ContractError> {
+    if accepted_denoms.is_empty() {
+        return Ok(());
+    }
+    for (i, denom) in accepted_denoms.iter().enumerate() {
+        ensure!(
+            denom != coin_denomination && !accepted_denoms[..i].contains(denom),
+            ContractError::InvalidAcceptedDenoms {
+                msg: format!("Duplicate denom {denom}"),
+            }
+        );
+        load_exchange_rate(storage, denom)?;
+    }
+    load_exchange_rate(storage, coin_denomination)?;
+    Ok(())
+}
+
+/// Checks that an auction's own bid increment, when set, is not zero.
+fn validate_bid_increment(bid_increment: Option<Uint128>) -> Result<(), ContractError> {
+    ensure!(
+        bid_increment.is_none_or(|bid_increment| !bid_increment.is_zero()),
+        ContractError::InvalidBidIncrement {}
+    );
+    Ok(())
+}
+
+/// Whether the high bid of an auction is worth at least its reserve price.
+fn reserve_met(storage: &dyn Storage, auction: &NFTAuctionState) -> Result<bool, ContractError> {
+    match auction.reserve_price {
+        None => Ok(true),
+        Some(reserve_price) => {
+            Ok(bid_value(storage, auction, &auction.high_bid())? >= reserve_price)
+        }
+    }
+}
+
+/// Values `bid` in the auction's `coin_denomination`, rounding down.
+fn bid_value(
+    storage: &dyn Storage,
+    auction: &NFTAuctionState,
+    bid: &Coin,
+) -> Result<Uint128, ContractError> {
+    if bid.denom == auction.coin_denomination {
+        return Ok(bid.amount);
+    }
+    let rate = load_exchange_rate(storage, &bid.denom)?;
+    let quote_rate = load_exchange_rate(storage, &auction.coin_denomination)?;
+    Ok(bid
+        .amount
+        .multiply_ratio(rate.atomics(), quote_rate.atomics()))
+}
+
+fn validate_bidder_access(deps: Deps, access: &BidderAccess) -> Result<(), ContractError> {
+    match access {
+        BidderAccess::Allowlist { addresses } => {
+            for address in addresses {
+                deps.api.addr_validate(address.as_str())?;
+            }
+        }
+        BidderAccess::MerkleRoot { root } => {
+            merkle::parse_root(root)?;
+        }
+        BidderAccess::HoldsToken { collection } => {
+            deps.api.addr_validate(collection)?;
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the bidder satisfies the auction's access rule, if it has one, and that `amount`
+/// stays within the cap committed in their Merkle leaf.
+fn ensure_bidder_allowed(
+    deps: Deps,
+    access: &Option<BidderAccess>,
+    bidder: &Addr,
+    amount: Uint128,
+    proof: Option<MerkleProof>,
+) -> Result<(), ContractError> {
+    let allowed = match access {
+        None => true,
+        Some(BidderAccess::Allowlist { addresses }) => addresses.contains(bidder),
+        Some(BidderAccess::MerkleRoot { root }) => {
+            let proof = proof.ok_or(ContractError::BidderNotAllowed {})?;
+            ensure!(
+                proof
+                    .max_amount
+                    .is_none_or(|max_amount| amount <= max_amount),
+                ContractError::BidExceedsAllowance {}
+            );
+            merkle::verify(root, bidder.as_str(), &proof)?
+        }
+        Some(BidderAccess::HoldsToken { collection }) => {
+            query_holds_token(deps.querier, collection.to_owned(), bidder.to_string())?
+        }
+    };
+    ensure!(allowed, ContractError::BidderNotAllowed {});
+    Ok(())
+}
+
+pub fn validate_auction_time(
+    env: &Env,
+    start_time: u64,
+    duration: u64,
+) -> Result<(Timestamp, Timestamp), ContractError> {
     ensure!(
         start_time > 0 && duration > 0,
         ContractError::InValidTime {}
     );
-    let end_timestamp = Timestamp::from_seconds(start_time + duration);
-    let start_timestamp = Timestamp::from_seconds(start_time);
+    let start_timestamp = checked_plus_seconds(Timestamp::from_nanos(0), start_time)?;
+    let end_timestamp = checked_plus_seconds(start_timestamp, duration)?;
 
     ensure!(
         start_timestamp.gt(&env.block.time),
         ContractError::InvalidStartTime {}
     );
+    Ok((start_timestamp, end_timestamp))
+}
 
-    let auction_id = fetch_and_update_next_auction_id(deps.storage)?;
-    let pk = token_id.to_owned() + &token_address;
+/// Works out the start and end of an auction from an absolute `start_time` or a `start_mode`,
+/// along with the countdown of an auction that starts on its first bid.
+fn resolve_auction_time(
+    env: &Env,
+    start_time: u64,
+    start_mode: Option<StartMode>,
+    duration: u64,
+) -> Result<(Timestamp, Timestamp, Option<u64>), ContractError> {
+    let Some(start_mode) = start_mode else {
+        let (start, end) = validate_auction_time(env, start_time, duration)?;
+        return Ok((start, end, None));
+    };
+    ensure!(start_time == 0, ContractError::InvalidStartTime {});
+    ensure!(duration > 0, ContractError::InValidTime {});
+    let now = env.block.time;
+    Ok(match start_mode {
+        StartMode::Now {} => (now, checked_plus_seconds(now, duration)?, None),
+        StartMode::After { seconds } => {
+            let start = checked_plus_seconds(now, seconds)?;
+            (start, checked_plus_seconds(start, duration)?, None)
+        }
+        StartMode::OnFirstBid {} => {
+            // Rejects durations the timer could never be started with.
+            checked_plus_seconds(now, duration)?;
+            (now, OPEN_END, Some(duration))
+        }
+    })
+}
+
+/// Escrows the configured keeper fee and listing deposit from the seller's balance, and returns
+/// them with the keeper reward rate so the auction keeps the terms it was created under.
+fn escrow_seller_fees(
+    storage: &mut dyn Storage,
+    owner: &Addr,
+) -> Result<(Option<Coin>, Option<Coin>, u64), ContractError> {
+    let config = load_config(storage)?;
+    for fee in [&config.keeper_fee, &config.listing_deposit]
+        .into_iter()
+        .flatten()
+    {
+        lock_balance(storage, owner, fee)?;
+    }
+    Ok((
+        config.keeper_fee,
+        config.listing_deposit,
+        config.keeper_reward_bps,
+    ))
+}
 
-    let mut auction_info = load_auction_details(deps.storage, &pk).unwrap_or_default();
-    auction_info.push(auction_id);
-    if auction_info.token_address.is_empty() {
-        auction_info.token_address = token_address.to_owned();
-        auction_info.token_id = token_id.to_owned();
+/// Returns the escrowed keeper fee and listing deposit to the seller's balance.
+fn refund_seller_fees(
+    storage: &mut dyn Storage,
+    owner: &Addr,
+    keeper_fee: &Option<Coin>,
+    listing_deposit: &Option<Coin>,
+) -> Result<(), ContractError> {
+    for fee in [keeper_fee, listing_deposit].into_iter().flatten() {
+        unlock_balance(storage, owner, fee)?;
     }
+    Ok(())
+}
 
-    save_auction_details(deps.storage, pk, auction_info)?;
+/// Saves the state of a new auction for NFTs that are already escrowed by the contract. The
+/// first item is the lot's primary token; every item is indexed so the auction can be found
+/// through any of them.
+#[allow(clippy::too_many_arguments)]
+fn open_auction(
+    deps: DepsMut,
+    auction_id: Uint128,
+    owner: Addr,
+    keeper_fee: Option<Coin>,
+    listing_deposit: Option<Coin>,
+    keeper_reward_bps: u64,
+    mut items: Vec<BundleItem>,
+    start_timestamp: Timestamp,
+    end_timestamp: Timestamp,
+    coin_denomination: String,
+    min_bid: Option<Uint128>,
+    reserve_price: Option<Uint128>,
+    access: Option<BidderAccess>,
+    accepted_denoms: Vec<String>,
+    lazy_mint: Option<LazyMint>,
+    held_by_seller: bool,
+    ics721_class: Option<Ics721Class>,
+    timer_duration: Option<u64>,
+    bid_increment: Option<Uint128>,
+    extension: Option<u64>,
+) -> Result<Response, ContractError> {
+    for item in items.iter() {
+        let pk = item.token_id.to_owned() + &item.token_address;
+        let mut auction_info = load_auction_details(deps.storage, &pk).unwrap_or_default();
+        auction_info.push(auction_id);
+        if auction_info.token_address.is_empty() {
+            auction_info.token_address = item.token_address.to_owned();
+            auction_info.token_id = item.token_id.to_owned();
+        }
+        save_auction_details(deps.storage, pk, auction_info)?;
+    }
     save_bids(deps.storage, auction_id.u128(), vec![])?;
 
-    save_nft_auction_state(
+    let primary = items.remove(0);
+    let auction = NFTAuctionState {
+        start: start_timestamp,
+        end: end_timestamp,
+        high_bidder_addr: Addr::unchecked(""),
+        high_bidder_amount: Uint128::zero(),
+        coin_denomination: coin_denomination.clone(),
+        auction_id,
+        min_bid,
+        reserve_price,
+        owner: owner.to_string(),
+        token_id: primary.token_id,
+        token_address: primary.token_address,
+        is_cancelled: false,
+        is_settled: false,
+        keeper_fee,
+        listing_deposit,
+        keeper_reward_bps,
+        access,
+        accepted_denoms,
+        high_bid_denom: None,
+        bundle: items,
+        lazy_mint,
+        held_by_seller,
+        ics721_class,
+        timer_duration,
+        bid_increment,
+        // A zero extension never extends the auction.
+        extension: extension.filter(|extension| *extension > 0),
+    };
+    save_nft_auction_state(deps.storage, auction_id.u128(), auction.clone())?;
+    Ok(Response::new()
+        .add_attribute("action", "start_auction")
+        .add_event(AuctionEvent::Created { auction }.into()))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_bundle_auction(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    items: Vec<BundleItem>,
+    start_time: u64,
+    duration: u64,
+    coin_denomination: String,
+    min_bid: Option<Uint128>,
+    reserve_price: Option<Uint128>,
+    accepted_denoms: Option<Vec<String>>,
+) -> Result<Response, ContractError> {
+    validate_auction_time(&env, start_time, duration)?;
+    let accepted_denoms = accepted_denoms.unwrap_or_default();
+    validate_accepted_denoms(deps.storage, &coin_denomination, &accepted_denoms)?;
+    ensure!(
+        items.len() > 1,
+        ContractError::InvalidBundle {
+            msg: "A bundle needs at least two tokens".to_string(),
+        }
+    );
+    for (i, item) in items.iter().enumerate() {
+        deps.api.addr_validate(&item.token_address)?;
+        ensure!(
+            !items[..i].contains(item),
+            ContractError::InvalidBundle {
+                msg: format!("Duplicate token {}", item.token_id),
+            }
+        );
+    }
+
+    let (keeper_fee, listing_deposit, keeper_reward_bps) =
+        escrow_seller_fees(deps.storage, &info.sender)?;
+    let bundle_id = fetch_and_update_next_auction_id(deps.storage)?;
+    let bundle = PendingBundle {
+        bundle_id,
+        owner: info.sender,
+        keeper_fee,
+        listing_deposit,
+        keeper_reward_bps,
+        items,
+        escrowed: vec![],
+        start_time,
+        duration,
+        coin_denomination,
+        min_bid,
+        reserve_price,
+        accepted_denoms,
+    };
+    PENDING_BUNDLES.save(deps.storage, bundle_id.u128(), &bundle)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_bundle")
+        .add_event(BundleEvent::Created { bundle }.into()))
+}
+
+/// Records an NFT sent into a pending bundle through `Cw721ReceiveMsg`.
+fn add_to_bundle(
+    deps: DepsMut,
+    env: &Env,
+    sender: String,
+    token_id: String,
+    token_address: String,
+    bundle_id: Uint128,
+) -> Result<Response, ContractError> {
+    let mut bundle = PENDING_BUNDLES
+        .may_load(deps.storage, bundle_id.u128())?
+        .ok_or(ContractError::BundleDoesNotExist {})?;
+    ensure!(bundle.owner == sender, ContractError::Unauthorized {});
+
+    let item = BundleItem {
+        token_address,
+        token_id,
+    };
+    ensure!(
+        bundle.items.contains(&item) && !bundle.escrowed.contains(&item),
+        ContractError::TokenNotInBundle {}
+    );
+    bundle.escrowed.push(item.clone());
+
+    let response = Response::new()
+        .add_attribute("action", "add_to_bundle")
+        .add_event(
+            BundleEvent::ItemEscrowed {
+                bundle: bundle.clone(),
+                item,
+            }
+            .into(),
+        );
+    open_bundle_if_complete(deps, env, bundle, response)
+}
+
+/// Pulls every NFT of a pending bundle the marketplace has been approved for out of the owner's
+/// wallet.
+pub fn escrow_bundle_items(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    bundle_id: Uint128,
+) -> Result<Response, ContractError> {
+    let mut bundle = PENDING_BUNDLES
+        .may_load(deps.storage, bundle_id.u128())?
+        .ok_or(ContractError::BundleDoesNotExist {})?;
+    ensure!(bundle.owner == info.sender, ContractError::Unauthorized {});
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut events: Vec<Event> = vec![];
+    for item in bundle.items.clone() {
+        if bundle.escrowed.contains(&item) {
+            continue;
+        }
+        let token_owner = query_token_owner(
+            deps.querier,
+            item.token_address.clone(),
+            item.token_id.clone(),
+        )?
+        .owner;
+        ensure!(token_owner == bundle.owner, ContractError::Unauthorized {});
+        messages.push(transfer_nft_msg(
+            item.token_address.clone(),
+            item.token_id.clone(),
+            env.contract.address.to_string(),
+        )?);
+        bundle.escrowed.push(item.clone());
+        events.push(
+            BundleEvent::ItemEscrowed {
+                bundle: bundle.clone(),
+                item,
+            }
+            .into(),
+        );
+    }
+
+    let response = Response::new()
+        .add_attribute("action", "escrow_bundle_items")
+        .add_messages(messages)
+        .add_events(events);
+    open_bundle_if_complete(deps, &env, bundle, response)
+}
+
+/// Opens the auction of a bundle once its last item is escrowed. If that happens after the
+/// bundle's start time, the auction starts now and still runs for the full duration.
+fn open_bundle_if_complete(
+    deps: DepsMut,
+    env: &Env,
+    bundle: PendingBundle,
+    response: Response,
+) -> Result<Response, ContractError> {
+    if !bundle.is_complete() {
+        PENDING_BUNDLES.save(deps.storage, bundle.bundle_id.u128(), &bundle)?;
+        return Ok(response);
+    }
+
+    PENDING_BUNDLES.remove(deps.storage, bundle.bundle_id.u128());
+    let start_timestamp =
+        checked_plus_seconds(Timestamp::from_nanos(0), bundle.start_time)?.max(env.block.time);
+    let end_timestamp = checked_plus_seconds(start_timestamp, bundle.duration)?;
+    let opened = open_auction(
+        deps,
+        bundle.bundle_id,
+        bundle.owner,
+        bundle.keeper_fee,
+        bundle.listing_deposit,
+        bundle.keeper_reward_bps,
+        bundle.items,
+        start_timestamp,
+        end_timestamp,
+        bundle.coin_denomination,
+        bundle.min_bid,
+        bundle.reserve_price,
+        None,
+        bundle.accepted_denoms,
+        None,
+        false,
+        None,
+        None,
+        None,
+        None,
+    )?;
+    Ok(response.add_events(opened.events))
+}
+
+/// Abandons a bundle that has not opened yet, returning the NFTs escrowed so far.
+pub fn cancel_bundle(
+    deps: DepsMut,
+    info: MessageInfo,
+    bundle_id: Uint128,
+) -> Result<Response, ContractError> {
+    let bundle = PENDING_BUNDLES
+        .may_load(deps.storage, bundle_id.u128())?
+        .ok_or(ContractError::BundleDoesNotExist {})?;
+    ensure!(bundle.owner == info.sender, ContractError::Unauthorized {});
+    PENDING_BUNDLES.remove(deps.storage, bundle_id.u128());
+    refund_seller_fees(
         deps.storage,
-        auction_id.u128(),
-        NFTAuctionState {
-            start: start_timestamp,
-            end: end_timestamp,
-            high_bidder_addr: Addr::unchecked(""),
-            high_bidder_amount: Uint128::zero(),
-            coin_denomination: coin_denomination.clone(),
-            auction_id,
-            min_bid,
-            owner: sender,
-            token_id,
-            token_address,
-            is_cancelled: false,
-        },
+        &info.sender,
+        &bundle.keeper_fee,
+        &bundle.listing_deposit,
     )?;
-    Ok(Response::new().add_attributes(vec![
-        attr("action", "start_auction"),
-        attr("start_time", start_timestamp.to_string()),
-        attr("end_time", end_timestamp.to_string()),
-        attr("coin_denomination", coin_denomination),
-        attr("auction_id", auction_id.to_string()),
-    ]))
+
+    let messages = bundle
+        .escrowed
+        .iter()
+        .map(|item| {
+            transfer_nft_msg(
+                item.token_address.clone(),
+                item.token_id.clone(),
+                info.sender.to_string(),
+            )
+        })
+        .collect::<StdResult<Vec<CosmosMsg>>>()?;
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_bundle")
+        .add_messages(messages)
+        .add_event(BundleEvent::Cancelled { bundle }.into()))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn submit_bid_for_auction(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     token_id: String,
     token_address: String,
+    proof: Option<MerkleProof>,
+    max_amount: Option<Uint128>,
+    ibc_origin: Option<IbcOrigin>,
 ) -> Result<Response, ContractError> {
     let mut token_auction_state =
         fetch_latest_auction_state_for_token(deps.storage, &token_id, &token_address)?;
+    if let Some(origin) = &ibc_origin {
+        // ibc-hooks runs the memo from an address derived from the origin channel and sender
+        // under this chain's bech32 prefix, so a mismatching origin cannot be claimed.
+        let (bech32_prefix, _, _) =
+            bech32::decode(info.sender.as_str()).map_err(|_| ContractError::InvalidIbcOrigin {})?;
+        ensure!(
+            ibc_hook_sender(origin, &bech32_prefix)? == info.sender,
+            ContractError::InvalidIbcOrigin {}
+        );
+        deps.api.addr_validate(&origin.nft_recipient)?;
+    }
 
     ensure!(
         !token_auction_state.is_cancelled,
@@ -120,11 +949,11 @@ pub fn submit_bid_for_auction(
     );
 
     ensure!(
-        token_auction_state.start.gt(&env.block.time),
+        token_auction_state.start.le(&env.block.time),
         ContractError::AuctionNotStarted {}
     );
     ensure!(
-        !token_auction_state.end.ge(&env.block.time),
+        token_auction_state.end.gt(&env.block.time),
         ContractError::AuctionEnded {}
     );
 
@@ -132,6 +961,15 @@ pub fn submit_bid_for_auction(
         token_auction_state.owner != info.sender,
         ContractError::TokenOwnerCannotBid {}
     );
+    if let Some(origin) = &ibc_origin {
+        IBC_BIDDERS.save(deps.storage, &info.sender, origin)?;
+    }
+    // Such an auction can be invalidated by finalizing it without waiting for its end.
+    ensure!(
+        !token_auction_state.held_by_seller
+            || seller_still_holds_nft(deps.as_ref(), &env, &token_auction_state),
+        ContractError::SellerNoLongerHoldsNft {}
+    );
 
     ensure!(
         info.funds.len() == 1,
@@ -145,55 +983,326 @@ pub fn submit_bid_for_auction(
         ContractError::HighestBidderCannotBeOutbid {}
     );
 
-    let coin_denomination = token_auction_state.coin_denomination.clone();
     let payment: &Coin = &info.funds[0];
+    let payment_denoms = token_auction_state.payment_denoms();
     ensure!(
-        payment.denom == coin_denomination && payment.amount > Uint128::zero(),
+        payment_denoms.contains(&payment.denom) && payment.amount > Uint128::zero(),
         ContractError::InvalidFunds {
             msg: format!(
                 "No {} assets are provided for the auction",
-                coin_denomination
+                payment_denoms.join(" or ")
             ),
         }
     );
     ensure!(
-        token_auction_state.high_bidder_amount < payment.amount,
+        max_amount.is_none_or(|max_amount| max_amount == payment.amount),
+        ContractError::InvalidFunds {
+            msg: "Proxy bids must escrow exactly their max_amount".to_string(),
+        }
+    );
+    ensure!(
+        max_amount.is_none() || token_auction_state.accepted_denoms.is_empty(),
+        ContractError::ProxyBidNotSupported {}
+    );
+    let config = load_config(deps.storage)?;
+    let bid_increment = token_auction_state
+        .bid_increment
+        .unwrap_or(config.bid_increment);
+    let has_bids = !token_auction_state.high_bidder_amount.is_zero();
+    // Bids are compared by their value in `coin_denomination`, which is the amount itself on
+    // single-denom auctions.
+    let payment_value = bid_value(deps.storage, &token_auction_state, payment)?;
+    let high_value = bid_value(
+        deps.storage,
+        &token_auction_state,
+        &token_auction_state.high_bid(),
+    )?;
+    ensure!(
+        !has_bids || payment_value >= high_value.checked_add(bid_increment)?,
         ContractError::BidSmallerThanHighestBid {}
     );
-
-    let mut messages: Vec<CosmosMsg> = vec![];
-    // Send back previous bid unless there was no previous bid.
-    if token_auction_state.high_bidder_amount > Uint128::zero() {
-        let bank_msg = BankMsg::Send {
-            to_address: token_auction_state.high_bidder_addr.to_string(),
-            amount: coins(
-                token_auction_state.high_bidder_amount.u128(),
-                token_auction_state.coin_denomination.clone(),
-            ),
-        };
-        messages.push(CosmosMsg::Bank(bank_msg));
-    }
-
-    token_auction_state.high_bidder_addr = info.sender.clone();
-    token_auction_state.high_bidder_amount = payment.amount;
+    ensure!(
+        token_auction_state
+            .min_bid
+            .is_none_or(|min_bid| payment_value >= min_bid),
+        ContractError::BidSmallerThanMinimumBid {}
+    );
+    // Until a bid meets the reserve, an auction that starts on its first bid has no end to settle
+    // at, so a lower bid could never be refunded.
+    ensure!(
+        token_auction_state.timer_duration.is_none()
+            || token_auction_state
+                .reserve_price
+                .is_none_or(|reserve_price| payment_value >= reserve_price),
+        ContractError::BidSmallerThanReservePrice {}
+    );
+    ensure_bidder_allowed(
+        deps.as_ref(),
+        &token_auction_state.access,
+        &info.sender,
+        payment_value,
+        proof,
+    )?;
 
     let key = token_auction_state.auction_id.u128();
-    save_nft_auction_state(deps.storage, key.clone(), token_auction_state)?;
-    let mut bids_for_auction = load_bids(deps.storage, key.clone())?;
+    let leader_max = Coin {
+        denom: token_auction_state.bid_denom().to_owned(),
+        amount: load_bid_escrow(deps.storage, &token_auction_state)?,
+    };
+    let leader_max_value = bid_value(deps.storage, &token_auction_state, &leader_max)?;
+    let leader_max = leader_max.amount;
+    let mut bids_for_auction = load_bids(deps.storage, key)?;
+    let refund: Option<(Addr, Coin, RefundReason)>;
+    let mut visible_bid = if !has_bids || payment_value > leader_max_value {
+        // The bidder takes the lead and the previous high bidder's escrow is returned.
+        refund = has_bids.then(|| {
+            (
+                token_auction_state.high_bidder_addr.clone(),
+                coin(leader_max.u128(), token_auction_state.bid_denom()),
+                RefundReason::Outbid,
+            )
+        });
+        token_auction_state.high_bidder_addr = info.sender.clone();
+        token_auction_state.high_bid_denom =
+            (payment.denom != token_auction_state.coin_denomination).then(|| payment.denom.clone());
+        BID_ESCROWS.save(deps.storage, key, &payment.amount)?;
+        match max_amount {
+            None => payment.amount,
+            Some(_) if has_bids => payment.amount.min(leader_max.checked_add(bid_increment)?),
+            Some(_) => payment
+                .amount
+                .min(token_auction_state.min_bid.unwrap_or(bid_increment)),
+        }
+    } else {
+        // The high bidder's maximum covers this bid, so their proxy raises to beat it and the
+        // bidder is refunded straight away.
+        refund = Some((
+            info.sender.clone(),
+            payment.clone(),
+            RefundReason::ProxyOutbid,
+        ));
+        bids_for_auction.push(Bid {
+            bidder: info.sender.to_string(),
+            amount: payment.amount,
+            timestamp: env.block.time,
+        });
+        leader_max.min(payment.amount.checked_add(bid_increment)?)
+    };
+    // Proxy bids meet the reserve price as soon as the high bidder's maximum allows it.
+    if let Some(reserve_price) = token_auction_state.reserve_price {
+        let escrow = load_bid_escrow(deps.storage, &token_auction_state)?;
+        if visible_bid < reserve_price && escrow >= reserve_price {
+            visible_bid = reserve_price;
+        }
+    }
+    token_auction_state.high_bidder_amount = visible_bid;
     bids_for_auction.push(Bid {
-        bidder: info.sender.to_string(),
-        amount: payment.amount,
+        bidder: token_auction_state.high_bidder_addr.to_string(),
+        amount: visible_bid,
         timestamp: env.block.time,
     });
+    let mut end_moved = None;
+    if let Some(duration) = token_auction_state.timer_duration {
+        if reserve_met(deps.storage, &token_auction_state)? {
+            token_auction_state.end = checked_plus_seconds(env.block.time, duration)?;
+            token_auction_state.timer_duration = None;
+            end_moved = Some(AuctionEvent::TimerStarted {
+                auction_id: token_auction_state.auction_id,
+                end_time: token_auction_state.end,
+            });
+        }
+    } else if let Some(extension) = token_auction_state.extension {
+        // Anti-sniping: a bid this close to the end leaves the others as long to answer it.
+        let extended_end = checked_plus_seconds(env.block.time, extension)?;
+        if extended_end > token_auction_state.end {
+            end_moved = Some(AuctionEvent::Extended {
+                auction_id: token_auction_state.auction_id,
+                previous_end_time: token_auction_state.end,
+                end_time: extended_end,
+            });
+            token_auction_state.end = extended_end;
+        }
+    }
+
+    let mut response = Response::new().add_attribute("action", "bid").add_event(
+        AuctionEvent::Bid {
+            auction: token_auction_state.clone(),
+            bidder: info.sender,
+        }
+        .into(),
+    );
+    response = response.add_events(end_moved.map(Event::from));
+    if let Some((outbid, amount, reason)) = refund {
+        let auction_id = token_auction_state.auction_id;
+        let high_bidder = token_auction_state.high_bidder_addr.clone();
+        let event = match reason {
+            RefundReason::ProxyOutbid => AuctionEvent::ProxyOutbid {
+                auction_id,
+                bidder: outbid.clone(),
+                high_bidder,
+            },
+            _ => AuctionEvent::Outbid {
+                auction_id,
+                bidder: outbid.clone(),
+                high_bidder,
+            },
+        };
+        response = response.add_event(event.into());
+        let (refund_msg, refund_event) =
+            refund_bid(deps.storage, &env, auction_id, &outbid, amount, reason)?;
+        response = response.add_submessage(refund_msg).add_event(refund_event);
+    }
+
+    save_nft_auction_state(deps.storage, key, token_auction_state)?;
     save_bids(deps.storage, key, bids_for_auction)?;
-    Ok(Response::new().add_messages(messages).add_attributes(vec![
-        attr("action", "bid"),
-        attr("token_id", token_id),
-        attr("bider", info.sender.to_string()),
-        attr("amount", payment.amount.to_string()),
+    Ok(response)
+}
+
+/// Sends bid funds back to a bidder, over IBC to the origin address of hook bidders.
+fn refund_bid(
+    storage: &mut dyn Storage,
+    env: &Env,
+    auction_id: Uint128,
+    recipient: &Addr,
+    amount: Coin,
+    reason: RefundReason,
+) -> StdResult<(SubMsg, Event)> {
+    let ibc_origin = IBC_BIDDERS.may_load(storage, recipient)?;
+    let msg = match &ibc_origin {
+        Some(origin) => ibc_refund_msg(storage, env, auction_id, origin, &amount)?,
+        None => SubMsg::new(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![amount.clone()],
+        }),
+    };
+    let event = AuctionEvent::Refunded {
+        auction_id,
+        recipient: recipient.clone(),
+        amount,
+        reason,
+        ibc_origin,
+    };
+    Ok((msg, event.into()))
+}
+
+pub fn approve_operator(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    operator: String,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let operator = deps.api.addr_validate(&operator)?;
+    let expires = expires.unwrap_or_default();
+    ensure!(
+        !expires.is_expired(&env.block),
+        ContractError::InValidTime {}
+    );
+    OPERATORS.save(deps.storage, (&info.sender, &operator), &expires)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "approve_operator"),
+        attr("seller", info.sender),
+        attr("operator", operator),
+        attr("expires", expires.to_string()),
     ]))
 }
 
+pub fn revoke_operator(
+    deps: DepsMut,
+    info: MessageInfo,
+    operator: String,
+) -> Result<Response, ContractError> {
+    let operator = deps.api.addr_validate(&operator)?;
+    ensure!(
+        OPERATORS.has(deps.storage, (&info.sender, &operator)),
+        ContractError::OperatorNotApproved {}
+    );
+    OPERATORS.remove(deps.storage, (&info.sender, &operator));
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "revoke_operator"),
+        attr("seller", info.sender),
+        attr("operator", operator),
+    ]))
+}
+
+pub fn save_template(
+    deps: DepsMut,
+    info: MessageInfo,
+    name: String,
+    params: AuctionTemplate,
+) -> Result<Response, ContractError> {
+    // Names are echoed in attributes and used as storage keys, so they are kept short and plain.
+    ensure!(
+        !name.is_empty() && name.len() <= MAX_TEMPLATE_NAME_LENGTH,
+        ContractError::InvalidTemplate {
+            msg: format!("Template names are 1 to {MAX_TEMPLATE_NAME_LENGTH} characters long"),
+        }
+    );
+    ensure!(
+        name.chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')),
+        ContractError::InvalidTemplate {
+            msg: "Template names may only contain ASCII letters, digits, '-', '_' and '.'"
+                .to_string(),
+        }
+    );
+    ensure!(params.duration > 0, ContractError::InValidTime {});
+    validate_bid_increment(params.bid_increment)?;
+    if let Some(access) = &params.access {
+        validate_bidder_access(deps.as_ref(), access)?;
+    }
+    validate_accepted_denoms(
+        deps.storage,
+        &params.coin_denomination,
+        params.accepted_denoms.as_deref().unwrap_or_default(),
+    )?;
+    TEMPLATES.save(deps.storage, (&info.sender, &name), &params)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "save_template"),
+        attr("seller", info.sender),
+        attr("name", name),
+    ]))
+}
+
+pub fn delete_template(
+    deps: DepsMut,
+    info: MessageInfo,
+    name: String,
+) -> Result<Response, ContractError> {
+    ensure!(
+        TEMPLATES.has(deps.storage, (&info.sender, &name)),
+        ContractError::TemplateDoesNotExist {}
+    );
+    TEMPLATES.remove(deps.storage, (&info.sender, &name));
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "delete_template"),
+        attr("seller", info.sender),
+        attr("name", name),
+    ]))
+}
+
+/// Checks that `sender` is the seller or one of its unexpired operators.
+fn ensure_owner_or_operator(
+    storage: &dyn Storage,
+    env: &Env,
+    owner: &Addr,
+    sender: &Addr,
+) -> Result<(), ContractError> {
+    if sender == owner {
+        return Ok(());
+    }
+    let expires = OPERATORS.may_load(storage, (owner, sender))?;
+    ensure!(
+        expires.is_some_and(|expires| !expires.is_expired(&env.block)),
+        ContractError::Unauthorized {}
+    );
+    Ok(())
+}
+
 pub fn cancel_auction_and_refund(
     deps: DepsMut,
     env: Env,
@@ -203,108 +1312,754 @@ pub fn cancel_auction_and_refund(
 ) -> Result<Response, ContractError> {
     let mut token_auction_state =
         fetch_latest_auction_state_for_token(deps.storage, &token_id, &token_address)?;
+    let owner = Addr::unchecked(token_auction_state.owner.clone());
+    ensure_owner_or_operator(deps.storage, &env, &owner, &info.sender)?;
     ensure!(
-        info.sender == token_auction_state.owner,
-        ContractError::Unauthorized {}
+        !token_auction_state.is_cancelled,
+        ContractError::AuctionCancelled {}
     );
     ensure!(
-        !token_auction_state.end.gt(&env.block.time),
+        token_auction_state.end.gt(&env.block.time),
         ContractError::AuctionEnded {}
     );
-    let mut messages: Vec<CosmosMsg> = vec![CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: token_auction_state.token_address.clone(),
-        msg: to_json_binary(&Cw721ExecuteMsg::TransferNft {
-            recipient: info.sender.to_string(),
-            token_id,
-        })?,
-        funds: vec![],
-    })];
-
-    // Refund highest bid, if it exists.
-    if !token_auction_state.high_bidder_amount.is_zero() {
-        messages.push(CosmosMsg::Bank(BankMsg::Send {
-            to_address: token_auction_state.high_bidder_addr.to_string(),
-            amount: coins(
-                token_auction_state.high_bidder_amount.u128(),
-                token_auction_state.coin_denomination.clone(),
-            ),
-        }));
+    let mut messages = token_auction_state
+        .escrowed_items()
+        .into_iter()
+        .map(|item| {
+            transfer_nft_msg(item.token_address, item.token_id, owner.to_string()).map(SubMsg::new)
+        })
+        .collect::<StdResult<Vec<SubMsg>>>()?;
+
+    // Refund highest bid, if it exists, along with any cancellation penalty.
+    let config = load_config(deps.storage)?;
+    let has_bids = !token_auction_state.high_bidder_amount.is_zero();
+    let mut penalty = Uint128::zero();
+    let mut refund_event = None;
+    if has_bids {
+        match config.cancel_policy {
+            CancelPolicy::Unrestricted => {}
+            CancelPolicy::BeforeFirstBid => return Err(ContractError::CancelNotAllowed {}),
+            CancelPolicy::WithPenalty { penalty_bps } => {
+                penalty = token_auction_state
+                    .high_bidder_amount
+                    .multiply_ratio(penalty_bps, MAX_BPS);
+                decrease_balance(
+                    deps.storage,
+                    &owner,
+                    &coin(penalty.u128(), token_auction_state.bid_denom()),
+                )?;
+            }
+        }
+        let escrow = load_bid_escrow(deps.storage, &token_auction_state)?;
+        BID_ESCROWS.remove(deps.storage, token_auction_state.auction_id.u128());
+        let (refund_msg, event) = refund_bid(
+            deps.storage,
+            &env,
+            token_auction_state.auction_id,
+            &token_auction_state.high_bidder_addr,
+            coin((escrow + penalty).u128(), token_auction_state.bid_denom()),
+            RefundReason::Cancelled,
+        )?;
+        messages.push(refund_msg);
+        refund_event = Some(event);
     }
 
+    // The listing deposit is slashed to the marketplace owner when bids are cancelled.
+    let mut listing_deposit = token_auction_state.listing_deposit.clone();
+    if has_bids {
+        if let Some(deposit) = listing_deposit.take() {
+            release_locked_balance(deps.storage, &owner, &deposit)?;
+            messages.push(SubMsg::new(BankMsg::Send {
+                to_address: config.owner.to_string(),
+                amount: vec![deposit],
+            }));
+        }
+    }
+    refund_seller_fees(
+        deps.storage,
+        &owner,
+        &token_auction_state.keeper_fee,
+        &listing_deposit,
+    )?;
+
     token_auction_state.is_cancelled = true;
     save_nft_auction_state(
         deps.storage,
         token_auction_state.auction_id.u128(),
-        token_auction_state,
+        token_auction_state.clone(),
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_auction")
+        .add_submessages(messages)
+        .add_event(
+            AuctionEvent::Cancelled {
+                auction: token_auction_state,
+                penalty,
+            }
+            .into(),
+        )
+        .add_events(refund_event))
+}
+
+/// Lets the seller change the terms of an auction that has not received any bids yet.
+#[allow(clippy::too_many_arguments)]
+pub fn update_auction(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    auction_id: Uint128,
+    min_bid: Option<Uint128>,
+    end: Option<u64>,
+    reserve_price: Option<Uint128>,
+    coin_denomination: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut token_auction_state = nft_auction_states()
+        .may_load(deps.storage, auction_id.u128())?
+        .ok_or(ContractError::AuctionDoesNotExist {})?;
+    ensure_owner_or_operator(
+        deps.storage,
+        &env,
+        &Addr::unchecked(token_auction_state.owner.clone()),
+        &info.sender,
     )?;
+    ensure!(
+        !token_auction_state.is_cancelled,
+        ContractError::AuctionCancelled {}
+    );
+    ensure!(
+        token_auction_state.end.gt(&env.block.time),
+        ContractError::AuctionEnded {}
+    );
+    ensure!(
+        token_auction_state.high_bidder_amount.is_zero(),
+        ContractError::AuctionHasBids {}
+    );
 
-    Ok(Response::new().add_messages(messages))
+    // A zero minimum bid or reserve price removes it.
+    if let Some(min_bid) = min_bid {
+        token_auction_state.min_bid = Some(min_bid).filter(|min_bid| !min_bid.is_zero());
+    }
+    if let Some(reserve_price) = reserve_price {
+        token_auction_state.reserve_price =
+            Some(reserve_price).filter(|reserve_price| !reserve_price.is_zero());
+    }
+    if let Some(coin_denomination) = coin_denomination {
+        validate_accepted_denoms(
+            deps.storage,
+            &coin_denomination,
+            &token_auction_state.accepted_denoms,
+        )?;
+        token_auction_state.coin_denomination = coin_denomination;
+    }
+    let mut rescheduled = None;
+    if let Some(end) = end {
+        let end = checked_plus_seconds(Timestamp::from_nanos(0), end)?;
+        ensure!(
+            end.gt(&env.block.time) && end.gt(&token_auction_state.start),
+            ContractError::InValidTime {}
+        );
+        // A fixed end replaces the countdown of an auction waiting for its first bid.
+        token_auction_state.timer_duration = None;
+        rescheduled = Some(AuctionEvent::Rescheduled {
+            auction_id,
+            previous_end_time: token_auction_state.end,
+            end_time: end,
+        });
+        token_auction_state.end = end;
+    }
+    save_nft_auction_state(deps.storage, auction_id.u128(), token_auction_state.clone())?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_auction")
+        .add_event(
+            AuctionEvent::Updated {
+                auction: token_auction_state,
+            }
+            .into(),
+        )
+        .add_events(rescheduled.map(Event::from)))
 }
 
 pub fn finalize_auction_and_transfer_assets(
     deps: DepsMut,
     env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     token_id: String,
     token_address: String,
 ) -> Result<Response, ContractError> {
     let token_auction_state =
         fetch_latest_auction_state_for_token(deps.storage, &token_id, &token_address)?;
+    let (messages, events) = settle_auction(deps, &env, &info.sender, token_auction_state)?;
+    Ok(Response::new()
+        .add_attribute("action", "claim")
+        .add_submessages(messages)
+        .add_events(events))
+}
+
+pub fn finalize_many(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    auction_ids: Vec<Uint128>,
+) -> Result<Response, ContractError> {
+    let mut response = Response::new().add_attribute("action", "finalize_many");
+    for auction_id in auction_ids {
+        response = settle_or_skip(
+            deps.branch(),
+            &env,
+            &info.sender,
+            auction_id.u128(),
+            response,
+        )?;
+    }
+    Ok(response)
+}
+
+pub fn finalize_expired(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    start_after: Option<Uint128>,
+    limit: Option<u64>,
+) -> Result<Response, ContractError> {
+    let auction_ids = read_expired_auction_ids(deps.storage, env.block.time, start_after, limit)?;
+    let mut response = Response::new().add_attribute("action", "finalize_expired");
+    for auction_id in auction_ids {
+        response = settle_or_skip(deps.branch(), &env, &info.sender, auction_id, response)?;
+    }
+    Ok(response)
+}
+
+/// Settles a single auction as part of a batch. Auctions that cannot be settled are reported
+/// in an `auction_settle_skipped` event instead of failing the whole batch.
+fn settle_or_skip(
+    deps: DepsMut,
+    env: &Env,
+    keeper: &Addr,
+    auction_id: u128,
+    response: Response,
+) -> Result<Response, ContractError> {
+    // Each settlement runs against its own cache, so one that fails leaves no writes behind.
+    let mut cache = StorageCache::new(deps.storage);
+    let result = match nft_auction_states().may_load(&cache, auction_id)? {
+        None => Err(ContractError::AuctionDoesNotExist {}),
+        Some(token_auction_state) => settle_auction(
+            DepsMut {
+                storage: &mut cache,
+                api: deps.api,
+                querier: deps.querier,
+            },
+            env,
+            keeper,
+            token_auction_state,
+        ),
+    };
+    let writes = cache.into_writes();
+    Ok(match result {
+        Ok((messages, events)) => {
+            writes.commit(deps.storage);
+            response.add_submessages(messages).add_events(events)
+        }
+        Err(err) => response.add_event(
+            AuctionEvent::SettleSkipped {
+                auction_id: auction_id.into(),
+                reason: err.to_string(),
+            }
+            .into(),
+        ),
+    })
+}
+
+/// Pays out an ended auction: the NFT goes to the highest bidder and the bid, minus the keeper
+/// reward, to the owner. When nobody bid the NFT is returned to the owner and the escrowed keeper
+/// fee is paid to the keeper instead.
+fn settle_auction(
+    deps: DepsMut,
+    env: &Env,
+    keeper: &Addr,
+    mut token_auction_state: NFTAuctionState,
+) -> Result<(Vec<SubMsg>, Vec<Event>), ContractError> {
     ensure!(
-        token_auction_state.end.gt(&env.block.time),
-        ContractError::AuctionNotEnded {}
+        !token_auction_state.is_cancelled,
+        ContractError::AuctionCancelled {}
     );
-    let token_owner = query_token_owner(
-        deps.querier,
-        token_auction_state.token_address.clone(),
-        token_id.clone(),
-    )?
-    .owner;
     ensure!(
-        token_owner == env.contract.address,
+        !token_auction_state.is_settled,
         ContractError::AuctionAlreadyClaimed {}
     );
-
-    if token_auction_state.high_bidder_addr.to_string().is_empty()
-        || token_auction_state.high_bidder_amount.is_zero()
+    // An approval-based auction whose NFT the seller moved or stopped approving cannot be won,
+    // so it is invalidated straight away rather than holding the high bid until its end.
+    if token_auction_state.held_by_seller
+        && !seller_still_holds_nft(deps.as_ref(), env, &token_auction_state)
     {
-        return Ok(Response::new()
-            .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: token_auction_state.token_address.clone(),
-                msg: to_json_binary(&Cw721ExecuteMsg::TransferNft {
-                    recipient: token_auction_state.owner.clone(),
-                    token_id: token_id.clone(),
-                })?,
-                funds: vec![],
-            }))
-            .add_attribute("action", "claim")
-            .add_attribute("token_id", token_id)
-            .add_attribute("token_contract", token_auction_state.token_address)
-            .add_attribute("recipient", token_auction_state.owner)
-            .add_attribute("winning_bid_amount", token_auction_state.high_bidder_amount)
-            .add_attribute("auction_id", token_auction_state.auction_id));
+        return invalidate_auction(deps.storage, env, token_auction_state);
+    }
+    ensure!(
+        token_auction_state.end.le(&env.block.time),
+        ContractError::AuctionNotEnded {}
+    );
+    let token_id = token_auction_state.token_id.clone();
+    if token_auction_state.lazy_mint.is_some() {
+        // The collection may have handed the minter role to someone else since the auction
+        // started, in which case the winner could never be minted the token.
+        if !marketplace_still_mints(deps.as_ref(), env, &token_auction_state) {
+            return invalidate_auction(deps.storage, env, token_auction_state);
+        }
+    } else if !token_auction_state.held_by_seller {
+        let token_owner = query_token_owner(
+            deps.querier,
+            token_auction_state.token_address.clone(),
+            token_id.clone(),
+        )?
+        .owner;
+        ensure!(
+            token_owner == env.contract.address,
+            ContractError::AuctionAlreadyClaimed {}
+        );
     }
 
-    Ok(Response::new()
-        .add_message(CosmosMsg::Bank(BankMsg::Send {
-            to_address: token_auction_state.owner,
+    let owner = Addr::unchecked(token_auction_state.owner.clone());
+    let mut messages: Vec<SubMsg> = vec![];
+    let mut refund_events: Vec<Event> = vec![];
+    let mut keeper_reward: Option<Coin> = None;
+    let mut send_back: Option<(Ics721Class, String)> = None;
+    let has_bid = !token_auction_state.high_bidder_addr.to_string().is_empty()
+        && !token_auction_state.high_bidder_amount.is_zero();
+    let reserve_met = reserve_met(deps.storage, &token_auction_state)?;
+    let escrow = load_bid_escrow(deps.storage, &token_auction_state)?;
+    BID_ESCROWS.remove(deps.storage, token_auction_state.auction_id.u128());
+    let recipient = if !has_bid || !reserve_met {
+        // The lot is unsold, so the highest bid, if any, is refunded.
+        if has_bid {
+            let (refund_msg, refund_event) = refund_bid(
+                deps.storage,
+                env,
+                token_auction_state.auction_id,
+                &token_auction_state.high_bidder_addr,
+                coin(escrow.u128(), token_auction_state.bid_denom()),
+                RefundReason::ReserveNotMet,
+            )?;
+            messages.push(refund_msg);
+            refund_events.push(refund_event);
+        }
+        if let Some(fee) = &token_auction_state.keeper_fee {
+            release_locked_balance(deps.storage, &owner, fee)?;
+        }
+        keeper_reward = token_auction_state.keeper_fee.clone();
+        token_auction_state.owner.clone()
+    } else {
+        let reward = token_auction_state
+            .high_bidder_amount
+            .multiply_ratio(token_auction_state.keeper_reward_bps, MAX_BPS);
+        // The winning bid is paid out in the denom it was made in.
+        if !reward.is_zero() {
+            keeper_reward = Some(coin(reward.u128(), token_auction_state.bid_denom()));
+        }
+        messages.push(SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: token_auction_state.owner.clone(),
             amount: coins(
+                (token_auction_state.high_bidder_amount - reward).u128(),
+                token_auction_state.bid_denom(),
+            ),
+        })));
+        // A proxy bidder only pays the winning bid; the rest of their maximum is refunded.
+        let excess = escrow - token_auction_state.high_bidder_amount;
+        if !excess.is_zero() {
+            let (refund_msg, refund_event) = refund_bid(
+                deps.storage,
+                env,
+                token_auction_state.auction_id,
+                &token_auction_state.high_bidder_addr,
+                coin(excess.u128(), token_auction_state.bid_denom()),
+                RefundReason::ProxyExcess,
+            )?;
+            messages.push(refund_msg);
+            refund_events.push(refund_event);
+        }
+        // The keeper is paid from the winning bid, so the escrowed fee goes back to the seller.
+        if let Some(fee) = &token_auction_state.keeper_fee {
+            unlock_balance(deps.storage, &owner, fee)?;
+        }
+        record_sale(
+            deps.storage,
+            &token_auction_state.items(),
+            token_auction_state.auction_id,
+            coin(
                 token_auction_state.high_bidder_amount.u128(),
-                token_auction_state.coin_denomination.clone(),
+                token_auction_state.bid_denom(),
             ),
+            env.block.time,
+            &token_auction_state.high_bidder_addr,
+            &owner,
+        )?;
+        // The winner may have asked for a bridged NFT back on its origin chain.
+        if let Some(class) = &token_auction_state.ics721_class {
+            send_back = ICS721_RECEIVERS
+                .may_load(
+                    deps.storage,
+                    (
+                        token_auction_state.auction_id.u128(),
+                        &token_auction_state.high_bidder_addr,
+                    ),
+                )?
+                .map(|receiver| (class.clone(), receiver));
+        }
+        nft_recipient(deps.storage, &token_auction_state.high_bidder_addr)?
+    };
+    if let Some(deposit) = &token_auction_state.listing_deposit {
+        unlock_balance(deps.storage, &owner, deposit)?;
+    }
+    // Deliveries are keyed by the index of their item in `items`.
+    let mut deliveries: Vec<(usize, CosmosMsg)> = vec![];
+    if let Some((class, receiver)) = send_back {
+        ICS721_SENT_BACK.save(
+            deps.storage,
+            token_auction_state.auction_id.u128(),
+            &recipient,
+        )?;
+        deliveries.push((
+            0,
+            send_back_msg(env, &token_auction_state, &class, receiver)?,
+        ));
+    } else {
+        match &token_auction_state.lazy_mint {
+            // An unsold lazy token is simply never minted.
+            Some(lazy_mint) if recipient != token_auction_state.owner => {
+                deliveries.push((
+                    0,
+                    mint_nft_msg(
+                        token_auction_state.token_address.clone(),
+                        token_id.clone(),
+                        lazy_mint.token_uri.clone(),
+                        recipient.clone(),
+                    )?,
+                ));
+            }
+            // The marketplace moves the NFT out of the seller's wallet using its approval.
+            None if token_auction_state.held_by_seller
+                && recipient != token_auction_state.owner =>
+            {
+                deliveries.push((
+                    0,
+                    transfer_nft_msg(
+                        token_auction_state.token_address.clone(),
+                        token_id.clone(),
+                        recipient.clone(),
+                    )?,
+                ));
+            }
+            _ => {
+                for (index, item) in token_auction_state.escrowed_items().into_iter().enumerate() {
+                    deliveries.push((
+                        index,
+                        transfer_nft_msg(item.token_address, item.token_id, recipient.clone())?,
+                    ));
+                }
+            }
+        }
+    }
+    // A failed delivery replies instead of reverting the settlement, and leaves the NFT to be
+    // claimed.
+    for (index, msg) in deliveries {
+        messages.push(SubMsg::reply_on_error(
+            msg,
+            delivery_reply_id(token_auction_state.auction_id, index)?,
+        ));
+    }
+
+    if let Some(reward) = &keeper_reward {
+        messages.push(SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: keeper.to_string(),
+            amount: vec![reward.clone()],
+        })));
+    }
+
+    token_auction_state.is_settled = true;
+    save_nft_auction_state(
+        deps.storage,
+        token_auction_state.auction_id.u128(),
+        token_auction_state.clone(),
+    )?;
+
+    let mut events: Vec<Event> = vec![AuctionEvent::Settled {
+        auction: token_auction_state,
+        recipient,
+        reserve_met,
+        keeper: keeper.clone(),
+        keeper_reward,
+    }
+    .into()];
+    events.extend(refund_events);
+    Ok((messages, events))
+}
+
+/// Handles a failed settlement delivery. The settlement stands and the NFT becomes claimable by
+/// the local recipient it was meant for: the winner's, or the seller when the lot went unsold.
+pub fn handle_delivery_reply(deps: DepsMut, msg: Reply) -> Result<Response, ContractError> {
+    let error = match msg.result {
+        SubMsgResult::Err(error) => error,
+        SubMsgResult::Ok(_) => return Err(ContractError::UnexpectedReply { id: msg.id }),
+    };
+    let (auction_id, index) = parse_delivery_reply_id(msg.id);
+    let auction = load_nft_auction_state(deps.storage, auction_id)?;
+    let item = auction
+        .items()
+        .into_iter()
+        .nth(index as usize)
+        .ok_or(ContractError::UnexpectedReply { id: msg.id })?;
+    let sale_key = (
+        item.token_address.as_str(),
+        auction_id,
+        item.token_id.as_str(),
+    );
+    let recipient = match SALES.may_load(deps.storage, sale_key)? {
+        Some(sale) => nft_recipient(deps.storage, &sale.buyer)?,
+        None => auction.owner.clone(),
+    };
+    CLAIMABLE_NFTS.save(deps.storage, (auction_id, index), &recipient)?;
+    // A send-back that failed here never reached ICS-721, so it cannot be returned later.
+    ICS721_SENT_BACK.remove(deps.storage, auction_id);
+
+    Ok(Response::new()
+        .add_attribute("action", "delivery_failed")
+        .add_event(
+            AuctionEvent::DeliveryFailed {
+                auction_id: auction.auction_id,
+                item,
+                recipient,
+                error,
+            }
+            .into(),
+        ))
+}
+
+/// Delivers the NFTs of a settled auction whose delivery failed, including an NFT that ICS-721
+/// returned after its IBC transfer failed or timed out.
+pub fn claim_nft(deps: DepsMut, env: Env, auction_id: Uint128) -> Result<Response, ContractError> {
+    let auction = load_nft_auction_state(deps.storage, auction_id.u128())?;
+    let mut claimable = CLAIMABLE_NFTS
+        .prefix(auction_id.u128())
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    if let Some(recipient) = ICS721_SENT_BACK.may_load(deps.storage, auction_id.u128())? {
+        if ics721_returned_nft(deps.as_ref(), &env, &auction)? {
+            ICS721_SENT_BACK.remove(deps.storage, auction_id.u128());
+            claimable.push((0, recipient));
+        }
+    }
+    ensure!(!claimable.is_empty(), ContractError::NftNotClaimable {});
+    let items = auction.items();
+    let mut messages = vec![];
+    for (index, recipient) in claimable {
+        CLAIMABLE_NFTS.remove(deps.storage, (auction_id.u128(), index));
+        let item = items[index as usize].clone();
+        messages.push(match &auction.lazy_mint {
+            Some(lazy_mint) => mint_nft_msg(
+                item.token_address,
+                item.token_id,
+                lazy_mint.token_uri.clone(),
+                recipient,
+            )?,
+            None => transfer_nft_msg(item.token_address, item.token_id, recipient)?,
+        });
+    }
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "claim_nft"),
+        attr("auction_id", auction_id),
+    ]))
+}
+
+/// Whether ICS-721 handed the NFT of a settled auction back: the marketplace holds it again and
+/// it was not put up in a later auction since.
+fn ics721_returned_nft(
+    deps: Deps,
+    env: &Env,
+    auction: &NFTAuctionState,
+) -> Result<bool, ContractError> {
+    let latest = fetch_latest_auction_state_for_token(
+        deps.storage,
+        &auction.token_id,
+        &auction.token_address,
+    )?;
+    if latest.auction_id != auction.auction_id {
+        return Ok(false);
+    }
+    let token = query_token_owner(
+        deps.querier,
+        auction.token_address.clone(),
+        auction.token_id.clone(),
+    )?;
+    Ok(token.owner == env.contract.address)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    owner: Option<String>,
+    keeper_reward_bps: Option<u64>,
+    keeper_fee: Option<Coin>,
+    cancel_policy: Option<CancelPolicy>,
+    listing_deposit: Option<Coin>,
+    bid_increment: Option<Uint128>,
+    randomness_beacon: Option<String>,
+    ics721: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut config = load_config(deps.storage)?;
+    ensure!(info.sender == config.owner, ContractError::Unauthorized {});
+
+    if let Some(owner) = owner {
+        config.owner = deps.api.addr_validate(&owner)?;
+    }
+    if let Some(keeper_reward_bps) = keeper_reward_bps {
+        ensure!(
+            keeper_reward_bps <= MAX_KEEPER_REWARD_BPS,
+            ContractError::KeeperRewardTooHigh {
+                max: MAX_KEEPER_REWARD_BPS,
+            }
+        );
+        config.keeper_reward_bps = keeper_reward_bps;
+    }
+    if let Some(keeper_fee) = keeper_fee {
+        // A zero fee disables the keeper fee.
+        config.keeper_fee = if keeper_fee.amount.is_zero() {
+            None
+        } else {
+            Some(keeper_fee)
+        };
+    }
+    if let Some(cancel_policy) = cancel_policy {
+        if let CancelPolicy::WithPenalty { penalty_bps } = cancel_policy {
+            ensure!(penalty_bps <= MAX_BPS, ContractError::InvalidBasisPoints {});
+        }
+        config.cancel_policy = cancel_policy;
+    }
+    if let Some(listing_deposit) = listing_deposit {
+        // A zero deposit disables the listing deposit.
+        config.listing_deposit = if listing_deposit.amount.is_zero() {
+            None
+        } else {
+            Some(listing_deposit)
+        };
+    }
+    if let Some(bid_increment) = bid_increment {
+        ensure!(
+            !bid_increment.is_zero(),
+            ContractError::InvalidBidIncrement {}
+        );
+        config.bid_increment = bid_increment;
+    }
+    if let Some(randomness_beacon) = randomness_beacon {
+        // An empty address removes the beacon.
+        config.randomness_beacon = if randomness_beacon.is_empty() {
+            None
+        } else {
+            Some(deps.api.addr_validate(&randomness_beacon)?)
+        };
+    }
+    if let Some(ics721) = ics721 {
+        // An empty address removes the ICS-721 contract.
+        config.ics721 = if ics721.is_empty() {
+            None
+        } else {
+            Some(deps.api.addr_validate(&ics721)?)
+        };
+    }
+    save_config(deps.storage, &config)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_config"),
+        attr("owner", config.owner),
+        attr("keeper_reward_bps", config.keeper_reward_bps.to_string()),
+    ]))
+}
+
+/// Sets or, with a zero rate, removes the exchange rates used to rank bids on multi-denom
+/// auctions.
+pub fn set_exchange_rates(
+    deps: DepsMut,
+    info: MessageInfo,
+    rates: Vec<ExchangeRate>,
+) -> Result<Response, ContractError> {
+    let config = load_config(deps.storage)?;
+    ensure!(info.sender == config.owner, ContractError::Unauthorized {});
+
+    for rate in rates.iter() {
+        if rate.rate.is_zero() {
+            // Open auctions could neither rank their bids nor settle without the rate.
+            ensure!(
+                !exchange_rate_in_use(deps.storage, &rate.denom)?,
+                ContractError::ExchangeRateInUse {
+                    denom: rate.denom.clone(),
+                }
+            );
+            EXCHANGE_RATES.remove(deps.storage, &rate.denom);
+        } else {
+            EXCHANGE_RATES.save(deps.storage, &rate.denom, &rate.rate)?;
+        }
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "set_exchange_rates"),
+        attr(
+            "rates",
+            rates
+                .iter()
+                .map(|rate| format!("{}:{}", rate.denom, rate.rate))
+                .collect::<Vec<_>>()
+                .join(","),
+        ),
+    ]))
+}
+
+pub fn deposit_balance(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    ensure!(
+        !info.funds.is_empty(),
+        ContractError::InvalidFunds {
+            msg: "Deposits require at least one coin".to_string(),
+        }
+    );
+    for coin in info.funds.iter() {
+        increase_balance(deps.storage, &info.sender, coin)?;
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "deposit_balance"),
+        attr("sender", info.sender),
+        attr(
+            "amount",
+            info.funds
+                .iter()
+                .map(|coin| coin.to_string())
+                .collect::<Vec<_>>()
+                .join(","),
+        ),
+    ]))
+}
+
+pub fn withdraw_balance(
+    deps: DepsMut,
+    info: MessageInfo,
+    amount: Coin,
+) -> Result<Response, ContractError> {
+    ensure!(
+        !amount.amount.is_zero(),
+        ContractError::InvalidFunds {
+            msg: "Withdrawal amount must be greater than zero".to_string(),
+        }
+    );
+    decrease_balance(deps.storage, &info.sender, &amount)?;
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Bank(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![amount.clone()],
         }))
-        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: token_auction_state.token_address.clone(),
-            msg: to_json_binary(&Cw721ExecuteMsg::TransferNft {
-                recipient: token_auction_state.high_bidder_addr.to_string(),
-                token_id: token_id.clone(),
-            })?,
-            funds: vec![],
-        }))
-        .add_attribute("action", "claim")
-        .add_attribute("token_id", token_id)
-        .add_attribute("token_contract", token_auction_state.token_address)
-        .add_attribute("recipient", &token_auction_state.high_bidder_addr)
-        .add_attribute("winning_bid_amount", token_auction_state.high_bidder_amount)
-        .add_attribute("auction_id", token_auction_state.auction_id))
+        .add_attributes(vec![
+            attr("action", "withdraw_balance"),
+            attr("sender", info.sender),
+            attr("amount", amount.to_string()),
+        ]))
 }
@@ -1,12 +1,28 @@
 use crate::{
     error::ContractError,
+    msg::{Cw721MinterExecuteMsg, Cw721MinterQueryMsg, MinterResponse},
     state::{
         auction_details, load_next_auction_id, load_nft_auction_state, save_next_auction_id,
-        NFTAuctionState,
+        IbcOrigin, NFTAuctionState,
     },
 };
-use cosmwasm_std::{to_json_binary, QuerierWrapper, QueryRequest, Storage, Uint128, WasmQuery};
-use cw721::{Cw721QueryMsg, OwnerOfResponse};
+use bech32::{ToBase32, Variant};
+use cosmwasm_std::{
+    to_json_binary, Addr, CosmosMsg, Empty, QuerierWrapper, QueryRequest, StdError, StdResult,
+    Storage, Timestamp, Uint128, Uint64, WasmMsg, WasmQuery,
+};
+use cw721::{Cw721ExecuteMsg, Cw721QueryMsg, OperatorResponse, OwnerOfResponse, TokensResponse};
+use sha2::{Digest, Sha256};
+
+/// Type that ibc-hooks hashes into the local sender of the wasm calls made from ICS-20 memos.
+const IBC_HOOK_SENDER_TYPE: &str = "ibc-wasm-hook-intermediary";
+
+/// Reply IDs of settlement deliveries hold the auction ID above the item index, which takes
+/// this many low bits.
+const DELIVERY_INDEX_BITS: u32 = 16;
+
+/// High bit that sets the reply IDs of IBC refunds apart from those of deliveries.
+pub const IBC_REFUND_REPLY_FLAG: u64 = 1 << 63;
 
 pub fn fetch_and_update_next_auction_id(
     storage: &mut dyn Storage,
@@ -49,3 +65,126 @@ pub fn query_token_owner(
 
     Ok(res)
 }
+
+/// Returns whether `owner` holds at least one token of the cw721 `collection`.
+pub fn query_holds_token(
+    querier: QuerierWrapper,
+    collection: String,
+    owner: String,
+) -> Result<bool, ContractError> {
+    let res: TokensResponse = querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: collection,
+        msg: to_json_binary(&Cw721QueryMsg::Tokens {
+            owner,
+            start_after: None,
+            limit: Some(1),
+        })?,
+    }))?;
+
+    Ok(!res.tokens.is_empty())
+}
+
+/// Returns whether `owner` approved `operator` for all of its tokens of the cw721 `collection`.
+/// cw721-base answers the query with an error when there is no unexpired approval.
+pub fn query_is_operator(
+    querier: QuerierWrapper,
+    collection: String,
+    owner: String,
+    operator: String,
+) -> Result<bool, ContractError> {
+    let request = QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: collection,
+        msg: to_json_binary(&Cw721QueryMsg::Operator {
+            owner,
+            operator,
+            include_expired: None,
+        })?,
+    });
+
+    Ok(querier.query::<OperatorResponse>(&request).is_ok())
+}
+
+/// Returns the minter of a cw721-base collection, if it has one.
+pub fn query_minter(
+    querier: QuerierWrapper,
+    token_addr: String,
+) -> Result<Option<String>, ContractError> {
+    let res: MinterResponse = querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: token_addr,
+        msg: to_json_binary(&Cw721MinterQueryMsg::Minter {})?,
+    }))?;
+
+    Ok(res.minter)
+}
+
+/// `time` plus `seconds`, or `InValidTime` when that does not fit in a `Timestamp`.
+pub fn checked_plus_seconds(time: Timestamp, seconds: u64) -> Result<Timestamp, ContractError> {
+    seconds
+        .checked_mul(1_000_000_000)
+        .and_then(|nanos| time.nanos().checked_add(nanos))
+        .map(Timestamp::from_nanos)
+        .ok_or(ContractError::InValidTime {})
+}
+
+/// Reply ID of the delivery of item `index` of `NFTAuctionState::items` on settlement.
+pub fn delivery_reply_id(auction_id: Uint128, index: usize) -> Result<u64, ContractError> {
+    let index = u16::try_from(index).map_err(|_| ContractError::Overflow {})?;
+    let auction_id = Uint64::try_from(auction_id)
+        .map_err(StdError::from)?
+        .u64()
+        .checked_mul(1 << DELIVERY_INDEX_BITS)
+        .filter(|id| id & IBC_REFUND_REPLY_FLAG == 0)
+        .ok_or(ContractError::Overflow {})?;
+    Ok(auction_id | u64::from(index))
+}
+
+/// Splits a `delivery_reply_id` into the auction ID and item index.
+pub fn parse_delivery_reply_id(id: u64) -> (u128, u32) {
+    let index = id & ((1 << DELIVERY_INDEX_BITS) - 1);
+    ((id >> DELIVERY_INDEX_BITS).into(), index as u32)
+}
+
+pub fn mint_nft_msg(
+    token_address: String,
+    token_id: String,
+    token_uri: Option<String>,
+    owner: String,
+) -> StdResult<CosmosMsg> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: token_address,
+        msg: to_json_binary(&Cw721MinterExecuteMsg::Mint {
+            token_id,
+            owner,
+            token_uri,
+            extension: Empty {},
+        })?,
+        funds: vec![],
+    }))
+}
+
+pub fn transfer_nft_msg(
+    token_address: String,
+    token_id: String,
+    recipient: String,
+) -> StdResult<CosmosMsg> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: token_address,
+        msg: to_json_binary(&Cw721ExecuteMsg::TransferNft {
+            recipient,
+            token_id,
+        })?,
+        funds: vec![],
+    }))
+}
+
+/// The address ibc-hooks executes the memo of `origin` from, i.e.
+/// `bech32(sha256(sha256(type) || "{channel_id}/{sender}"))` with the given bech32 prefix.
+pub fn ibc_hook_sender(origin: &IbcOrigin, bech32_prefix: &str) -> Result<Addr, ContractError> {
+    let hash = Sha256::new()
+        .chain_update(Sha256::digest(IBC_HOOK_SENDER_TYPE))
+        .chain_update(format!("{}/{}", origin.channel_id, origin.sender))
+        .finalize();
+    bech32::encode(bech32_prefix, hash.to_base32(), Variant::Bech32)
+        .map(Addr::unchecked)
+        .map_err(|_| ContractError::InvalidIbcOrigin {})
+}
@@ -0,0 +1,128 @@
+use crate::{
+    contract::{events::AuctionEvent, helper::IBC_REFUND_REPLY_FLAG},
+    error::ContractError,
+    msg::{IbcCallbackMemo, IbcLifecycleComplete, MsgTransfer, MsgTransferResponse, ProtoCoin},
+    state::{
+        increase_balance, IbcOrigin, IbcRefund, IBC_REFUNDS, NEXT_IBC_REFUND_ID,
+        PENDING_IBC_REFUNDS,
+    },
+};
+use cosmwasm_std::{
+    to_json_string, Addr, Coin, CosmosMsg, DepsMut, Env, Reply, Response, StdError, StdResult,
+    Storage, SubMsg, SubMsgResult, Uint128,
+};
+use prost::Message;
+
+/// How long an IBC refund to a hook bidder may take before the transfer times out.
+pub const IBC_REFUND_TIMEOUT_SECONDS: u64 = 600;
+
+const MSG_TRANSFER_TYPE_URL: &str = "/ibc.applications.transfer.v1.MsgTransfer";
+
+/// Sends `amount` back to a hook bidder on its origin chain. The transfer asks ibc-hooks to call
+/// back with its outcome, and replies with its packet sequence so the callback can be matched, or
+/// with its error so the refund can be credited locally.
+pub fn ibc_refund_msg(
+    storage: &mut dyn Storage,
+    env: &Env,
+    auction_id: Uint128,
+    origin: &IbcOrigin,
+    amount: &Coin,
+) -> StdResult<SubMsg> {
+    let next_id = NEXT_IBC_REFUND_ID.may_load(storage)?.unwrap_or_default();
+    NEXT_IBC_REFUND_ID.save(storage, &(next_id + 1))?;
+    let reply_id = IBC_REFUND_REPLY_FLAG | next_id;
+    PENDING_IBC_REFUNDS.save(
+        storage,
+        reply_id,
+        &IbcRefund {
+            auction_id,
+            channel_id: origin.channel_id.clone(),
+            recipient: Addr::unchecked(&origin.nft_recipient),
+            amount: amount.clone(),
+        },
+    )?;
+
+    let transfer = MsgTransfer {
+        source_port: "transfer".to_owned(),
+        source_channel: origin.channel_id.clone(),
+        token: Some(ProtoCoin {
+            denom: amount.denom.clone(),
+            amount: amount.amount.to_string(),
+        }),
+        sender: env.contract.address.to_string(),
+        receiver: origin.sender.clone(),
+        timeout_timestamp: env
+            .block
+            .time
+            .plus_seconds(IBC_REFUND_TIMEOUT_SECONDS)
+            .nanos(),
+        memo: to_json_string(&IbcCallbackMemo {
+            ibc_callback: env.contract.address.to_string(),
+        })?,
+    };
+    Ok(SubMsg::reply_always(
+        CosmosMsg::Stargate {
+            type_url: MSG_TRANSFER_TYPE_URL.to_owned(),
+            value: transfer.encode_to_vec().into(),
+        },
+        reply_id,
+    ))
+}
+
+/// Files a sent IBC refund under the packet sequence `MsgTransfer` responded with. A transfer that
+/// could not be sent does not undo the outbid, cancellation or settlement that refunded the bidder;
+/// the refund is credited to the bidder's `nft_recipient` instead.
+pub fn handle_ibc_refund_reply(deps: DepsMut, msg: Reply) -> Result<Response, ContractError> {
+    let refund = PENDING_IBC_REFUNDS.load(deps.storage, msg.id)?;
+    PENDING_IBC_REFUNDS.remove(deps.storage, msg.id);
+    let response = match msg.result {
+        SubMsgResult::Ok(response) => response,
+        SubMsgResult::Err(_) => return credit_refund(deps.storage, refund),
+    };
+    let data = response.data.unwrap_or_default();
+    let sequence = MsgTransferResponse::decode(data.as_slice())
+        .map_err(|err| StdError::parse_err("MsgTransferResponse", err))?
+        .sequence;
+    IBC_REFUNDS.save(deps.storage, (&refund.channel_id, sequence), &refund)?;
+    Ok(Response::new())
+}
+
+/// Settles an IBC refund once ibc-hooks reports its outcome. ICS-20 returns the funds of a failed
+/// or timed out transfer to the marketplace, which credits them to the bidder's `nft_recipient`
+/// to withdraw locally.
+pub fn ibc_lifecycle_complete(
+    deps: DepsMut,
+    msg: IbcLifecycleComplete,
+) -> Result<Response, ContractError> {
+    let (channel, sequence, delivered) = match msg {
+        IbcLifecycleComplete::IbcAck {
+            channel,
+            sequence,
+            success,
+            ..
+        } => (channel, sequence, success),
+        IbcLifecycleComplete::IbcTimeout { channel, sequence } => (channel, sequence, false),
+    };
+    let refund = IBC_REFUNDS.load(deps.storage, (&channel, sequence))?;
+    IBC_REFUNDS.remove(deps.storage, (&channel, sequence));
+    if delivered {
+        return Ok(Response::new().add_attribute("action", "ibc_refund_delivered"));
+    }
+    credit_refund(deps.storage, refund)
+}
+
+/// Credits a refund that did not reach the origin chain to its local recipient.
+fn credit_refund(storage: &mut dyn Storage, refund: IbcRefund) -> Result<Response, ContractError> {
+    increase_balance(storage, &refund.recipient, &refund.amount)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "ibc_refund_credited")
+        .add_event(
+            AuctionEvent::RefundCredited {
+                auction_id: refund.auction_id,
+                recipient: refund.recipient,
+                amount: refund.amount,
+            }
+            .into(),
+        ))
+}
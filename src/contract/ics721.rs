@@ -0,0 +1,81 @@
+use crate::{
+    error::ContractError,
+    msg::{IbcOutgoingMsg, Ics721QueryMsg},
+    state::{load_config, load_nft_auction_state, Ics721Class, NFTAuctionState, ICS721_RECEIVERS},
+};
+use cosmwasm_std::{
+    attr, ensure, to_json_binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response, Uint128,
+    WasmMsg,
+};
+use cw721::Cw721ExecuteMsg;
+
+/// How long sending a bridged NFT back over ICS-721 may take before the transfer times out.
+pub const ICS721_TIMEOUT_SECONDS: u64 = 600;
+
+/// Looks up the ICS-721 class of `token_address` when it is a proxy collection of the configured
+/// ICS-721 contract.
+pub fn query_ics721_class(
+    deps: Deps,
+    token_address: &str,
+) -> Result<Option<Ics721Class>, ContractError> {
+    let Some(ics721) = load_config(deps.storage)?.ics721 else {
+        return Ok(None);
+    };
+    let class_id: Option<String> = deps.querier.query_wasm_smart(
+        &ics721,
+        &Ics721QueryMsg::ClassId {
+            contract: token_address.to_owned(),
+        },
+    )?;
+    // Received classes are prefixed with the port and channel they arrived on.
+    Ok(class_id.and_then(|class_id| {
+        let channel_id = class_id.split('/').nth(1)?.to_owned();
+        Some(Ics721Class {
+            ics721,
+            class_id,
+            channel_id,
+        })
+    }))
+}
+
+pub fn set_ics721_receiver(
+    deps: DepsMut,
+    info: MessageInfo,
+    auction_id: Uint128,
+    receiver: String,
+) -> Result<Response, ContractError> {
+    let auction = load_nft_auction_state(deps.storage, auction_id.u128())?;
+    ensure!(auction.ics721_class.is_some(), ContractError::NotBridged {});
+    ensure!(auction.is_open(), ContractError::AuctionAlreadyClaimed {});
+    ICS721_RECEIVERS.save(deps.storage, (auction_id.u128(), &info.sender), &receiver)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "set_ics721_receiver"),
+        attr("auction_id", auction_id),
+        attr("bidder", info.sender),
+        attr("receiver", receiver),
+    ]))
+}
+
+/// Sends the NFT of a settled bridged auction to `receiver` on its origin chain.
+pub fn send_back_msg(
+    env: &Env,
+    auction: &NFTAuctionState,
+    class: &Ics721Class,
+    receiver: String,
+) -> Result<CosmosMsg, ContractError> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: auction.token_address.clone(),
+        msg: to_json_binary(&Cw721ExecuteMsg::SendNft {
+            contract: class.ics721.to_string(),
+            token_id: auction.token_id.clone(),
+            msg: to_json_binary(&IbcOutgoingMsg {
+                receiver,
+                channel_id: class.channel_id.clone(),
+                timeout: env.block.time.plus_seconds(ICS721_TIMEOUT_SECONDS).into(),
+                memo: None,
+            })?,
+        })?,
+        funds: vec![],
+    }))
+}
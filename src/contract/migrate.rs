@@ -0,0 +1,64 @@
+use crate::{
+    contract::helper::{fetch_latest_auction_state_for_token, query_token_owner},
+    error::ContractError,
+    state::{
+        nft_auction_states, save_config, save_nft_auction_state, Config, NFTAuctionState, CONFIG,
+    },
+};
+use cosmwasm_std::{Deps, DepsMut, Env, Order, StdResult};
+
+/// Brings the state written by an earlier version up to date: writes a config when there is
+/// none and re-saves every auction so the start and end indexes cover it.
+///
+/// `from_baseline` is set when migrating from the first release, which kept no settled flag and
+/// left auctions untouched when they were claimed. Those are marked settled here.
+///
+/// Runs over every auction ever created, so its gas grows with the auction count.
+pub fn migrate_state(
+    deps: DepsMut,
+    env: &Env,
+    owner: Option<String>,
+    from_baseline: bool,
+) -> Result<(), ContractError> {
+    if CONFIG.may_load(deps.storage)?.is_none() {
+        let owner = owner.ok_or(ContractError::MigrationOwnerRequired {})?;
+        save_config(deps.storage, &Config::new(deps.api.addr_validate(&owner)?))?;
+    }
+
+    let auctions = nft_auction_states()
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (auction_id, mut auction) in auctions {
+        if from_baseline
+            && auction.is_open()
+            && !baseline_auction_is_pending(deps.as_ref(), env, &auction)?
+        {
+            auction.is_settled = true;
+        }
+        save_nft_auction_state(deps.storage, auction_id, auction)?;
+    }
+    Ok(())
+}
+
+/// A first-release auction is still pending only while it is the latest auction of its token
+/// and the marketplace still holds the NFT; otherwise it was claimed.
+fn baseline_auction_is_pending(
+    deps: Deps,
+    env: &Env,
+    auction: &NFTAuctionState,
+) -> Result<bool, ContractError> {
+    let latest = fetch_latest_auction_state_for_token(
+        deps.storage,
+        &auction.token_id,
+        &auction.token_address,
+    )?;
+    if latest.auction_id != auction.auction_id {
+        return Ok(false);
+    }
+    let owner = query_token_owner(
+        deps.querier,
+        auction.token_address.clone(),
+        auction.token_id.clone(),
+    );
+    Ok(owner.is_ok_and(|res| res.owner == env.contract.address))
+}
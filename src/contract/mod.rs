@@ -1,3 +1,10 @@
+pub mod cache;
+pub mod events;
 pub mod exec;
-mod helper;
+pub mod helper;
+pub mod ibc_refund;
+pub mod ics721;
+pub mod migrate;
 pub mod query;
+pub mod raffle;
+pub mod rental;
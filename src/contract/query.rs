@@ -1,11 +1,15 @@
 use crate::{
     error::ContractError,
     state::{
-        get_bids, load_nft_auction_state, read_auction_details, AuctionDetails, Bid,
-        NFTAuctionState, OrderBy,
+        get_bids, load_config, load_nft_auction_state, read_auction_details,
+        read_auctions_ending_soon, read_balances, read_exchange_rates, read_live_auctions,
+        read_locked_balances, read_operators, read_rentals, read_sale_stats, read_sales,
+        read_templates, read_ticket_holdings, read_upcoming_auctions, AuctionDetails, Bid, Config,
+        ExchangeRate, NFTAuctionState, NamedTemplate, Operator, OrderBy, PendingBundle, Raffle,
+        Rental, Sale, SaleStats, TicketHolding, PENDING_BUNDLES, RAFFLES, RENTALS,
     },
 };
-use cosmwasm_std::{Deps, Uint128};
+use cosmwasm_std::{Coin, Deps, Env, Timestamp, Uint128};
 
 pub fn get_auction_details(
     deps: Deps,
@@ -40,3 +44,168 @@ pub fn get_auction_state_by_id(
     let token_auction_state = load_nft_auction_state(deps.storage, auction_id.u128())?;
     Ok(token_auction_state)
 }
+
+pub fn get_auctions_ending_soon(
+    deps: Deps,
+    env: Env,
+    within_seconds: u64,
+    limit: Option<u64>,
+) -> Result<Vec<NFTAuctionState>, ContractError> {
+    // A window reaching past the end of time covers every remaining auction.
+    let until = within_seconds
+        .checked_mul(1_000_000_000)
+        .and_then(|nanos| env.block.time.nanos().checked_add(nanos))
+        .map_or(Timestamp::from_nanos(u64::MAX), Timestamp::from_nanos);
+    let auctions = read_auctions_ending_soon(deps.storage, env.block.time, until, limit)?;
+    Ok(auctions)
+}
+
+pub fn get_live_auctions(
+    deps: Deps,
+    env: Env,
+    start_after: Option<Uint128>,
+    limit: Option<u64>,
+) -> Result<Vec<NFTAuctionState>, ContractError> {
+    let auctions = read_live_auctions(deps.storage, env.block.time, start_after, limit)?;
+    Ok(auctions)
+}
+
+pub fn get_upcoming_auctions(
+    deps: Deps,
+    env: Env,
+    start_after: Option<Uint128>,
+    limit: Option<u64>,
+) -> Result<Vec<NFTAuctionState>, ContractError> {
+    let auctions = read_upcoming_auctions(deps.storage, env.block.time, start_after, limit)?;
+    Ok(auctions)
+}
+
+pub fn get_config(deps: Deps) -> Result<Config, ContractError> {
+    let config = load_config(deps.storage)?;
+    Ok(config)
+}
+
+pub fn get_exchange_rates(deps: Deps) -> Result<Vec<ExchangeRate>, ContractError> {
+    let rates = read_exchange_rates(deps.storage)?;
+    Ok(rates)
+}
+
+pub fn get_rental(
+    deps: Deps,
+    token_address: String,
+    token_id: String,
+) -> Result<Rental, ContractError> {
+    RENTALS
+        .may_load(deps.storage, (&token_address, &token_id))?
+        .ok_or(ContractError::RentalDoesNotExist {})
+}
+
+pub fn get_rentals(
+    deps: Deps,
+    start_after: Option<(String, String)>,
+    limit: Option<u64>,
+) -> Result<Vec<Rental>, ContractError> {
+    let rentals = read_rentals(deps.storage, start_after, limit)?;
+    Ok(rentals)
+}
+
+pub fn get_raffle(deps: Deps, raffle_id: Uint128) -> Result<Raffle, ContractError> {
+    RAFFLES
+        .may_load(deps.storage, raffle_id.u128())?
+        .ok_or(ContractError::RaffleDoesNotExist {})
+}
+
+pub fn get_raffle_tickets(
+    deps: Deps,
+    raffle_id: Uint128,
+    start_after: Option<String>,
+    limit: Option<u64>,
+) -> Result<Vec<TicketHolding>, ContractError> {
+    let start_after = start_after
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?;
+    let holdings =
+        read_ticket_holdings(deps.storage, raffle_id.u128(), start_after.as_ref(), limit)?;
+    Ok(holdings)
+}
+
+pub fn get_balance(deps: Deps, address: String) -> Result<Vec<Coin>, ContractError> {
+    let address = deps.api.addr_validate(&address)?;
+    let balances = read_balances(deps.storage, &address)?;
+    Ok(balances)
+}
+
+pub fn get_pending_bundle(deps: Deps, bundle_id: Uint128) -> Result<PendingBundle, ContractError> {
+    PENDING_BUNDLES
+        .may_load(deps.storage, bundle_id.u128())?
+        .ok_or(ContractError::BundleDoesNotExist {})
+}
+
+pub fn get_locked_balance(deps: Deps, address: String) -> Result<Vec<Coin>, ContractError> {
+    let address = deps.api.addr_validate(&address)?;
+    let balances = read_locked_balances(deps.storage, &address)?;
+    Ok(balances)
+}
+
+pub fn get_sales(
+    deps: Deps,
+    token_address: String,
+    start_after: Option<(Uint128, String)>,
+    limit: Option<u64>,
+    order_by: Option<OrderBy>,
+) -> Result<Vec<Sale>, ContractError> {
+    let sales = read_sales(
+        deps.storage,
+        &token_address,
+        start_after
+            .as_ref()
+            .map(|(auction_id, token_id)| (auction_id.u128(), token_id.as_str())),
+        limit,
+        order_by,
+    )?;
+    Ok(sales)
+}
+
+pub fn get_sale_stats(
+    deps: Deps,
+    token_address: String,
+    start_after: Option<String>,
+    limit: Option<u64>,
+) -> Result<Vec<SaleStats>, ContractError> {
+    let stats = read_sale_stats(deps.storage, &token_address, start_after, limit)?;
+    Ok(stats)
+}
+
+pub fn get_templates(
+    deps: Deps,
+    seller: String,
+    start_after: Option<String>,
+    limit: Option<u64>,
+) -> Result<Vec<NamedTemplate>, ContractError> {
+    let seller = deps.api.addr_validate(&seller)?;
+    let templates = read_templates(deps.storage, &seller, start_after.as_deref(), limit)?;
+    Ok(templates)
+}
+
+pub fn get_operators(
+    deps: Deps,
+    env: Env,
+    seller: String,
+    include_expired: Option<bool>,
+    start_after: Option<String>,
+    limit: Option<u64>,
+) -> Result<Vec<Operator>, ContractError> {
+    let seller = deps.api.addr_validate(&seller)?;
+    let start_after = start_after
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?;
+    let operators = read_operators(
+        deps.storage,
+        &env.block,
+        &seller,
+        include_expired.unwrap_or(false),
+        start_after.as_ref(),
+        limit,
+    )?;
+    Ok(operators)
+}
@@ -0,0 +1,369 @@
+use crate::{
+    contract::events::RaffleEvent,
+    contract::exec::validate_auction_time,
+    contract::helper::{checked_plus_seconds, fetch_and_update_next_auction_id, transfer_nft_msg},
+    error::ContractError,
+    state::{
+        load_config, record_sale, BundleItem, Raffle, RaffleDraw, RandomnessSource, RAFFLES,
+        RAFFLE_PURCHASES, RAFFLE_TICKETS,
+    },
+};
+use cosmwasm_std::{
+    coin, ensure, Addr, BankMsg, CosmosMsg, DepsMut, Env, MessageInfo, Order, Response, Storage,
+    Uint128,
+};
+use cw_storage_plus::Bound;
+use sha2::{Digest, Sha256};
+
+/// How long after the end of a raffle it can be drawn. Afterwards it can only be refunded, which
+/// is the fallback when a commit-reveal seller never reveals their secret.
+pub const DRAW_WINDOW_SECONDS: u64 = 7 * 86_400;
+
+/// Most addresses that can hold tickets of one raffle.
+pub const MAX_TICKET_HOLDERS: u32 = 1_000;
+
+/// Opens a raffle for an NFT sent through `Cw721ReceiveMsg`.
+#[allow(clippy::too_many_arguments)]
+pub fn start_raffle(
+    deps: DepsMut,
+    env: Env,
+    sender: String,
+    token_id: String,
+    token_address: String,
+    start_time: u64,
+    duration: u64,
+    denom: String,
+    ticket_price: Uint128,
+    max_tickets: u32,
+    max_tickets_per_address: Option<u32>,
+    randomness_source: RandomnessSource,
+) -> Result<Response, ContractError> {
+    let (start, end) = validate_auction_time(&env, start_time, duration)?;
+    ensure!(
+        !ticket_price.is_zero(),
+        ContractError::InvalidRaffle {
+            msg: "Tickets must have a price".to_string(),
+        }
+    );
+    ensure!(
+        max_tickets > 0 && max_tickets_per_address != Some(0),
+        ContractError::InvalidRaffle {
+            msg: "Ticket caps must be greater than zero".to_string(),
+        }
+    );
+    match &randomness_source {
+        RandomnessSource::CommitReveal { commitment } => ensure!(
+            decode_hash(commitment).is_some(),
+            ContractError::InvalidRaffle {
+                msg: "Commitment must be a hex-encoded sha256 hash".to_string(),
+            }
+        ),
+        RandomnessSource::Beacon {} => ensure!(
+            load_config(deps.storage)?.randomness_beacon.is_some(),
+            ContractError::InvalidRaffle {
+                msg: "No randomness beacon is configured".to_string(),
+            }
+        ),
+    }
+    let owner = deps.api.addr_validate(&sender)?;
+    let raffle_id = fetch_and_update_next_auction_id(deps.storage)?;
+    let raffle = Raffle {
+        raffle_id,
+        owner,
+        token_address,
+        token_id,
+        start,
+        end,
+        denom,
+        ticket_price,
+        max_tickets,
+        max_tickets_per_address,
+        randomness_source,
+        tickets_sold: 0,
+        ticket_holders: 0,
+        draw: None,
+        is_refunded: false,
+    };
+    RAFFLES.save(deps.storage, raffle_id.u128(), &raffle)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "start_raffle")
+        .add_event(RaffleEvent::Created { raffle }.into()))
+}
+
+pub fn buy_tickets(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    raffle_id: Uint128,
+    count: u32,
+) -> Result<Response, ContractError> {
+    let mut raffle = RAFFLES
+        .may_load(deps.storage, raffle_id.u128())?
+        .ok_or(ContractError::RaffleDoesNotExist {})?;
+    ensure!(
+        raffle.is_open() && raffle.start <= env.block.time && env.block.time < raffle.end,
+        ContractError::RaffleNotOpen {}
+    );
+    // The seller of a commit-reveal raffle knows the outcome in advance.
+    ensure!(
+        info.sender != raffle.owner,
+        ContractError::InvalidRaffle {
+            msg: "Owners cannot buy tickets for their own raffle".to_string(),
+        }
+    );
+    ensure!(
+        count > 0,
+        ContractError::InvalidRaffle {
+            msg: "Buy at least one ticket".to_string(),
+        }
+    );
+    let held = RAFFLE_TICKETS
+        .may_load(deps.storage, (raffle_id.u128(), &info.sender))?
+        .unwrap_or_default();
+    if held == 0 {
+        ensure!(
+            raffle.ticket_holders < MAX_TICKET_HOLDERS,
+            ContractError::TicketHolderCapExceeded {}
+        );
+        raffle.ticket_holders += 1;
+    }
+    let tickets_sold = raffle
+        .tickets_sold
+        .checked_add(count)
+        .filter(|sold| *sold <= raffle.max_tickets)
+        .ok_or(ContractError::TicketCapExceeded {})?;
+    let held = held
+        .checked_add(count)
+        .filter(|held| {
+            raffle
+                .max_tickets_per_address
+                .is_none_or(|cap| *held <= cap)
+        })
+        .ok_or(ContractError::TicketCapExceeded {})?;
+    let price = raffle.ticket_price.checked_mul(count.into())?;
+    let paid = match info.funds.as_slice() {
+        [coin] if coin.denom == raffle.denom => coin.amount,
+        _ => Uint128::zero(),
+    };
+    ensure!(
+        paid == price,
+        ContractError::InvalidFunds {
+            msg: format!("{} tickets cost exactly {}{}", count, price, raffle.denom),
+        }
+    );
+
+    RAFFLE_PURCHASES.save(
+        deps.storage,
+        (raffle_id.u128(), raffle.tickets_sold),
+        &info.sender,
+    )?;
+    raffle.tickets_sold = tickets_sold;
+    RAFFLES.save(deps.storage, raffle_id.u128(), &raffle)?;
+    RAFFLE_TICKETS.save(deps.storage, (raffle_id.u128(), &info.sender), &held)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "buy_tickets")
+        .add_event(
+            RaffleEvent::TicketsBought {
+                raffle,
+                buyer: info.sender,
+                tickets: count,
+                tickets_held: held,
+            }
+            .into(),
+        ))
+}
+
+/// Draws the winner of an ended raffle, sending them the NFT and the ticket proceeds to the
+/// owner. `randomness` is the seller's revealed secret for commit-reveal raffles, or the
+/// beacon's 32 random bytes, hex-encoded. A raffle without tickets returns the NFT to its owner
+/// and needs no randomness.
+pub fn draw_raffle(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    raffle_id: Uint128,
+    randomness: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut raffle = RAFFLES
+        .may_load(deps.storage, raffle_id.u128())?
+        .ok_or(ContractError::RaffleDoesNotExist {})?;
+    ensure!(raffle.is_open(), ContractError::RaffleClosed {});
+    ensure!(
+        raffle.end <= env.block.time,
+        ContractError::RaffleNotEnded {}
+    );
+    // Past the deadline the raffle belongs to the refund path, so a late reveal cannot race it.
+    ensure!(
+        env.block.time < checked_plus_seconds(raffle.end, DRAW_WINDOW_SECONDS)?,
+        ContractError::DrawWindowClosed {}
+    );
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let draw = if raffle.tickets_sold == 0 {
+        RaffleDraw {
+            randomness: String::new(),
+            winning_ticket: None,
+            winner: raffle.owner.clone(),
+        }
+    } else {
+        let randomness = randomness.ok_or(ContractError::InvalidRandomness {})?;
+        let bytes = hex::decode(&randomness).map_err(|_| ContractError::InvalidRandomness {})?;
+        match &raffle.randomness_source {
+            RandomnessSource::CommitReveal { commitment } => ensure!(
+                decode_hash(commitment) == Some(Sha256::digest(&bytes).into()),
+                ContractError::InvalidRandomness {}
+            ),
+            RandomnessSource::Beacon {} => {
+                let config = load_config(deps.storage)?;
+                ensure!(
+                    config.randomness_beacon.as_ref() == Some(&info.sender),
+                    ContractError::Unauthorized {}
+                );
+                ensure!(bytes.len() == 32, ContractError::InvalidRandomness {});
+            }
+        }
+        let seed: [u8; 32] = Sha256::new()
+            .chain_update(&bytes)
+            .chain_update(raffle_id.u128().to_be_bytes())
+            .finalize()
+            .into();
+        let winning_ticket = (u128::from_be_bytes(seed[..16].try_into().unwrap())
+            % u128::from(raffle.tickets_sold)) as u32;
+        let winner = ticket_holder(deps.as_ref().storage, raffle_id.u128(), winning_ticket)?;
+
+        let proceeds = coin(
+            raffle
+                .ticket_price
+                .checked_mul(raffle.tickets_sold.into())?
+                .u128(),
+            &raffle.denom,
+        );
+        record_sale(
+            deps.storage,
+            &[BundleItem {
+                token_address: raffle.token_address.clone(),
+                token_id: raffle.token_id.clone(),
+            }],
+            raffle_id,
+            proceeds.clone(),
+            env.block.time,
+            &winner,
+            &raffle.owner,
+        )?;
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: raffle.owner.to_string(),
+            amount: vec![proceeds],
+        }));
+        RaffleDraw {
+            randomness,
+            winning_ticket: Some(winning_ticket),
+            winner,
+        }
+    };
+    messages.push(transfer_nft_msg(
+        raffle.token_address.clone(),
+        raffle.token_id.clone(),
+        draw.winner.to_string(),
+    )?);
+    raffle.draw = Some(draw);
+    RAFFLES.save(deps.storage, raffle_id.u128(), &raffle)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "draw_raffle")
+        .add_messages(messages)
+        .add_event(RaffleEvent::Drawn { raffle }.into()))
+}
+
+/// Closes a raffle that was not drawn within `DRAW_WINDOW_SECONDS` of its end, e.g. because the
+/// seller never revealed their secret, and returns the NFT to its owner. Each ticket holder then
+/// claims their own refund, so closing costs the same however many tickets were sold.
+pub fn refund_raffle(
+    deps: DepsMut,
+    env: Env,
+    raffle_id: Uint128,
+) -> Result<Response, ContractError> {
+    let mut raffle = RAFFLES
+        .may_load(deps.storage, raffle_id.u128())?
+        .ok_or(ContractError::RaffleDoesNotExist {})?;
+    ensure!(raffle.is_open(), ContractError::RaffleClosed {});
+    ensure!(
+        checked_plus_seconds(raffle.end, DRAW_WINDOW_SECONDS)? <= env.block.time,
+        ContractError::DrawWindowOpen {}
+    );
+
+    let message = transfer_nft_msg(
+        raffle.token_address.clone(),
+        raffle.token_id.clone(),
+        raffle.owner.to_string(),
+    )?;
+    raffle.is_refunded = true;
+    RAFFLES.save(deps.storage, raffle_id.u128(), &raffle)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "refund_raffle")
+        .add_message(message)
+        .add_event(RaffleEvent::Refunded { raffle }.into()))
+}
+
+/// Pays the sender back for the tickets they hold in a refunded raffle.
+pub fn claim_raffle_refund(
+    deps: DepsMut,
+    info: MessageInfo,
+    raffle_id: Uint128,
+) -> Result<Response, ContractError> {
+    let raffle = RAFFLES
+        .may_load(deps.storage, raffle_id.u128())?
+        .ok_or(ContractError::RaffleDoesNotExist {})?;
+    ensure!(raffle.is_refunded, ContractError::RaffleNotRefunded {});
+    let key = (raffle_id.u128(), &info.sender);
+    let tickets = RAFFLE_TICKETS
+        .may_load(deps.storage, key)?
+        .ok_or(ContractError::NoTicketsToRefund {})?;
+    RAFFLE_TICKETS.remove(deps.storage, key);
+    let amount = coin(
+        raffle.ticket_price.checked_mul(tickets.into())?.u128(),
+        &raffle.denom,
+    );
+
+    Ok(Response::new()
+        .add_attribute("action", "claim_raffle_refund")
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![amount.clone()],
+        })
+        .add_event(
+            RaffleEvent::RefundClaimed {
+                raffle_id,
+                holder: info.sender,
+                tickets,
+                amount,
+            }
+            .into(),
+        ))
+}
+
+/// Finds the holder of `ticket`: the buyer of the last purchase starting at or before it.
+fn ticket_holder(
+    storage: &dyn Storage,
+    raffle_id: u128,
+    ticket: u32,
+) -> Result<Addr, ContractError> {
+    RAFFLE_PURCHASES
+        .prefix(raffle_id)
+        .range(
+            storage,
+            None,
+            Some(Bound::inclusive(ticket)),
+            Order::Descending,
+        )
+        .next()
+        .transpose()?
+        .map(|(_, buyer)| buyer)
+        .ok_or(ContractError::InvalidRandomness {})
+}
+
+fn decode_hash(hash: &str) -> Option<[u8; 32]> {
+    hex::decode(hash).ok()?.try_into().ok()
+}
@@ -0,0 +1,224 @@
+use crate::{
+    contract::events::RentalEvent,
+    contract::helper::transfer_nft_msg,
+    error::ContractError,
+    state::{Rental, RENTALS},
+};
+use cosmwasm_std::{
+    coins, ensure, Addr, BankMsg, CosmosMsg, DepsMut, Env, MessageInfo, Response, Uint128,
+};
+
+const SECONDS_PER_DAY: u64 = 86_400;
+/// Longest rental period a listing may offer.
+pub const MAX_RENTAL_DAYS: u64 = 365;
+
+/// Lists an NFT sent through `Cw721ReceiveMsg` for rent. The marketplace keeps it in escrow
+/// while nobody rents it.
+#[allow(clippy::too_many_arguments)]
+pub fn list_for_rent(
+    deps: DepsMut,
+    sender: String,
+    token_id: String,
+    token_address: String,
+    denom: String,
+    price_per_day: Uint128,
+    collateral: Uint128,
+    max_days: u64,
+) -> Result<Response, ContractError> {
+    ensure!(
+        max_days > 0 && max_days <= MAX_RENTAL_DAYS,
+        ContractError::InvalidRental {
+            msg: format!("max_days must be between 1 and {MAX_RENTAL_DAYS}"),
+        }
+    );
+    // The collateral is all that gets the owner anything back if the NFT is never returned.
+    ensure!(
+        !collateral.is_zero(),
+        ContractError::InvalidRental {
+            msg: "Rentals require collateral".to_string(),
+        }
+    );
+    ensure!(
+        !RENTALS.has(deps.storage, (&token_address, &token_id)),
+        ContractError::AlreadyRented {}
+    );
+    let owner = deps.api.addr_validate(&sender)?;
+    let rental = Rental {
+        owner,
+        token_address,
+        token_id,
+        denom,
+        price_per_day,
+        collateral,
+        max_days,
+        renter: None,
+        expires: None,
+    };
+    RENTALS.save(
+        deps.storage,
+        (&rental.token_address, &rental.token_id),
+        &rental,
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "list_rental")
+        .add_event(RentalEvent::Listed { rental }.into()))
+}
+
+/// Rents a listed NFT for `days` days. The fee is paid to the owner straight away, the collateral
+/// stays with the marketplace until the NFT comes back.
+pub fn rent(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_address: String,
+    token_id: String,
+    days: u64,
+) -> Result<Response, ContractError> {
+    let mut rental = RENTALS
+        .may_load(deps.storage, (&token_address, &token_id))?
+        .ok_or(ContractError::RentalDoesNotExist {})?;
+    ensure!(!rental.is_rented(), ContractError::AlreadyRented {});
+    ensure!(
+        info.sender != rental.owner,
+        ContractError::InvalidRental {
+            msg: "Owners cannot rent their own token".to_string(),
+        }
+    );
+    ensure!(
+        days > 0 && days <= rental.max_days,
+        ContractError::InvalidRental {
+            msg: format!("Rentals last between 1 and {} days", rental.max_days),
+        }
+    );
+    let fee = rental.price_per_day.checked_mul(days.into())?;
+    let total = fee.checked_add(rental.collateral)?;
+    let paid = match info.funds.as_slice() {
+        [] => Uint128::zero(),
+        [coin] if coin.denom == rental.denom => coin.amount,
+        _ => Uint128::MAX,
+    };
+    ensure!(
+        paid == total,
+        ContractError::InvalidFunds {
+            msg: format!("Renting requires exactly {}{}", total, rental.denom),
+        }
+    );
+
+    let expires = env.block.time.plus_seconds(days * SECONDS_PER_DAY);
+    rental.renter = Some(info.sender.clone());
+    rental.expires = Some(expires);
+    RENTALS.save(deps.storage, (&token_address, &token_id), &rental)?;
+
+    let mut messages = vec![transfer_nft_msg(
+        token_address.clone(),
+        token_id.clone(),
+        info.sender.to_string(),
+    )?];
+    if !fee.is_zero() {
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: rental.owner.to_string(),
+            amount: coins(fee.u128(), &rental.denom),
+        }));
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "rent")
+        .add_messages(messages)
+        .add_event(RentalEvent::Rented { rental, fee }.into()))
+}
+
+/// Takes back a rented NFT sent through `Cw721ReceiveMsg` and refunds the renter's collateral.
+/// Whoever holds the NFT may send it back, since the renter may have passed it on; the
+/// collateral still goes to the renter who paid it. The NFT stays listed for the next renter.
+pub fn return_rental(
+    deps: DepsMut,
+    env: Env,
+    sender: String,
+    token_id: String,
+    token_address: String,
+) -> Result<Response, ContractError> {
+    let mut rental = RENTALS
+        .may_load(deps.storage, (&token_address, &token_id))?
+        .ok_or(ContractError::RentalDoesNotExist {})?;
+    let renter = rental.renter.take().ok_or(ContractError::NotRented {})?;
+    ensure!(
+        rental
+            .expires
+            .take()
+            .is_some_and(|expires| env.block.time < expires),
+        ContractError::RentalExpired {}
+    );
+    RENTALS.save(deps.storage, (&token_address, &token_id), &rental)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "return_rental")
+        .add_messages(collateral_msg(&rental, &renter))
+        .add_event(
+            RentalEvent::Returned {
+                rental,
+                renter,
+                returned_by: sender,
+            }
+            .into(),
+        ))
+}
+
+/// Pays the collateral of a rental that expired without the NFT being returned to the owner and
+/// closes the listing.
+pub fn claim_collateral(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_address: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let rental = RENTALS
+        .may_load(deps.storage, (&token_address, &token_id))?
+        .ok_or(ContractError::RentalDoesNotExist {})?;
+    ensure!(info.sender == rental.owner, ContractError::Unauthorized {});
+    let expires = rental.expires.ok_or(ContractError::NotRented {})?;
+    ensure!(
+        env.block.time >= expires,
+        ContractError::RentalNotExpired {}
+    );
+    RENTALS.remove(deps.storage, (&token_address, &token_id));
+
+    Ok(Response::new()
+        .add_attribute("action", "claim_collateral")
+        .add_messages(collateral_msg(&rental, &rental.owner))
+        .add_event(RentalEvent::CollateralClaimed { rental }.into()))
+}
+
+/// Returns an NFT that is not currently rented to its owner.
+pub fn delist_rental(
+    deps: DepsMut,
+    info: MessageInfo,
+    token_address: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let rental = RENTALS
+        .may_load(deps.storage, (&token_address, &token_id))?
+        .ok_or(ContractError::RentalDoesNotExist {})?;
+    ensure!(info.sender == rental.owner, ContractError::Unauthorized {});
+    ensure!(!rental.is_rented(), ContractError::AlreadyRented {});
+    RENTALS.remove(deps.storage, (&token_address, &token_id));
+
+    Ok(Response::new()
+        .add_attribute("action", "delist_rental")
+        .add_message(transfer_nft_msg(
+            token_address,
+            token_id,
+            info.sender.to_string(),
+        )?)
+        .add_event(RentalEvent::Delisted { rental }.into()))
+}
+
+fn collateral_msg(rental: &Rental, recipient: &Addr) -> Option<CosmosMsg> {
+    (!rental.collateral.is_zero()).then(|| {
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: coins(rental.collateral.u128(), &rental.denom),
+        })
+    })
+}
@@ -46,6 +46,156 @@ pub enum ContractError {
 
     #[error("Token owner cannot bid")]
     TokenOwnerCannotBid {},
+
+    #[error("Insufficient balance")]
+    InsufficientBalance {},
+
+    #[error("Basis points must not exceed 10000")]
+    InvalidBasisPoints {},
+
+    #[error("Bundle does not exist")]
+    BundleDoesNotExist {},
+
+    #[error("Invalid bundle: {msg}")]
+    InvalidBundle { msg: String },
+
+    #[error("Token is not part of the bundle or already escrowed")]
+    TokenNotInBundle {},
+
+    #[error("Bid smaller than minimum bid")]
+    BidSmallerThanMinimumBid {},
+
+    #[error("Auction already has bids")]
+    AuctionHasBids {},
+
+    #[error("Auction cannot be cancelled once it has bids")]
+    CancelNotAllowed {},
+
+    #[error("Bidder is not allowed to bid on this auction")]
+    BidderNotAllowed {},
+
+    #[error("Invalid Merkle root")]
+    InvalidMerkleRoot {},
+
+    #[error("Invalid Merkle proof")]
+    InvalidMerkleProof {},
+
+    #[error("Bid exceeds the bidder's allowlist allowance")]
+    BidExceedsAllowance {},
+
+    #[error("Bid increment must be greater than zero")]
+    InvalidBidIncrement {},
+
+    #[error("Cannot migrate from contract {contract}")]
+    CannotMigrate { contract: String },
+
+    #[error("Migrating a contract without a config requires an owner")]
+    MigrationOwnerRequired {},
+
+    #[error("No exchange rate set for {denom}")]
+    MissingExchangeRate { denom: String },
+
+    #[error("Invalid accepted denoms: {msg}")]
+    InvalidAcceptedDenoms { msg: String },
+
+    #[error("Proxy bids are not supported on multi-denom auctions")]
+    ProxyBidNotSupported {},
+
+    #[error("Rental does not exist")]
+    RentalDoesNotExist {},
+
+    #[error("Token is already rented")]
+    AlreadyRented {},
+
+    #[error("Token is not rented")]
+    NotRented {},
+
+    #[error("Rental expired")]
+    RentalExpired {},
+
+    #[error("Rental not expired")]
+    RentalNotExpired {},
+
+    #[error("Invalid rental: {msg}")]
+    InvalidRental { msg: String },
+
+    #[error("Raffle does not exist")]
+    RaffleDoesNotExist {},
+
+    #[error("Invalid raffle: {msg}")]
+    InvalidRaffle { msg: String },
+
+    #[error("Raffle is not selling tickets")]
+    RaffleNotOpen {},
+
+    #[error("Raffle already drawn or refunded")]
+    RaffleClosed {},
+
+    #[error("Raffle not ended")]
+    RaffleNotEnded {},
+
+    #[error("Not enough tickets left")]
+    TicketCapExceeded {},
+
+    #[error("Raffle has reached its maximum number of ticket holders")]
+    TicketHolderCapExceeded {},
+
+    #[error("Invalid randomness")]
+    InvalidRandomness {},
+
+    #[error("Raffle can only be refunded once its draw window has passed")]
+    DrawWindowOpen {},
+
+    #[error("Raffle can no longer be drawn, its draw window has passed")]
+    DrawWindowClosed {},
+
+    #[error("Raffle was not refunded")]
+    RaffleNotRefunded {},
+
+    #[error("No tickets to refund")]
+    NoTicketsToRefund {},
+
+    #[error("Invalid lazy mint: {msg}")]
+    InvalidLazyMint { msg: String },
+
+    #[error("The marketplace is not approved to transfer the token")]
+    NftNotApproved {},
+
+    #[error("Token is already being auctioned")]
+    AuctionAlreadyOpen {},
+
+    #[error("The sender is not the ibc-hooks address of the given IBC origin")]
+    InvalidIbcOrigin {},
+
+    #[error("The auction's NFT was not bridged over ICS-721")]
+    NotBridged {},
+
+    #[error("The NFT of this auction cannot be claimed")]
+    NftNotClaimable {},
+
+    #[error("Unexpected reply to submessage {id}")]
+    UnexpectedReply { id: u64 },
+
+    #[error("Auction template does not exist")]
+    TemplateDoesNotExist {},
+
+    #[error("The seller no longer holds the NFT or approves the marketplace to transfer it")]
+    SellerNoLongerHoldsNft {},
+
+    #[error("Operator is not approved")]
+    OperatorNotApproved {},
+
+    #[error("Invalid auction template: {msg}")]
+    InvalidTemplate { msg: String },
+
+    #[error("Bid smaller than the reserve price needed to start the auction")]
+    BidSmallerThanReservePrice {},
+
+    #[error("The exchange rate of {denom} is used by open auctions")]
+    ExchangeRateInUse { denom: String },
+
+    #[error("Keeper reward must not exceed {max} basis points")]
+    KeeperRewardTooHigh { max: u64 },
 }
 
 impl From<OverflowError> for ContractError {
@@ -1,34 +1,60 @@
 mod contract;
 mod error;
+pub mod merkle;
 #[cfg(test)]
 pub mod mock;
 pub mod msg;
 mod state;
 mod testing;
 use cosmwasm_std::{
-    entry_point, to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, Uint128,
+    ensure, entry_point, to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response,
+    Uint128,
 };
+use cw2::set_contract_version;
 use {
     contract::{
         exec::{
-            cancel_auction_and_refund, finalize_auction_and_transfer_assets,
-            handle_cw721_auction_start, submit_bid_for_auction,
+            approve_operator, cancel_auction_and_refund, cancel_bundle, claim_nft,
+            create_approved_auction, create_bundle_auction, create_lazy_auction, delete_template,
+            deposit_balance, escrow_bundle_items, finalize_auction_and_transfer_assets,
+            finalize_expired, finalize_many, handle_cw721_auction_start, handle_delivery_reply,
+            revoke_operator, save_template, set_exchange_rates, submit_bid_for_auction,
+            update_auction, update_config, withdraw_balance,
         },
-        query::{get_auction_details, get_auction_state_by_id, get_bids_for_auction},
+        helper::IBC_REFUND_REPLY_FLAG,
+        ibc_refund::{handle_ibc_refund_reply, ibc_lifecycle_complete},
+        ics721::set_ics721_receiver,
+        migrate::migrate_state,
+        query::{
+            get_auction_details, get_auction_state_by_id, get_auctions_ending_soon, get_balance,
+            get_bids_for_auction, get_config, get_exchange_rates, get_live_auctions,
+            get_locked_balance, get_operators, get_pending_bundle, get_raffle, get_raffle_tickets,
+            get_rental, get_rentals, get_sale_stats, get_sales, get_templates,
+            get_upcoming_auctions,
+        },
+        raffle::{buy_tickets, claim_raffle_refund, draw_raffle, refund_raffle},
+        rental::{claim_collateral, delist_rental, rent},
     },
     error::ContractError,
-    msg::{ExecuteMsg, InstantiateMsg, QueryMsg},
-    state::save_next_auction_id,
+    msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, SudoMsg},
+    state::{load_next_auction_id, save_config, save_next_auction_id, Config},
 };
 
+const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Version reported for the first release, which did not record one.
+const BASELINE_VERSION: &str = "0.0.0";
+
 #[entry_point]
 pub fn instantiate(
     deps: DepsMut,
     _env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     _msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     save_next_auction_id(deps.storage, Uint128::from(1u128))?;
+    save_config(deps.storage, &Config::new(info.sender))?;
     Ok(Response::new())
 }
 
@@ -44,7 +70,19 @@ pub fn execute(
         ExecuteMsg::SubmitBid {
             token_id,
             token_address,
-        } => submit_bid_for_auction(deps, env, info, token_id, token_address),
+            proof,
+            max_amount,
+            ibc_origin,
+        } => submit_bid_for_auction(
+            deps,
+            env,
+            info,
+            token_id,
+            token_address,
+            proof,
+            max_amount,
+            ibc_origin,
+        ),
         ExecuteMsg::CancelAuctionAndRefund {
             token_id,
             token_address,
@@ -53,11 +91,207 @@ pub fn execute(
             token_id,
             token_address,
         } => finalize_auction_and_transfer_assets(deps, env, info, token_id, token_address),
+        ExecuteMsg::FinalizeMany { auction_ids } => finalize_many(deps, env, info, auction_ids),
+        ExecuteMsg::FinalizeExpired { start_after, limit } => {
+            finalize_expired(deps, env, info, start_after, limit)
+        }
+        ExecuteMsg::UpdateConfig {
+            owner,
+            keeper_reward_bps,
+            keeper_fee,
+            cancel_policy,
+            listing_deposit,
+            bid_increment,
+            randomness_beacon,
+            ics721,
+        } => update_config(
+            deps,
+            info,
+            owner,
+            keeper_reward_bps,
+            keeper_fee,
+            cancel_policy,
+            listing_deposit,
+            bid_increment,
+            randomness_beacon,
+            ics721,
+        ),
+        ExecuteMsg::SetIcs721Receiver {
+            auction_id,
+            receiver,
+        } => set_ics721_receiver(deps, info, auction_id, receiver),
+        ExecuteMsg::ClaimNft { auction_id } => claim_nft(deps, env, auction_id),
+        ExecuteMsg::DepositBalance {} => deposit_balance(deps, info),
+        ExecuteMsg::WithdrawBalance { amount } => withdraw_balance(deps, info, amount),
+        ExecuteMsg::ApproveOperator { operator, expires } => {
+            approve_operator(deps, env, info, operator, expires)
+        }
+        ExecuteMsg::RevokeOperator { operator } => revoke_operator(deps, info, operator),
+        ExecuteMsg::SaveTemplate { name, params } => save_template(deps, info, name, params),
+        ExecuteMsg::DeleteTemplate { name } => delete_template(deps, info, name),
+        ExecuteMsg::CreateLazyAuction {
+            token_address,
+            token_id,
+            token_uri,
+            start_time,
+            duration,
+            coin_denomination,
+            min_bid,
+            reserve_price,
+            accepted_denoms,
+        } => create_lazy_auction(
+            deps,
+            env,
+            info,
+            token_address,
+            token_id,
+            token_uri,
+            start_time,
+            duration,
+            coin_denomination,
+            min_bid,
+            reserve_price,
+            accepted_denoms,
+        ),
+        ExecuteMsg::CreateApprovedAuction {
+            token_address,
+            token_id,
+            start_time,
+            duration,
+            coin_denomination,
+            min_bid,
+            reserve_price,
+            access,
+            accepted_denoms,
+        } => create_approved_auction(
+            deps,
+            env,
+            info,
+            token_address,
+            token_id,
+            start_time,
+            duration,
+            coin_denomination,
+            min_bid,
+            reserve_price,
+            access,
+            accepted_denoms,
+        ),
+        ExecuteMsg::CreateBundleAuction {
+            items,
+            start_time,
+            duration,
+            coin_denomination,
+            min_bid,
+            reserve_price,
+            accepted_denoms,
+        } => create_bundle_auction(
+            deps,
+            env,
+            info,
+            items,
+            start_time,
+            duration,
+            coin_denomination,
+            min_bid,
+            reserve_price,
+            accepted_denoms,
+        ),
+        ExecuteMsg::EscrowBundleItems { bundle_id } => {
+            escrow_bundle_items(deps, env, info, bundle_id)
+        }
+        ExecuteMsg::CancelBundle { bundle_id } => cancel_bundle(deps, info, bundle_id),
+        ExecuteMsg::UpdateAuction {
+            auction_id,
+            min_bid,
+            end,
+            reserve_price,
+            coin_denomination,
+        } => update_auction(
+            deps,
+            env,
+            info,
+            auction_id,
+            min_bid,
+            end,
+            reserve_price,
+            coin_denomination,
+        ),
+        ExecuteMsg::SetExchangeRates { rates } => set_exchange_rates(deps, info, rates),
+        ExecuteMsg::Rent {
+            token_address,
+            token_id,
+            days,
+        } => rent(deps, env, info, token_address, token_id, days),
+        ExecuteMsg::ClaimCollateral {
+            token_address,
+            token_id,
+        } => claim_collateral(deps, env, info, token_address, token_id),
+        ExecuteMsg::DelistRental {
+            token_address,
+            token_id,
+        } => delist_rental(deps, info, token_address, token_id),
+        ExecuteMsg::BuyTickets { raffle_id, count } => {
+            buy_tickets(deps, env, info, raffle_id, count)
+        }
+        ExecuteMsg::DrawRaffle {
+            raffle_id,
+            randomness,
+        } => draw_raffle(deps, env, info, raffle_id, randomness),
+        ExecuteMsg::RefundRaffle { raffle_id } => refund_raffle(deps, env, raffle_id),
+        ExecuteMsg::ClaimRaffleRefund { raffle_id } => claim_raffle_refund(deps, info, raffle_id),
+    }
+}
+
+/// NFT deliveries of settlements reply on error, with IDs from `delivery_reply_id`. IBC refunds
+/// always reply, with `IBC_REFUND_REPLY_FLAG` set in their IDs.
+#[entry_point]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    if msg.id & IBC_REFUND_REPLY_FLAG != 0 {
+        handle_ibc_refund_reply(deps, msg)
+    } else {
+        handle_delivery_reply(deps, msg)
+    }
+}
+
+/// ibc-hooks reports the outcome of IBC refunds here.
+#[entry_point]
+pub fn sudo(deps: DepsMut, _env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        SudoMsg::IbcLifecycleComplete(msg) => ibc_lifecycle_complete(deps, msg),
     }
 }
 
 #[entry_point]
-pub fn query(deps: Deps, _env: Env, msg: msg::QueryMsg) -> Result<Binary, ContractError> {
+pub fn migrate(mut deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    // The first release did not record a contract version.
+    let (previous_version, from_baseline) = match cw2::CONTRACT.may_load(deps.storage)? {
+        Some(previous) => {
+            ensure!(
+                previous.contract == CONTRACT_NAME,
+                ContractError::CannotMigrate {
+                    contract: previous.contract,
+                }
+            );
+            (previous.version, false)
+        }
+        None => {
+            load_next_auction_id(deps.storage).map_err(|_| ContractError::CannotMigrate {
+                contract: String::new(),
+            })?;
+            (BASELINE_VERSION.to_owned(), true)
+        }
+    };
+    migrate_state(deps.branch(), &env, msg.owner, from_baseline)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("previous_version", previous_version)
+        .add_attribute("version", CONTRACT_VERSION))
+}
+
+#[entry_point]
+pub fn query(deps: Deps, env: Env, msg: msg::QueryMsg) -> Result<Binary, ContractError> {
     match msg {
         QueryMsg::AuctionDetails {
             token_address,
@@ -86,5 +320,86 @@ pub fn query(deps: Deps, _env: Env, msg: msg::QueryMsg) -> Result<Binary, Contra
         QueryMsg::AuctionState { auction_id } => {
             to_json_binary(&get_auction_state_by_id(deps, auction_id)?).map_err(|err| err.into())
         }
+        QueryMsg::PendingBundle { bundle_id } => {
+            to_json_binary(&get_pending_bundle(deps, bundle_id)?).map_err(|err| err.into())
+        }
+        QueryMsg::Config {} => to_json_binary(&get_config(deps)?).map_err(|err| err.into()),
+        QueryMsg::Balance { address } => {
+            to_json_binary(&get_balance(deps, address)?).map_err(|err| err.into())
+        }
+        QueryMsg::LockedBalance { address } => {
+            to_json_binary(&get_locked_balance(deps, address)?).map_err(|err| err.into())
+        }
+        QueryMsg::EndingSoon {
+            within_seconds,
+            limit,
+        } => to_json_binary(&get_auctions_ending_soon(deps, env, within_seconds, limit)?)
+            .map_err(|err| err.into()),
+        QueryMsg::Live { start_after, limit } => {
+            to_json_binary(&get_live_auctions(deps, env, start_after, limit)?)
+                .map_err(|err| err.into())
+        }
+        QueryMsg::Upcoming { start_after, limit } => {
+            to_json_binary(&get_upcoming_auctions(deps, env, start_after, limit)?)
+                .map_err(|err| err.into())
+        }
+        QueryMsg::ExchangeRates {} => {
+            to_json_binary(&get_exchange_rates(deps)?).map_err(|err| err.into())
+        }
+        QueryMsg::Rental {
+            token_address,
+            token_id,
+        } => to_json_binary(&get_rental(deps, token_address, token_id)?).map_err(|err| err.into()),
+        QueryMsg::Rentals { start_after, limit } => {
+            to_json_binary(&get_rentals(deps, start_after, limit)?).map_err(|err| err.into())
+        }
+        QueryMsg::Raffle { raffle_id } => {
+            to_json_binary(&get_raffle(deps, raffle_id)?).map_err(|err| err.into())
+        }
+        QueryMsg::RaffleTickets {
+            raffle_id,
+            start_after,
+            limit,
+        } => to_json_binary(&get_raffle_tickets(deps, raffle_id, start_after, limit)?)
+            .map_err(|err| err.into()),
+        QueryMsg::Sales {
+            token_address,
+            start_after,
+            limit,
+            order_by,
+        } => to_json_binary(&get_sales(
+            deps,
+            token_address,
+            start_after,
+            limit,
+            order_by,
+        )?)
+        .map_err(|err| err.into()),
+        QueryMsg::Operators {
+            seller,
+            include_expired,
+            start_after,
+            limit,
+        } => to_json_binary(&get_operators(
+            deps,
+            env,
+            seller,
+            include_expired,
+            start_after,
+            limit,
+        )?)
+        .map_err(|err| err.into()),
+        QueryMsg::Templates {
+            seller,
+            start_after,
+            limit,
+        } => to_json_binary(&get_templates(deps, seller, start_after, limit)?)
+            .map_err(|err| err.into()),
+        QueryMsg::SaleStats {
+            token_address,
+            start_after,
+            limit,
+        } => to_json_binary(&get_sale_stats(deps, token_address, start_after, limit)?)
+            .map_err(|err| err.into()),
     }
 }
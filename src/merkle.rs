@@ -0,0 +1,53 @@
+use crate::error::ContractError;
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::Uint128;
+use sha2::{Digest, Sha256};
+
+/// A Merkle proof that an address belongs to an allowlist, optionally with a cap on the amount
+/// it may spend.
+#[cw_serde]
+pub struct MerkleProof {
+    /// The spending cap committed in the address's leaf, if any.
+    pub max_amount: Option<Uint128>,
+    /// Hex-encoded sibling hashes from the leaf up to the root.
+    pub proof: Vec<String>,
+}
+
+/// Hashes an allowlist leaf. Leaves without a cap are `sha256(address)`, capped leaves are
+/// `sha256("{address}:{max_amount}")`.
+pub fn leaf_hash(address: &str, max_amount: Option<Uint128>) -> [u8; 32] {
+    match max_amount {
+        None => Sha256::digest(address.as_bytes()).into(),
+        Some(max_amount) => Sha256::digest(format!("{address}:{max_amount}").as_bytes()).into(),
+    }
+}
+
+/// Hashes two sibling nodes in sorted order, so proofs don't need to carry left/right flags.
+pub fn hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    Sha256::new()
+        .chain_update(first)
+        .chain_update(second)
+        .finalize()
+        .into()
+}
+
+/// Decodes a hex-encoded root, failing unless it is a 32 byte sha256 hash.
+pub fn parse_root(root: &str) -> Result<[u8; 32], ContractError> {
+    decode_hash(root).ok_or(ContractError::InvalidMerkleRoot {})
+}
+
+fn decode_hash(hash: &str) -> Option<[u8; 32]> {
+    hex::decode(hash).ok()?.try_into().ok()
+}
+
+/// Returns whether `proof` shows that `address` is part of the allowlist committed in `root`.
+pub fn verify(root: &str, address: &str, proof: &MerkleProof) -> Result<bool, ContractError> {
+    let root = parse_root(root)?;
+    let mut hash = leaf_hash(address, proof.max_amount);
+    for sibling in proof.proof.iter() {
+        let sibling = decode_hash(sibling).ok_or(ContractError::InvalidMerkleProof {})?;
+        hash = hash_pair(&hash, &sibling);
+    }
+    Ok(hash == root)
+}
@@ -1,14 +1,24 @@
 use cosmwasm_std::{
     from_json,
     testing::{mock_env, MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR},
-    to_json_binary, Binary, Coin, ContractResult, OwnedDeps, Querier, QuerierResult, QueryRequest,
-    SystemError, SystemResult, WasmQuery,
+    to_json_binary, Binary, Coin, ContractInfoResponse, ContractResult, OwnedDeps, Querier,
+    QuerierResult, QueryRequest, SystemError, SystemResult, WasmQuery,
 };
-use cw721::{Cw721QueryMsg, OwnerOfResponse};
+use cw721::{Approval, Cw721QueryMsg, Expiration, OwnerOfResponse, TokensResponse};
+use std::collections::HashMap;
+
+use crate::msg::{Cw721MinterQueryMsg, Ics721QueryMsg, MinterResponse};
 
 pub const MOCK_TOKEN_ADDR: &str = "mock_token_addr";
 pub const MOCK_TOKEN_OWNER: &str = "mock_token_owner";
 pub const MOCK_UNCLAIMED_TOKEN: &str = "mock_unclaimed_token";
+pub const MOCK_TOKEN_HOLDER: &str = "mock_token_holder";
+/// A token owned by `MOCK_TOKEN_OWNER` that the marketplace is approved to transfer.
+pub const MOCK_APPROVED_TOKEN: &str = "mock_approved_token";
+/// ICS-721 contract whose proxy collection `MOCK_TOKEN_ADDR` is.
+pub const MOCK_ICS721_ADDR: &str = "mock_ics721_addr";
+/// A token of `MOCK_TOKEN_ADDR` that has not been minted yet.
+pub const MOCK_LAZY_TOKEN: &str = "mock_lazy_token";
 
 pub fn custom_mock_dependencies(
     contract_balance: &[Coin],
@@ -26,6 +36,11 @@ pub fn custom_mock_dependencies(
 
 pub struct WasmMockQuerier {
     base: MockQuerier,
+    /// The minter `MOCK_TOKEN_ADDR` reports, the marketplace by default.
+    pub minter: String,
+    /// Owners of `MOCK_TOKEN_ADDR` tokens that replace the ones below, e.g. to follow a token
+    /// through an ICS-721 transfer.
+    pub token_owners: HashMap<String, String>,
 }
 
 impl Querier for WasmMockQuerier {
@@ -49,17 +64,52 @@ impl WasmMockQuerier {
             QueryRequest::Wasm(WasmQuery::Smart { contract_addr, msg }) => {
                 match contract_addr.as_str() {
                     MOCK_TOKEN_ADDR => self.handle_token_query(msg),
+                    MOCK_ICS721_ADDR => self.handle_ics721_query(msg),
                     _ => panic!("Unknown Contract Address {}", contract_addr),
                 }
             }
+            QueryRequest::Wasm(WasmQuery::ContractInfo { contract_addr })
+                if contract_addr == MOCK_TOKEN_ADDR =>
+            {
+                let mut res = ContractInfoResponse::default();
+                res.code_id = 1;
+                res.creator = MOCK_TOKEN_OWNER.to_owned();
+                SystemResult::Ok(ContractResult::Ok(to_json_binary(&res).unwrap()))
+            }
             _ => self.base.handle_query(request),
         }
     }
 
     fn handle_token_query(&self, msg: &Binary) -> QuerierResult {
+        if let Ok(Cw721MinterQueryMsg::Minter {}) = from_json(msg) {
+            let res = MinterResponse {
+                minter: Some(self.minter.clone()),
+            };
+            return SystemResult::Ok(ContractResult::Ok(to_json_binary(&res).unwrap()));
+        }
         match from_json(msg).unwrap() {
+            Cw721QueryMsg::OwnerOf { token_id, .. } if token_id == MOCK_LAZY_TOKEN => {
+                SystemResult::Ok(ContractResult::Err("Token not found".to_owned()))
+            }
+            Cw721QueryMsg::OwnerOf { token_id, .. }
+                if self.token_owners.contains_key(&token_id) =>
+            {
+                let res = OwnerOfResponse {
+                    owner: self.token_owners[&token_id].clone(),
+                    approvals: vec![],
+                };
+                SystemResult::Ok(ContractResult::Ok(to_json_binary(&res).unwrap()))
+            }
             Cw721QueryMsg::OwnerOf { token_id, .. } => {
-                let res = if token_id == MOCK_UNCLAIMED_TOKEN {
+                let res = if token_id == MOCK_APPROVED_TOKEN {
+                    OwnerOfResponse {
+                        owner: MOCK_TOKEN_OWNER.to_owned(),
+                        approvals: vec![Approval {
+                            spender: MOCK_CONTRACT_ADDR.to_owned(),
+                            expires: Expiration::Never {},
+                        }],
+                    }
+                } else if token_id == MOCK_UNCLAIMED_TOKEN {
                     OwnerOfResponse {
                         owner: mock_env().contract.address.to_string(),
                         approvals: vec![],
@@ -72,12 +122,35 @@ impl WasmMockQuerier {
                 };
                 SystemResult::Ok(ContractResult::Ok(to_json_binary(&res).unwrap()))
             }
+            Cw721QueryMsg::Operator { .. } => {
+                SystemResult::Ok(ContractResult::Err("Approval not found".to_owned()))
+            }
+            Cw721QueryMsg::Tokens { owner, .. } => {
+                let tokens = if owner == MOCK_TOKEN_HOLDER {
+                    vec!["mock_held_token".to_owned()]
+                } else {
+                    vec![]
+                };
+                let res = TokensResponse { tokens };
+                SystemResult::Ok(ContractResult::Ok(to_json_binary(&res).unwrap()))
+            }
 
             _ => panic!("Unsupported Query"),
         }
     }
 
+    fn handle_ics721_query(&self, msg: &Binary) -> QuerierResult {
+        let Ics721QueryMsg::ClassId { contract } = from_json(msg).unwrap();
+        let class_id = (contract == MOCK_TOKEN_ADDR)
+            .then(|| format!("wasm.{MOCK_ICS721_ADDR}/channel-5/origin-class"));
+        SystemResult::Ok(ContractResult::Ok(to_json_binary(&class_id).unwrap()))
+    }
+
     pub fn new(base: MockQuerier<cosmwasm_std::Empty>) -> Self {
-        WasmMockQuerier { base }
+        WasmMockQuerier {
+            base,
+            minter: MOCK_CONTRACT_ADDR.to_owned(),
+            token_owners: HashMap::new(),
+        }
     }
 }
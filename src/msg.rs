@@ -1,17 +1,40 @@
-#[allow(unused_imports)]
-use crate::state::{AuctionDetails, Bid, NFTAuctionState, OrderBy};
+use crate::merkle::MerkleProof;
+use crate::state::{
+    AuctionDetails, AuctionTemplate, Bid, BidderAccess, BundleItem, CancelPolicy, Config,
+    ExchangeRate, IbcOrigin, NFTAuctionState, NamedTemplate, Operator, OrderBy, PendingBundle,
+    Raffle, RandomnessSource, Rental, Sale, SaleStats, TicketHolding,
+};
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::Uint128;
+use cosmwasm_std::{Coin, Empty, IbcTimeout, Uint128};
+use cw721::Expiration;
 
 #[cw_serde]
 pub struct InstantiateMsg {}
 
+#[cw_serde]
+pub struct MigrateMsg {
+    /// Owner of the marketplace config. Required when migrating from the first release, which
+    /// had no config; ignored otherwise.
+    pub owner: Option<String>,
+}
+
 #[cw_serde]
 pub enum ExecuteMsg {
+    /// Sent by the cw721 contract on `send_nft`, so it must keep the `receive_nft` wire name.
+    /// `auction_start`, its name before, is still accepted for clients that send it directly.
+    #[serde(rename = "receive_nft", alias = "auction_start")]
     AuctionStart(cw721::Cw721ReceiveMsg),
     SubmitBid {
         token_id: String,
         token_address: String,
+        /// Proves the bidder is in the auction's Merkle allowlist.
+        proof: Option<MerkleProof>,
+        /// Makes this a proxy bid: the attached funds, which must equal `max_amount`, are
+        /// escrowed and the contract only bids as much as needed to stay ahead.
+        max_amount: Option<Uint128>,
+        /// Set by bids made through an ibc-hooks ICS-20 memo. The sender must be the address
+        /// ibc-hooks derives for this origin; refunds are sent back to it over IBC.
+        ibc_origin: Option<IbcOrigin>,
     },
     CancelAuctionAndRefund {
         token_id: String,
@@ -21,12 +44,171 @@ pub enum ExecuteMsg {
         token_id: String,
         token_address: String,
     },
+    /// Settles each of the given auctions, skipping the ones that cannot be settled.
+    FinalizeMany {
+        auction_ids: Vec<Uint128>,
+    },
+    /// Settles up to `limit` ended auctions, oldest end time first. Auctions that cannot be
+    /// settled stay in the index, so `start_after`, the id of the last auction visited by the
+    /// previous call, lets keepers page past them.
+    FinalizeExpired {
+        start_after: Option<Uint128>,
+        limit: Option<u64>,
+    },
+    /// Updates the marketplace configuration. Only callable by the config owner.
+    /// A `keeper_fee` or `listing_deposit` with a zero amount disables it. `keeper_reward_bps` is
+    /// capped at 500 and only applies to auctions created afterwards.
+    UpdateConfig {
+        owner: Option<String>,
+        keeper_reward_bps: Option<u64>,
+        keeper_fee: Option<Coin>,
+        cancel_policy: Option<CancelPolicy>,
+        listing_deposit: Option<Coin>,
+        bid_increment: Option<Uint128>,
+        /// An empty address removes the beacon.
+        randomness_beacon: Option<String>,
+        /// An empty address removes the ICS-721 contract. Existing auctions keep theirs.
+        ics721: Option<String>,
+    },
+    /// Asks for the NFT of a bridged auction to be sent back over ICS-721 to `receiver` on its
+    /// origin chain if the sender wins. Can be changed until the auction is settled.
+    SetIcs721Receiver {
+        auction_id: Uint128,
+        receiver: String,
+    },
+    /// Delivers the NFTs of a settled auction whose transfer, mint or ICS-721 send-back failed
+    /// on settlement, or that ICS-721 returned after the IBC transfer failed or timed out.
+    ClaimNft {
+        auction_id: Uint128,
+    },
+    /// Credits the attached funds to the sender's balance, used to escrow seller fees.
+    DepositBalance {},
+    WithdrawBalance {
+        amount: Coin,
+    },
+    /// Lets `operator` cancel and update the sender's auctions until `expires`, which defaults
+    /// to never. Approving an operator again replaces its expiration.
+    ApproveOperator {
+        operator: String,
+        expires: Option<Expiration>,
+    },
+    /// Removes an operator approved by the sender. Fails if `operator` is not approved.
+    RevokeOperator {
+        operator: String,
+    },
+    /// Saves auction parameters under `name`, replacing any template of the sender with that
+    /// name. Names are 1 to 64 ASCII letters, digits, `-`, `_` or `.`.
+    SaveTemplate {
+        name: String,
+        params: AuctionTemplate,
+    },
+    DeleteTemplate {
+        name: String,
+    },
+    /// Creates a pending bundle lot. The auction opens once every item has been escrowed, either
+    /// by sending it with `Cw721CustomMsg::AddToBundle` or through `EscrowBundleItems`.
+    CreateBundleAuction {
+        items: Vec<BundleItem>,
+        start_time: u64,
+        duration: u64,
+        coin_denomination: String,
+        min_bid: Option<Uint128>,
+        reserve_price: Option<Uint128>,
+        accepted_denoms: Option<Vec<String>>,
+    },
+    /// Registers an auction for a token that is minted to the winner on settlement. The sender
+    /// must be the creator or admin of `token_address`, and the marketplace its minter.
+    CreateLazyAuction {
+        token_address: String,
+        token_id: String,
+        token_uri: Option<String>,
+        start_time: u64,
+        duration: u64,
+        coin_denomination: String,
+        min_bid: Option<Uint128>,
+        reserve_price: Option<Uint128>,
+        accepted_denoms: Option<Vec<String>>,
+    },
+    /// Auctions an NFT that stays in the sender's wallet until settlement. The sender must have
+    /// approved the marketplace for the token with `Approve`, or for all its tokens with
+    /// `ApproveAll`. If the NFT moves or the approval is revoked, bids are rejected and
+    /// finalizing invalidates the auction, even before its end, refunding the high bid.
+    CreateApprovedAuction {
+        token_address: String,
+        token_id: String,
+        start_time: u64,
+        duration: u64,
+        coin_denomination: String,
+        min_bid: Option<Uint128>,
+        reserve_price: Option<Uint128>,
+        access: Option<BidderAccess>,
+        accepted_denoms: Option<Vec<String>>,
+    },
+    /// Transfers the remaining bundle items the marketplace is approved for into escrow.
+    EscrowBundleItems {
+        bundle_id: Uint128,
+    },
+    /// Cancels a bundle that has not opened yet and returns the escrowed items.
+    CancelBundle {
+        bundle_id: Uint128,
+    },
+    /// Changes the terms of an auction. Only the seller can do this, and only before the
+    /// first bid. `end` is a unix timestamp in seconds. A zero `min_bid` or `reserve_price`
+    /// removes it.
+    UpdateAuction {
+        auction_id: Uint128,
+        min_bid: Option<Uint128>,
+        end: Option<u64>,
+        reserve_price: Option<Uint128>,
+        coin_denomination: Option<String>,
+    },
+    /// Sets the exchange rates multi-denom auctions rank bids with. A zero rate removes the
+    /// denom. Only the config owner can do this.
+    SetExchangeRates {
+        rates: Vec<ExchangeRate>,
+    },
+    /// Rents a listed NFT for `days` days. The fee and the collateral must be attached.
+    Rent {
+        token_address: String,
+        token_id: String,
+        days: u64,
+    },
+    /// Pays the collateral of a rental that was not returned in time to the NFT's owner.
+    ClaimCollateral {
+        token_address: String,
+        token_id: String,
+    },
+    /// Takes an NFT that is not currently rented off the rental market and returns it.
+    DelistRental {
+        token_address: String,
+        token_id: String,
+    },
+    /// Buys `count` tickets of a raffle. Their price must be attached.
+    BuyTickets {
+        raffle_id: Uint128,
+        count: u32,
+    },
+    /// Draws the winner of an ended raffle. `randomness` is the hex-encoded secret behind the
+    /// seller's commitment, or the beacon's 32 random bytes when sent by the beacon.
+    DrawRaffle {
+        raffle_id: Uint128,
+        randomness: Option<String>,
+    },
+    /// Closes a raffle that was not drawn in time and returns its NFT to the seller. Ticket holders
+    /// then claim their refunds with `ClaimRaffleRefund`.
+    RefundRaffle {
+        raffle_id: Uint128,
+    },
+    /// Pays the sender back for their tickets of a refunded raffle.
+    ClaimRaffleRefund {
+        raffle_id: Uint128,
+    },
 }
 
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
-    #[returns(AuctionDetails)]
+    #[returns(Vec<AuctionDetails>)]
     AuctionDetails {
         token_address: Option<String>,
         start_after: Option<String>,
@@ -41,14 +223,262 @@ pub enum QueryMsg {
         limit: Option<u64>,
         order_by: Option<OrderBy>,
     },
+    #[returns(PendingBundle)]
+    PendingBundle { bundle_id: Uint128 },
+    #[returns(Config)]
+    Config {},
+    /// Funds held for the address by the marketplace.
+    #[returns(Vec<Coin>)]
+    Balance { address: String },
+    /// Funds of the address escrowed for its live auctions.
+    #[returns(Vec<Coin>)]
+    LockedBalance { address: String },
+    /// Live auctions ending within the next `within_seconds`, soonest first.
+    #[returns(Vec<NFTAuctionState>)]
+    EndingSoon {
+        within_seconds: u64,
+        limit: Option<u64>,
+    },
+    /// Open auctions that are currently accepting bids, ordered by end time.
+    #[returns(Vec<NFTAuctionState>)]
+    Live {
+        start_after: Option<Uint128>,
+        limit: Option<u64>,
+    },
+    /// Open auctions that have not started yet, ordered by start time.
+    #[returns(Vec<NFTAuctionState>)]
+    Upcoming {
+        start_after: Option<Uint128>,
+        limit: Option<u64>,
+    },
+    #[returns(Vec<ExchangeRate>)]
+    ExchangeRates {},
+    #[returns(Rental)]
+    Rental {
+        token_address: String,
+        token_id: String,
+    },
+    /// Rental listings ordered by `(token_address, token_id)`.
+    #[returns(Vec<Rental>)]
+    Rentals {
+        start_after: Option<(String, String)>,
+        limit: Option<u64>,
+    },
+    /// The raffle, including its draw result once drawn.
+    #[returns(Raffle)]
+    Raffle { raffle_id: Uint128 },
+    /// Ticket holders of a raffle in address order.
+    #[returns(Vec<TicketHolding>)]
+    RaffleTickets {
+        raffle_id: Uint128,
+        start_after: Option<String>,
+        limit: Option<u64>,
+    },
+    /// Sold tokens of a collection by auction id and token id, oldest first unless `order_by`
+    /// is `Desc`. Pages continue after the `(auction_id, token_id)` of the last sale.
+    #[returns(Vec<Sale>)]
+    Sales {
+        token_address: String,
+        start_after: Option<(Uint128, String)>,
+        limit: Option<u64>,
+        order_by: Option<OrderBy>,
+    },
+    /// Operators of a seller in address order. Expired ones are only listed with
+    /// `include_expired`.
+    #[returns(Vec<Operator>)]
+    Operators {
+        seller: String,
+        include_expired: Option<bool>,
+        start_after: Option<String>,
+        limit: Option<u64>,
+    },
+    /// Auction templates of a seller in name order.
+    #[returns(Vec<NamedTemplate>)]
+    Templates {
+        seller: String,
+        start_after: Option<String>,
+        limit: Option<u64>,
+    },
+    /// Sale volume, count, last price and all-time high of a collection, per denom.
+    #[returns(Vec<SaleStats>)]
+    SaleStats {
+        token_address: String,
+        start_after: Option<String>,
+        limit: Option<u64>,
+    },
 }
 
 #[cw_serde]
 pub enum Cw721CustomMsg {
     InitializeCW721TokenAuction {
+        /// Absolute start time. Must be zero when `start_mode` is set.
         start_time: u64,
+        start_mode: Option<StartMode>,
         duration: u64,
         coin_denomination: String,
         min_bid: Option<Uint128>,
+        reserve_price: Option<Uint128>,
+        /// Makes the auction private to the bidders matching this rule.
+        access: Option<BidderAccess>,
+        /// Further denoms bids may be paid in. `min_bid` and `reserve_price` stay in
+        /// `coin_denomination` and every denom needs an exchange rate.
+        accepted_denoms: Option<Vec<String>>,
+    },
+    /// Auctions the sent NFT with the parameters of the seller's template `name`, replaced by
+    /// the `overrides` that are set.
+    StartFromTemplate {
+        name: String,
+        start_time: u64,
+        start_mode: Option<StartMode>,
+        overrides: Option<TemplateOverrides>,
+    },
+    AddToBundle {
+        bundle_id: Uint128,
+    },
+    /// Lists the sent NFT for rent. Renters pay `price_per_day` per day up front plus
+    /// `collateral`, both in `denom`, for at most `max_days`. The collateral cannot be zero.
+    ListForRent {
+        denom: String,
+        price_per_day: Uint128,
+        collateral: Uint128,
+        max_days: u64,
+    },
+    /// Returns a rented NFT before the rental expires, releasing the renter's collateral. Any
+    /// holder of the NFT may send it back.
+    ReturnRental {},
+    /// Raffles the sent NFT: between `start_time` and `start_time + duration` anyone may buy
+    /// tickets at `ticket_price`, and one ticket wins the NFT afterwards.
+    StartRaffle {
+        start_time: u64,
+        duration: u64,
+        denom: String,
+        ticket_price: Uint128,
+        max_tickets: u32,
+        max_tickets_per_address: Option<u32>,
+        randomness_source: RandomnessSource,
+    },
+}
+
+/// Starts an auction relative to the block that creates it, instead of at an absolute time.
+#[cw_serde]
+pub enum StartMode {
+    /// Bids are accepted straight away.
+    Now {},
+    /// Bids are accepted `seconds` after creation.
+    After { seconds: u64 },
+    /// Bids are accepted straight away, but the auction's `duration` only starts counting down
+    /// with the first bid that meets the reserve price, or the first bid if there is none.
+    OnFirstBid {},
+}
+
+/// Template parameters to replace when starting an auction from a template.
+#[cw_serde]
+#[derive(Default)]
+pub struct TemplateOverrides {
+    pub duration: Option<u64>,
+    pub coin_denomination: Option<String>,
+    pub min_bid: Option<Uint128>,
+    pub reserve_price: Option<Uint128>,
+    pub access: Option<BidderAccess>,
+    pub accepted_denoms: Option<Vec<String>>,
+    pub bid_increment: Option<Uint128>,
+    pub extension: Option<u64>,
+}
+
+/// The minting part of the cw721-base interface, which the `cw721` package does not cover.
+#[cw_serde]
+pub enum Cw721MinterExecuteMsg {
+    Mint {
+        token_id: String,
+        owner: String,
+        token_uri: Option<String>,
+        extension: Empty,
+    },
+}
+
+#[cw_serde]
+pub enum Cw721MinterQueryMsg {
+    Minter {},
+}
+
+#[cw_serde]
+pub struct MinterResponse {
+    pub minter: Option<String>,
+}
+
+/// Query of the ICS-721 contract, which answers with the class ID of a proxy collection, or
+/// `None` for collections it did not create.
+#[cw_serde]
+pub enum Ics721QueryMsg {
+    ClassId { contract: String },
+}
+
+/// ICS-20 `MsgTransfer`, sent as a stargate message so that refunds can carry an ibc-hooks
+/// callback memo.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct MsgTransfer {
+    #[prost(string, tag = "1")]
+    pub source_port: String,
+    #[prost(string, tag = "2")]
+    pub source_channel: String,
+    #[prost(message, optional, tag = "3")]
+    pub token: Option<ProtoCoin>,
+    #[prost(string, tag = "4")]
+    pub sender: String,
+    #[prost(string, tag = "5")]
+    pub receiver: String,
+    #[prost(uint64, tag = "7")]
+    pub timeout_timestamp: u64,
+    #[prost(string, tag = "8")]
+    pub memo: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoCoin {
+    #[prost(string, tag = "1")]
+    pub denom: String,
+    #[prost(string, tag = "2")]
+    pub amount: String,
+}
+
+/// Response to `MsgTransfer`, holding the packet sequence the ibc-hooks callback refers to.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct MsgTransferResponse {
+    #[prost(uint64, tag = "1")]
+    pub sequence: u64,
+}
+
+/// Memo asking ibc-hooks to report the outcome of a transfer to `ibc_callback`.
+#[cw_serde]
+pub struct IbcCallbackMemo {
+    pub ibc_callback: String,
+}
+
+/// Sudo calls ibc-hooks makes once a transfer with an `ibc_callback` memo completes.
+#[cw_serde]
+pub enum SudoMsg {
+    IbcLifecycleComplete(IbcLifecycleComplete),
+}
+
+#[cw_serde]
+pub enum IbcLifecycleComplete {
+    IbcAck {
+        channel: String,
+        sequence: u64,
+        ack: String,
+        success: bool,
     },
+    IbcTimeout {
+        channel: String,
+        sequence: u64,
+    },
+}
+
+/// `SendNft` payload that makes the ICS-721 contract transfer the NFT over IBC.
+#[cw_serde]
+pub struct IbcOutgoingMsg {
+    pub receiver: String,
+    pub channel_id: String,
+    pub timeout: IbcTimeout,
+    pub memo: Option<String>,
 }
@@ -1,6 +1,7 @@
 use crate::ContractError;
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Order, StdResult, Storage, Timestamp, Uint128};
+use cosmwasm_std::{Addr, BlockInfo, Coin, Decimal, Order, StdResult, Storage, Timestamp, Uint128};
+use cw721::Expiration;
 use cw_storage_plus::{Bound, Index, IndexList, IndexedMap, Item, Map, MultiIndex};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -8,6 +9,220 @@ use std::cmp;
 
 const MAX_LIMIT: u64 = 70;
 const DEFAULT_LIMIT: u64 = 20;
+pub const MAX_BPS: u64 = 10_000;
+/// Largest share of a winning bid, in basis points, that keepers can be paid.
+pub const MAX_KEEPER_REWARD_BPS: u64 = 500;
+
+#[cw_serde]
+pub struct Config {
+    pub owner: Addr,
+    /// Share of the winning bid, in basis points, paid to whoever settles an auction.
+    pub keeper_reward_bps: u64,
+    /// Flat fee escrowed from the seller's balance at creation and paid to whoever settles an
+    /// auction that received no bids.
+    pub keeper_fee: Option<Coin>,
+    pub cancel_policy: CancelPolicy,
+    /// Deposit, in the marketplace's fee denom, escrowed from the seller's balance for every
+    /// auction. It is refunded on settlement and slashed to the owner on cancel-after-bids.
+    pub listing_deposit: Option<Coin>,
+    /// Smallest raise over the current high bid, also used to step proxy bids.
+    pub bid_increment: Uint128,
+    /// Contract trusted to deliver randomness for raffles drawn with `RandomnessSource::Beacon`.
+    pub randomness_beacon: Option<Addr>,
+    /// ICS-721 contract whose proxy collections hold NFTs bridged from other chains. Auctions of
+    /// those NFTs can settle by sending them back to the winner on the origin chain.
+    pub ics721: Option<Addr>,
+}
+
+impl Config {
+    /// The settings a fresh marketplace starts with: no fees, deposits or integrations, and
+    /// unrestricted cancelling.
+    pub fn new(owner: Addr) -> Self {
+        Config {
+            owner,
+            keeper_reward_bps: 0,
+            keeper_fee: None,
+            cancel_policy: CancelPolicy::Unrestricted,
+            listing_deposit: None,
+            bid_increment: Uint128::one(),
+            randomness_beacon: None,
+            ics721: None,
+        }
+    }
+}
+
+/// Whether sellers may cancel an auction that already has bids.
+#[cw_serde]
+pub enum CancelPolicy {
+    /// Cancelling is allowed until the auction ends; the highest bid is refunded.
+    Unrestricted,
+    /// Cancelling is only allowed before the first bid.
+    BeforeFirstBid,
+    /// Cancelling with bids costs the seller `penalty_bps` of the highest bid, paid from their
+    /// deposited balance to the highest bidder.
+    WithPenalty { penalty_bps: u64 },
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+
+pub fn save_config(storage: &mut dyn Storage, config: &Config) -> StdResult<()> {
+    CONFIG.save(storage, config)
+}
+
+pub fn load_config(storage: &dyn Storage) -> StdResult<Config> {
+    CONFIG.load(storage)
+}
+
+/// Admin-set value of one unit of each denom in a common reference unit. Only the ratio between
+/// two rates matters.
+pub const EXCHANGE_RATES: Map<&str, Decimal> = Map::new("exchange_rates");
+
+#[cw_serde]
+pub struct ExchangeRate {
+    pub denom: String,
+    pub rate: Decimal,
+}
+
+pub fn load_exchange_rate(storage: &dyn Storage, denom: &str) -> Result<Decimal, ContractError> {
+    EXCHANGE_RATES
+        .may_load(storage, denom)?
+        .ok_or(ContractError::MissingExchangeRate {
+            denom: denom.to_owned(),
+        })
+}
+
+pub fn read_exchange_rates(storage: &dyn Storage) -> StdResult<Vec<ExchangeRate>> {
+    EXCHANGE_RATES
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(denom, rate)| ExchangeRate { denom, rate }))
+        .collect()
+}
+
+/// Whether an open multi-denom auction values its bids with the exchange rate of `denom`. Runs
+/// over every open auction.
+pub fn exchange_rate_in_use(storage: &dyn Storage, denom: &str) -> StdResult<bool> {
+    for item in nft_auction_states()
+        .idx
+        .end
+        .sub_prefix(OPEN_AUCTIONS)
+        .range(storage, None, None, Order::Ascending)
+    {
+        let (_, auction) = item?;
+        if !auction.accepted_denoms.is_empty()
+            && (auction.coin_denomination == denom
+                || auction
+                    .accepted_denoms
+                    .iter()
+                    .any(|accepted| accepted == denom))
+        {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Funds deposited by an address, keyed by `(address, denom)`.
+pub const BALANCES: Map<(&Addr, &str), Uint128> = Map::new("balances");
+
+/// Deposited funds escrowed for the address's live auctions, keyed by `(address, denom)`.
+pub const LOCKED_BALANCES: Map<(&Addr, &str), Uint128> = Map::new("locked_balances");
+
+fn add_amount(
+    storage: &mut dyn Storage,
+    balances: Map<(&Addr, &str), Uint128>,
+    address: &Addr,
+    coin: &Coin,
+) -> Result<Uint128, ContractError> {
+    let balance = balances
+        .may_load(storage, (address, &coin.denom))?
+        .unwrap_or_default()
+        .checked_add(coin.amount)?;
+    balances.save(storage, (address, &coin.denom), &balance)?;
+    Ok(balance)
+}
+
+fn sub_amount(
+    storage: &mut dyn Storage,
+    balances: Map<(&Addr, &str), Uint128>,
+    address: &Addr,
+    coin: &Coin,
+) -> Result<Uint128, ContractError> {
+    let balance = balances
+        .may_load(storage, (address, &coin.denom))?
+        .unwrap_or_default()
+        .checked_sub(coin.amount)
+        .map_err(|_| ContractError::InsufficientBalance {})?;
+    if balance.is_zero() {
+        balances.remove(storage, (address, &coin.denom));
+    } else {
+        balances.save(storage, (address, &coin.denom), &balance)?;
+    }
+    Ok(balance)
+}
+
+pub fn increase_balance(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    coin: &Coin,
+) -> Result<Uint128, ContractError> {
+    add_amount(storage, BALANCES, address, coin)
+}
+
+pub fn decrease_balance(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    coin: &Coin,
+) -> Result<Uint128, ContractError> {
+    sub_amount(storage, BALANCES, address, coin)
+}
+
+/// Moves funds from the address's balance into escrow.
+pub fn lock_balance(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    coin: &Coin,
+) -> Result<(), ContractError> {
+    sub_amount(storage, BALANCES, address, coin)?;
+    add_amount(storage, LOCKED_BALANCES, address, coin)?;
+    Ok(())
+}
+
+/// Returns escrowed funds to the address's balance.
+pub fn unlock_balance(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    coin: &Coin,
+) -> Result<(), ContractError> {
+    sub_amount(storage, LOCKED_BALANCES, address, coin)?;
+    add_amount(storage, BALANCES, address, coin)?;
+    Ok(())
+}
+
+/// Removes escrowed funds that are being paid out of the contract.
+pub fn release_locked_balance(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    coin: &Coin,
+) -> Result<(), ContractError> {
+    sub_amount(storage, LOCKED_BALANCES, address, coin)?;
+    Ok(())
+}
+
+pub fn read_balances(storage: &dyn Storage, address: &Addr) -> StdResult<Vec<Coin>> {
+    BALANCES
+        .prefix(address)
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(denom, amount)| Coin { denom, amount }))
+        .collect()
+}
+
+pub fn read_locked_balances(storage: &dyn Storage, address: &Addr) -> StdResult<Vec<Coin>> {
+    LOCKED_BALANCES
+        .prefix(address)
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(denom, amount)| Coin { denom, amount }))
+        .collect()
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct NFTAuctionState {
@@ -18,12 +233,518 @@ pub struct NFTAuctionState {
     pub coin_denomination: String,
     pub auction_id: Uint128,
     pub min_bid: Option<Uint128>,
+    pub reserve_price: Option<Uint128>,
     pub owner: String,
     pub token_id: String,
     pub token_address: String,
     pub is_cancelled: bool,
+    #[serde(default)]
+    pub is_settled: bool,
+    /// Keeper fee escrowed from the seller when the auction was created.
+    pub keeper_fee: Option<Coin>,
+    /// Listing deposit escrowed from the seller when the auction was created.
+    pub listing_deposit: Option<Coin>,
+    /// Share of the winning bid, in basis points, paid to the keeper. Taken from the config when
+    /// the auction was created.
+    #[serde(default)]
+    pub keeper_reward_bps: u64,
+    /// Restricts who may bid. Anyone can bid when unset.
+    pub access: Option<BidderAccess>,
+    /// Denoms accepted besides `coin_denomination`. Bids in these are ranked by their value in
+    /// `coin_denomination` according to `EXCHANGE_RATES`.
+    #[serde(default)]
+    pub accepted_denoms: Vec<String>,
+    /// Denom the high bid was paid in, when it is not `coin_denomination`.
+    pub high_bid_denom: Option<String>,
+    /// Further NFTs sold together with `token_id` when the auction is a bundle lot.
+    #[serde(default)]
+    pub bundle: Vec<BundleItem>,
+    /// Set when `token_id` does not exist yet and is minted to the winner on settlement.
+    pub lazy_mint: Option<LazyMint>,
+    /// Set when the NFT stays in the seller's wallet under an approval to the marketplace until
+    /// settlement, instead of being escrowed.
+    #[serde(default)]
+    pub held_by_seller: bool,
+    /// Set when `token_address` is an ICS-721 proxy collection of an NFT bridged from another
+    /// chain.
+    pub ics721_class: Option<Ics721Class>,
+    /// Set while an auction that starts on its first bid waits for a bid meeting the reserve
+    /// price, which starts a countdown of this many seconds. `end` is `OPEN_END` until then.
+    pub timer_duration: Option<u64>,
+    /// Smallest raise over the high bid. The config's `bid_increment` applies when unset.
+    #[serde(default)]
+    pub bid_increment: Option<Uint128>,
+    /// Anti-sniping window in seconds. A bid placed less than this long before the end moves the
+    /// end to this long after the bid.
+    #[serde(default)]
+    pub extension: Option<u64>,
+}
+
+/// End of an auction whose timer has not started yet.
+pub const OPEN_END: Timestamp = Timestamp::from_nanos(u64::MAX);
+
+/// The ICS-721 class of a bridged NFT.
+#[cw_serde]
+pub struct Ics721Class {
+    /// ICS-721 contract that bridged the NFT in and can send it back.
+    pub ics721: Addr,
+    /// Class ID on this chain, `{port}/{channel}/{origin class ID}`.
+    pub class_id: String,
+    /// Local channel the class arrived on, which sends the NFT back to its origin chain.
+    pub channel_id: String,
+}
+
+/// Metadata of a lazily minted token.
+#[cw_serde]
+pub struct LazyMint {
+    pub token_uri: Option<String>,
+}
+
+#[cw_serde]
+pub struct BundleItem {
+    pub token_address: String,
+    pub token_id: String,
+}
+
+/// Who is allowed to bid on a private auction.
+#[cw_serde]
+pub enum BidderAccess {
+    /// Only the listed addresses may bid.
+    Allowlist { addresses: Vec<Addr> },
+    /// Bidders prove membership with a Merkle proof against this hex-encoded sha256 root.
+    MerkleRoot { root: String },
+    /// Bidders must own at least one token of this cw721 collection.
+    HoldsToken { collection: String },
+}
+
+/// A bundle lot waiting for all of its NFTs to be escrowed before its auction opens.
+#[cw_serde]
+pub struct PendingBundle {
+    pub bundle_id: Uint128,
+    pub owner: Addr,
+    pub keeper_fee: Option<Coin>,
+    pub listing_deposit: Option<Coin>,
+    #[serde(default)]
+    pub keeper_reward_bps: u64,
+    pub items: Vec<BundleItem>,
+    pub escrowed: Vec<BundleItem>,
+    pub start_time: u64,
+    pub duration: u64,
+    pub coin_denomination: String,
+    pub min_bid: Option<Uint128>,
+    pub reserve_price: Option<Uint128>,
+    pub accepted_denoms: Vec<String>,
+}
+
+impl PendingBundle {
+    pub fn is_complete(&self) -> bool {
+        self.escrowed.len() == self.items.len()
+    }
+}
+
+/// Pending bundle lots, keyed by the auction id they will open with.
+pub const PENDING_BUNDLES: Map<u128, PendingBundle> = Map::new("pending_bundles");
+
+/// An NFT listed for rent. The marketplace escrows it while it is available and sends it to the
+/// renter for the rental period.
+#[cw_serde]
+pub struct Rental {
+    pub owner: Addr,
+    pub token_address: String,
+    pub token_id: String,
+    pub denom: String,
+    pub price_per_day: Uint128,
+    /// Held until the NFT is returned, and paid to the owner if it is not returned in time.
+    pub collateral: Uint128,
+    pub max_days: u64,
+    pub renter: Option<Addr>,
+    /// When the current rental ends. The NFT must be returned before then.
+    pub expires: Option<Timestamp>,
+}
+
+impl Rental {
+    pub fn is_rented(&self) -> bool {
+        self.renter.is_some()
+    }
+}
+
+/// Where the randomness that draws a raffle's winner comes from.
+#[cw_serde]
+pub enum RandomnessSource {
+    /// The seller commits to the hex-encoded sha256 of a secret when creating the raffle and
+    /// reveals the secret to draw it.
+    ///
+    /// Buyers must trust the seller: the seller knows the secret and so the winning ticket as
+    /// soon as tickets are sold. They can buy tickets from another address, or withhold the
+    /// secret when the outcome does not suit them. Withholding it past `DRAW_WINDOW_SECONDS`
+    /// only gets the raffle refunded. Use `Beacon` when the seller is not trusted.
+    CommitReveal { commitment: String },
+    /// The marketplace's configured randomness beacon delivers the randomness.
+    Beacon {},
+}
+
+/// An NFT sold by drawing one ticket among all tickets bought.
+#[cw_serde]
+pub struct Raffle {
+    pub raffle_id: Uint128,
+    pub owner: Addr,
+    pub token_address: String,
+    pub token_id: String,
+    pub start: Timestamp,
+    pub end: Timestamp,
+    pub denom: String,
+    pub ticket_price: Uint128,
+    /// Total number of tickets for sale.
+    pub max_tickets: u32,
+    pub max_tickets_per_address: Option<u32>,
+    pub randomness_source: RandomnessSource,
+    pub tickets_sold: u32,
+    /// Number of addresses holding tickets, capped at `MAX_TICKET_HOLDERS`.
+    #[serde(default)]
+    pub ticket_holders: u32,
+    /// Set once the raffle is drawn.
+    pub draw: Option<RaffleDraw>,
+    /// Set when the raffle was not drawn in time. Ticket holders then claim their refunds.
+    pub is_refunded: bool,
+}
+
+impl Raffle {
+    pub fn is_open(&self) -> bool {
+        self.draw.is_none() && !self.is_refunded
+    }
 }
 
+#[cw_serde]
+pub struct RaffleDraw {
+    /// Hex-encoded randomness the draw used. Empty when no tickets were sold.
+    pub randomness: String,
+    /// Index of the drawn ticket. Tickets are numbered from 0 in the order they were bought.
+    pub winning_ticket: Option<u32>,
+    /// Receives the NFT. The owner when no tickets were sold.
+    pub winner: Addr,
+}
+
+#[cw_serde]
+pub struct TicketHolding {
+    pub holder: Addr,
+    pub tickets: u32,
+}
+
+pub const RAFFLES: Map<u128, Raffle> = Map::new("raffles");
+
+/// Number of tickets each address holds, keyed by `(raffle_id, holder)`.
+pub const RAFFLE_TICKETS: Map<(u128, &Addr), u32> = Map::new("raffle_tickets");
+
+/// Buyer of every ticket purchase, keyed by `(raffle_id, first ticket)`. A ticket belongs to the
+/// purchase with the greatest first ticket not above it, so the draw finds its holder with a
+/// single lookup.
+pub const RAFFLE_PURCHASES: Map<(u128, u32), Addr> = Map::new("raffle_purchases");
+
+/// Returns the ticket holders of a raffle in address order.
+pub fn read_ticket_holdings(
+    storage: &dyn Storage,
+    raffle_id: u128,
+    start_after: Option<&Addr>,
+    limit: Option<u64>,
+) -> StdResult<Vec<TicketHolding>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    RAFFLE_TICKETS
+        .prefix(raffle_id)
+        .range(
+            storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(holder, tickets)| TicketHolding { holder, tickets }))
+        .collect()
+}
+
+/// Rental listings keyed by `(token_address, token_id)`.
+pub const RENTALS: Map<(&str, &str), Rental> = Map::new("rentals");
+
+/// Returns rental listings ordered by `(token_address, token_id)`.
+pub fn read_rentals(
+    storage: &dyn Storage,
+    start_after: Option<(String, String)>,
+    limit: Option<u64>,
+) -> StdResult<Vec<Rental>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_ref().map(|(token_address, token_id)| {
+        Bound::exclusive((token_address.as_str(), token_id.as_str()))
+    });
+    RENTALS
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, rental)| rental))
+        .collect()
+}
+
+/// An address allowed to cancel and update a seller's auctions until `expires`.
+#[cw_serde]
+pub struct Operator {
+    pub operator: Addr,
+    pub expires: Expiration,
+}
+
+/// Operator expirations keyed by `(seller, operator)`.
+pub const OPERATORS: Map<(&Addr, &Addr), Expiration> = Map::new("operators");
+
+/// Returns the operators of a seller in address order, skipping expired ones unless
+/// `include_expired` is set.
+pub fn read_operators(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    seller: &Addr,
+    include_expired: bool,
+    start_after: Option<&Addr>,
+    limit: Option<u64>,
+) -> StdResult<Vec<Operator>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    OPERATORS
+        .prefix(seller)
+        .range(
+            storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .filter(|item| {
+            include_expired
+                || item
+                    .as_ref()
+                    .map_or(true, |(_, expires)| !expires.is_expired(block))
+        })
+        .take(limit)
+        .map(|item| item.map(|(operator, expires)| Operator { operator, expires }))
+        .collect()
+}
+
+/// Auction parameters a seller saved to start auctions with
+/// `Cw721CustomMsg::StartFromTemplate`.
+#[cw_serde]
+pub struct AuctionTemplate {
+    pub duration: u64,
+    pub coin_denomination: String,
+    pub min_bid: Option<Uint128>,
+    pub reserve_price: Option<Uint128>,
+    pub access: Option<BidderAccess>,
+    pub accepted_denoms: Option<Vec<String>>,
+    /// Smallest raise over the high bid, instead of the config's `bid_increment`.
+    pub bid_increment: Option<Uint128>,
+    /// Anti-sniping window in seconds, see `NFTAuctionState::extension`.
+    pub extension: Option<u64>,
+}
+
+#[cw_serde]
+pub struct NamedTemplate {
+    pub name: String,
+    pub params: AuctionTemplate,
+}
+
+/// Longest auction template name.
+pub const MAX_TEMPLATE_NAME_LENGTH: usize = 64;
+
+/// Auction templates keyed by `(seller, name)`.
+pub const TEMPLATES: Map<(&Addr, &str), AuctionTemplate> = Map::new("templates");
+
+pub fn read_templates(
+    storage: &dyn Storage,
+    seller: &Addr,
+    start_after: Option<&str>,
+    limit: Option<u64>,
+) -> StdResult<Vec<NamedTemplate>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    TEMPLATES
+        .prefix(seller)
+        .range(
+            storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(name, params)| NamedTemplate { name, params }))
+        .collect()
+}
+
+/// A token sold by a completed auction or drawn raffle. A bundle records one sale per item.
+#[cw_serde]
+pub struct Sale {
+    /// ID of the auction or raffle.
+    pub auction_id: Uint128,
+    pub token_address: String,
+    pub token_id: String,
+    /// Price of the whole lot: the winning bid before the keeper reward is deducted, or the
+    /// ticket proceeds of a raffle.
+    pub price: Uint128,
+    pub denom: String,
+    pub time: Timestamp,
+    pub buyer: Addr,
+    pub seller: Addr,
+    /// Number of tokens sold together for `price`.
+    pub lot_size: u32,
+}
+
+/// Running sale aggregates of a collection in one denom.
+#[cw_serde]
+pub struct SaleStats {
+    pub token_address: String,
+    pub denom: String,
+    pub total_volume: Uint128,
+    pub sale_count: u64,
+    pub last_price: Uint128,
+    pub all_time_high: Uint128,
+}
+
+/// Sale history keyed by `(token_address, auction_id, token_id)`.
+pub const SALES: Map<(&str, u128, &str), Sale> = Map::new("sales");
+
+/// Sale aggregates keyed by `(token_address, denom)`.
+pub const SALE_STATS: Map<(&str, &str), SaleStats> = Map::new("sale_stats");
+
+/// Records the sale of a lot: one history entry per item in `items`, and one sale in the
+/// aggregates of each collection the lot contains, since `price` is the price of the whole lot.
+pub fn record_sale(
+    storage: &mut dyn Storage,
+    items: &[BundleItem],
+    auction_id: Uint128,
+    price: Coin,
+    time: Timestamp,
+    buyer: &Addr,
+    seller: &Addr,
+) -> StdResult<()> {
+    for (i, item) in items.iter().enumerate() {
+        let sale = Sale {
+            auction_id,
+            token_address: item.token_address.clone(),
+            token_id: item.token_id.clone(),
+            price: price.amount,
+            denom: price.denom.clone(),
+            time,
+            buyer: buyer.clone(),
+            seller: seller.clone(),
+            lot_size: items.len() as u32,
+        };
+        SALES.save(
+            storage,
+            (&item.token_address, auction_id.u128(), &item.token_id),
+            &sale,
+        )?;
+        if items[..i]
+            .iter()
+            .any(|other| other.token_address == item.token_address)
+        {
+            continue;
+        }
+        let key = (sale.token_address.as_str(), sale.denom.as_str());
+        let mut stats = SALE_STATS.may_load(storage, key)?.unwrap_or(SaleStats {
+            token_address: sale.token_address.clone(),
+            denom: sale.denom.clone(),
+            total_volume: Uint128::zero(),
+            sale_count: 0,
+            last_price: Uint128::zero(),
+            all_time_high: Uint128::zero(),
+        });
+        stats.total_volume = stats.total_volume.checked_add(sale.price)?;
+        stats.sale_count += 1;
+        stats.last_price = sale.price;
+        stats.all_time_high = stats.all_time_high.max(sale.price);
+        SALE_STATS.save(storage, key, &stats)?;
+    }
+    Ok(())
+}
+
+/// Returns the sales of a collection by auction id and token id, oldest first unless `order_by`
+/// is `Desc`.
+pub fn read_sales(
+    storage: &dyn Storage,
+    token_address: &str,
+    start_after: Option<(u128, &str)>,
+    limit: Option<u64>,
+    order_by: Option<OrderBy>,
+) -> StdResult<Vec<Sale>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after.map(Bound::exclusive);
+    let (min, max, order) = match order_by {
+        Some(OrderBy::Desc) => (None, start_after, Order::Descending),
+        _ => (start_after, None, Order::Ascending),
+    };
+    SALES
+        .sub_prefix(token_address)
+        .range(storage, min, max, order)
+        .take(limit)
+        .map(|item| item.map(|(_, sale)| sale))
+        .collect()
+}
+
+/// Returns the sale aggregates of a collection ordered by denom.
+pub fn read_sale_stats(
+    storage: &dyn Storage,
+    token_address: &str,
+    start_after: Option<String>,
+    limit: Option<u64>,
+) -> StdResult<Vec<SaleStats>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    SALE_STATS
+        .prefix(token_address)
+        .range(
+            storage,
+            start_after.as_deref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(_, stats)| stats))
+        .collect()
+}
+
+/// Where a bidder that bids through an ibc-hooks ICS-20 memo sends its funds from.
+#[cw_serde]
+pub struct IbcOrigin {
+    /// Channel on this chain the transfer arrives on.
+    pub channel_id: String,
+    /// Address of the bidder on the origin chain, which refunds are sent back to.
+    pub sender: String,
+    /// Local address that receives the NFTs the bidder wins, as nobody controls the address
+    /// ibc-hooks derives for the bidder.
+    pub nft_recipient: String,
+}
+
+/// IBC origins of hook bidders, keyed by the local address ibc-hooks derived for them.
+pub const IBC_BIDDERS: Map<&Addr, IbcOrigin> = Map::new("ibc_bidders");
+
+/// The local address NFTs won by `bidder` are delivered to: the `nft_recipient` of hook bidders,
+/// or the bidder itself.
+pub fn nft_recipient(storage: &dyn Storage, bidder: &Addr) -> StdResult<String> {
+    Ok(IBC_BIDDERS
+        .may_load(storage, bidder)?
+        .map_or_else(|| bidder.to_string(), |origin| origin.nft_recipient))
+}
+
+/// An IBC refund to a hook bidder that has not been acknowledged yet. If the transfer fails or
+/// times out, `amount` is credited to the balance of `recipient`, the bidder's `nft_recipient`.
+#[cw_serde]
+pub struct IbcRefund {
+    pub auction_id: Uint128,
+    pub channel_id: String,
+    pub recipient: Addr,
+    pub amount: Coin,
+}
+
+/// IBC refunds waiting for the reply that reports their packet sequence, keyed by reply ID.
+pub const PENDING_IBC_REFUNDS: Map<u64, IbcRefund> = Map::new("pending_ibc_refunds");
+
+/// IBC refunds in flight, keyed by `(channel_id, sequence)` of their packet.
+pub const IBC_REFUNDS: Map<(&str, u64), IbcRefund> = Map::new("ibc_refunds");
+
+pub const NEXT_IBC_REFUND_ID: Item<u64> = Item::new("next_ibc_refund_id");
+
+/// Origin-chain addresses bidders want bridged NFTs sent back to, keyed by
+/// `(auction_id, bidder)`.
+pub const ICS721_RECEIVERS: Map<(u128, &Addr), String> = Map::new("ics721_receivers");
+
 #[cw_serde]
 pub struct Bid {
     pub bidder: String,
@@ -33,8 +754,31 @@ pub struct Bid {
 
 pub const NEXT_AUCTION_ID: Item<Uint128> = Item::new("next_auction_id");
 
+/// Local recipients of NFTs whose delivery on settlement failed, keyed by `(auction_id, index)`
+/// with `index` into `NFTAuctionState::items`. Only these NFTs can be claimed, and each only
+/// once.
+pub const CLAIMABLE_NFTS: Map<(u128, u32), String> = Map::new("claimable_nfts");
+
+/// Local recipients of NFTs sent back over ICS-721 on settlement, keyed by auction id. ICS-721
+/// returns the NFT to the marketplace without notice when the transfer fails or times out, after
+/// which the recipient can claim it.
+pub const ICS721_SENT_BACK: Map<u128, String> = Map::new("ics721_sent_back");
+
 pub const BIDS: Map<u128, Vec<Bid>> = Map::new("bids");
 
+/// Funds escrowed by the high bidder of each auction, i.e. their maximum bid. Kept apart from
+/// `NFTAuctionState` so that proxy bid maxima are not exposed by the auction queries.
+pub const BID_ESCROWS: Map<u128, Uint128> = Map::new("bid_escrows");
+
+pub fn load_bid_escrow(
+    storage: &dyn Storage,
+    auction_state: &NFTAuctionState,
+) -> StdResult<Uint128> {
+    Ok(BID_ESCROWS
+        .may_load(storage, auction_state.auction_id.u128())?
+        .unwrap_or(auction_state.high_bidder_amount))
+}
+
 pub fn save_bids(storage: &mut dyn Storage, auction_id: u128, bid: Vec<Bid>) -> StdResult<()> {
     BIDS.save(storage, auction_id, &bid)?;
     Ok(())
@@ -44,14 +788,55 @@ pub fn load_bids(storage: &dyn Storage, auction_id: u128) -> StdResult<Vec<Bid>>
     BIDS.load(storage, auction_id)
 }
 
-pub const NFT_AUCTION_STATE: Map<u128, NFTAuctionState> = Map::new("nft_auction_state");
+/// Leading part of the start and end index keys of open auctions. Cancelled and settled auctions
+/// are indexed under `1`, so ranging over this prefix never visits them.
+pub const OPEN_AUCTIONS: u8 = 0;
+
+/// Start and end times of auctions, each keyed by `(open, seconds)` where `open` is
+/// `OPEN_AUCTIONS` while the auction is open.
+pub struct NFTAuctionStateIndices<'a> {
+    pub start: MultiIndex<'a, (u8, u64), NFTAuctionState, u128>,
+    pub end: MultiIndex<'a, (u8, u64), NFTAuctionState, u128>,
+}
+
+impl<'a> IndexList<NFTAuctionState> for NFTAuctionStateIndices<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<NFTAuctionState>> + '_> {
+        let v: Vec<&dyn Index<NFTAuctionState>> = vec![&self.start, &self.end];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn nft_auction_states<'a>() -> IndexedMap<'a, u128, NFTAuctionState, NFTAuctionStateIndices<'a>>
+{
+    let indexes = NFTAuctionStateIndices {
+        start: MultiIndex::new(
+            |_pk: &[u8], s| (open_key(s), s.start.seconds()),
+            "nft_auction_state",
+            "nft_auction_state__open_start",
+        ),
+        end: MultiIndex::new(
+            |_pk: &[u8], s| (open_key(s), s.end.seconds()),
+            "nft_auction_state",
+            "nft_auction_state__open_end",
+        ),
+    };
+    IndexedMap::new("nft_auction_state", indexes)
+}
+
+fn open_key(state: &NFTAuctionState) -> u8 {
+    if state.is_open() {
+        OPEN_AUCTIONS
+    } else {
+        1
+    }
+}
 
 pub fn save_nft_auction_state(
     storage: &mut dyn Storage,
     auction_id: u128,
     auction_state: NFTAuctionState,
 ) -> StdResult<()> {
-    NFT_AUCTION_STATE.save(storage, auction_id, &auction_state)?;
+    nft_auction_states().save(storage, auction_id, &auction_state)?;
     Ok(())
 }
 
@@ -59,7 +844,174 @@ pub fn load_nft_auction_state(
     storage: &dyn Storage,
     auction_id: u128,
 ) -> StdResult<NFTAuctionState> {
-    NFT_AUCTION_STATE.load(storage, auction_id)
+    nft_auction_states().load(storage, auction_id)
+}
+
+/// Returns the ids of auctions that ended at or before `time` and are still waiting to be
+/// settled, oldest end time first. `start_after` is the id of the last auction of the previous
+/// page.
+pub fn read_expired_auction_ids(
+    storage: &dyn Storage,
+    time: Timestamp,
+    start_after: Option<Uint128>,
+    limit: Option<u64>,
+) -> StdResult<Vec<u128>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let min = match start_after {
+        Some(auction_id) => {
+            let state = load_nft_auction_state(storage, auction_id.u128())?;
+            Some(Bound::exclusive((state.end.seconds(), auction_id.u128())))
+        }
+        None => None,
+    };
+    nft_auction_states()
+        .idx
+        .end
+        .sub_prefix(OPEN_AUCTIONS)
+        .range(
+            storage,
+            min,
+            Some(Bound::inclusive((time.seconds(), u128::MAX))),
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(auction_id, _)| auction_id))
+        .collect()
+}
+
+impl NFTAuctionState {
+    /// An auction is open until it is either cancelled or settled.
+    pub fn is_open(&self) -> bool {
+        !self.is_cancelled && !self.is_settled
+    }
+
+    /// All NFTs sold by the auction, starting with `token_id`.
+    pub fn items(&self) -> Vec<BundleItem> {
+        let mut items = vec![BundleItem {
+            token_address: self.token_address.clone(),
+            token_id: self.token_id.clone(),
+        }];
+        items.extend(self.bundle.iter().cloned());
+        items
+    }
+
+    /// Every denom bids may be paid in, starting with `coin_denomination`.
+    pub fn payment_denoms(&self) -> Vec<String> {
+        let mut denoms = vec![self.coin_denomination.clone()];
+        denoms.extend(self.accepted_denoms.iter().cloned());
+        denoms
+    }
+
+    /// NFTs held in escrow by the auction. Lazily minted tokens and NFTs kept by the seller are
+    /// not escrowed.
+    pub fn escrowed_items(&self) -> Vec<BundleItem> {
+        if self.lazy_mint.is_some() || self.held_by_seller {
+            return vec![];
+        }
+        self.items()
+    }
+
+    /// Denom of the current high bid. Escrows, refunds and payouts are made in it.
+    pub fn bid_denom(&self) -> &str {
+        self.high_bid_denom
+            .as_deref()
+            .unwrap_or(&self.coin_denomination)
+    }
+
+    pub fn high_bid(&self) -> Coin {
+        Coin {
+            denom: self.bid_denom().to_owned(),
+            amount: self.high_bidder_amount,
+        }
+    }
+}
+
+/// Returns started, open auctions whose end time falls within `(time, until]`, soonest first.
+pub fn read_auctions_ending_soon(
+    storage: &dyn Storage,
+    time: Timestamp,
+    until: Timestamp,
+    limit: Option<u64>,
+) -> StdResult<Vec<NFTAuctionState>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    nft_auction_states()
+        .idx
+        .end
+        .sub_prefix(OPEN_AUCTIONS)
+        .range(
+            storage,
+            Some(Bound::exclusive((time.seconds(), u128::MAX))),
+            Some(Bound::inclusive((until.seconds(), u128::MAX))),
+            Order::Ascending,
+        )
+        .filter(|item| match item {
+            // Auctions waiting for their first bid have no end yet.
+            Ok((_, state)) => state.start <= time && state.end != OPEN_END,
+            Err(_) => true,
+        })
+        .take(limit)
+        .map(|item| item.map(|(_, state)| state))
+        .collect()
+}
+
+/// Returns open auctions that have started but not ended at `time`, ordered by end time.
+/// `start_after` is the id of the last auction of the previous page.
+pub fn read_live_auctions(
+    storage: &dyn Storage,
+    time: Timestamp,
+    start_after: Option<Uint128>,
+    limit: Option<u64>,
+) -> StdResult<Vec<NFTAuctionState>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let min = match start_after {
+        Some(auction_id) => {
+            let state = load_nft_auction_state(storage, auction_id.u128())?;
+            (state.end.seconds(), auction_id.u128())
+        }
+        None => (time.seconds(), u128::MAX),
+    };
+    nft_auction_states()
+        .idx
+        .end
+        .sub_prefix(OPEN_AUCTIONS)
+        .range(storage, Some(Bound::exclusive(min)), None, Order::Ascending)
+        .filter(|item| match item {
+            Ok((_, state)) => state.start <= time && state.end > time,
+            Err(_) => true,
+        })
+        .take(limit)
+        .map(|item| item.map(|(_, state)| state))
+        .collect()
+}
+
+/// Returns open auctions that start after `time`, ordered by start time.
+/// `start_after` is the id of the last auction of the previous page.
+pub fn read_upcoming_auctions(
+    storage: &dyn Storage,
+    time: Timestamp,
+    start_after: Option<Uint128>,
+    limit: Option<u64>,
+) -> StdResult<Vec<NFTAuctionState>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let min = match start_after {
+        Some(auction_id) => {
+            let state = load_nft_auction_state(storage, auction_id.u128())?;
+            (state.start.seconds(), auction_id.u128())
+        }
+        None => (time.seconds(), u128::MAX),
+    };
+    nft_auction_states()
+        .idx
+        .start
+        .sub_prefix(OPEN_AUCTIONS)
+        .range(storage, Some(Bound::exclusive(min)), None, Order::Ascending)
+        .filter(|item| match item {
+            Ok((_, state)) => state.start > time,
+            Err(_) => true,
+        })
+        .take(limit)
+        .map(|item| item.map(|(_, state)| state))
+        .collect()
 }
 
 pub fn save_next_auction_id(storage: &mut dyn Storage, auction_id: Uint128) -> StdResult<()> {
@@ -125,10 +1077,7 @@ pub fn save_auction_details(
     Ok(())
 }
 
-pub fn load_auction_details(
-    storage: &mut dyn Storage,
-    pk: &std::string::String,
-) -> StdResult<AuctionDetails> {
+pub fn load_auction_details(storage: &mut dyn Storage, pk: &str) -> StdResult<AuctionDetails> {
     auction_details().load(storage, pk)
 }
 
@@ -0,0 +1,297 @@
+#[cfg(test)]
+mod tests {
+    use super::super::multitest::tests::{
+        config_msg, Suite, ADMIN, ALICE, BOB, DENOM, FEE_DENOM, KEEPER, SELLER,
+    };
+    use crate::{
+        msg::{Cw721CustomMsg, ExecuteMsg},
+        state::{
+            load_bid_escrow, nft_auction_states, CancelPolicy, ExchangeRate, NFTAuctionState,
+            BALANCES, LOCKED_BALANCES,
+        },
+    };
+    use cosmwasm_std::{coin, testing::MockStorage, Coin, Decimal, Order, Storage, Uint128};
+    use proptest::prelude::*;
+
+    const OTHER_DENOM: &str = "eur";
+    const DEALER: &str = "dealer";
+    const CAROL: &str = "carol";
+    const DENOMS: [&str; 3] = [DENOM, OTHER_DENOM, FEE_DENOM];
+    const SELLERS: [&str; 2] = [SELLER, DEALER];
+    const BIDDERS: [&str; 3] = [ALICE, BOB, CAROL];
+    const TOKENS: usize = 4;
+    const DURATION: u64 = 100;
+    const FUNDS: u128 = 10_000;
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Create {
+            token: usize,
+            denom: usize,
+            multi_denom: bool,
+            reserve_price: Option<u128>,
+        },
+        Bid {
+            token: usize,
+            bidder: usize,
+            denom: usize,
+            amount: u128,
+            proxy: bool,
+        },
+        Cancel {
+            token: usize,
+        },
+        Finalize {
+            token: usize,
+        },
+        FinalizeExpired,
+        Advance {
+            seconds: u64,
+        },
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            2 => (0..TOKENS, 0..2usize, any::<bool>(), prop::option::of(1u128..800)).prop_map(
+                |(token, denom, multi_denom, reserve_price)| Op::Create {
+                    token,
+                    denom,
+                    multi_denom,
+                    reserve_price,
+                }
+            ),
+            4 => (0..TOKENS, 0..BIDDERS.len(), 0..2usize, 1u128..600, any::<bool>()).prop_map(
+                |(token, bidder, denom, amount, proxy)| Op::Bid {
+                    token,
+                    bidder,
+                    denom,
+                    amount,
+                    proxy,
+                }
+            ),
+            1 => (0..TOKENS).prop_map(|token| Op::Cancel { token }),
+            1 => (0..TOKENS).prop_map(|token| Op::Finalize { token }),
+            1 => Just(Op::FinalizeExpired),
+            2 => (1u64..120).prop_map(|seconds| Op::Advance { seconds }),
+        ]
+    }
+
+    fn token_id(token: usize) -> String {
+        format!("token{token}")
+    }
+
+    /// Copies the marketplace's storage out of the app so the state helpers can read it.
+    fn marketplace_storage(suite: &Suite) -> MockStorage {
+        let mut storage = MockStorage::new();
+        for (key, value) in suite.app.dump_wasm_raw(&suite.marketplace) {
+            storage.set(&key, &value);
+        }
+        storage
+    }
+
+    /// The most recent auction that contains `token_id`, if it was ever listed.
+    fn latest_auction(storage: &dyn Storage, token_id: &str) -> Option<NFTAuctionState> {
+        nft_auction_states()
+            .range(storage, None, None, Order::Descending)
+            .map(|item| item.unwrap().1)
+            .find(|auction| auction.items().iter().any(|item| item.token_id == token_id))
+    }
+
+    fn sum_balances(
+        storage: &dyn Storage,
+        balances: cw_storage_plus::Map<(&cosmwasm_std::Addr, &str), Uint128>,
+        denom: &str,
+    ) -> u128 {
+        balances
+            .range(storage, None, None, Order::Ascending)
+            .map(|item| item.unwrap())
+            .filter(|((_, balance_denom), _)| balance_denom == denom)
+            .map(|(_, amount)| amount.u128())
+            .sum()
+    }
+
+    fn setup() -> Suite {
+        let mut suite = Suite::new();
+        for address in SELLERS.iter().chain(BIDDERS.iter()) {
+            suite.fund(
+                address,
+                DENOMS.iter().map(|denom| coin(FUNDS, *denom)).collect(),
+            );
+        }
+        let rates = vec![
+            ExchangeRate {
+                denom: DENOM.to_owned(),
+                rate: Decimal::one(),
+            },
+            ExchangeRate {
+                denom: OTHER_DENOM.to_owned(),
+                rate: Decimal::percent(150),
+            },
+        ];
+        suite
+            .execute(ADMIN, &ExecuteMsg::SetExchangeRates { rates }, &[])
+            .unwrap();
+        suite
+            .execute(
+                ADMIN,
+                &config_msg(
+                    Some(200),
+                    Some(CancelPolicy::WithPenalty { penalty_bps: 1_000 }),
+                    Some(coin(5, FEE_DENOM)),
+                ),
+                &[],
+            )
+            .unwrap();
+        for seller in SELLERS {
+            let funds: Vec<Coin> = DENOMS.iter().map(|denom| coin(100, *denom)).collect();
+            suite
+                .execute(seller, &ExecuteMsg::DepositBalance {}, &funds)
+                .unwrap();
+        }
+        for token in 0..TOKENS {
+            suite.mint(SELLERS[token % SELLERS.len()], &token_id(token));
+        }
+        suite
+    }
+
+    /// Runs `op`, ignoring rejected messages: a failed message must leave no trace, which the
+    /// invariants check as well.
+    fn apply(suite: &mut Suite, op: Op) {
+        let collection = suite.collection.to_string();
+        match op {
+            Op::Create {
+                token,
+                denom,
+                multi_denom,
+                reserve_price,
+            } => {
+                let owner = suite.owner_of(&token_id(token));
+                if owner == suite.marketplace.as_str() {
+                    return;
+                }
+                let msg = Cw721CustomMsg::InitializeCW721TokenAuction {
+                    start_time: suite.app.block_info().time.seconds() + 1,
+                    duration: DURATION,
+                    coin_denomination: DENOMS[denom].to_owned(),
+                    min_bid: None,
+                    reserve_price: reserve_price.map(Uint128::new),
+                    access: None,
+                    accepted_denoms: multi_denom.then(|| vec![DENOMS[1 - denom].to_owned()]),
+                    start_mode: None,
+                };
+                let _ = suite.send_nft(&owner, &token_id(token), &msg);
+            }
+            Op::Bid {
+                token,
+                bidder,
+                denom,
+                amount,
+                proxy,
+            } => {
+                let msg = ExecuteMsg::SubmitBid {
+                    token_id: token_id(token),
+                    token_address: collection,
+                    proof: None,
+                    max_amount: proxy.then_some(Uint128::new(amount)),
+                    ibc_origin: None,
+                };
+                let funds = [coin(amount, DENOMS[denom])];
+                let _ = suite.execute(BIDDERS[bidder], &msg, &funds);
+            }
+            Op::Cancel { token } => {
+                let storage = marketplace_storage(suite);
+                let Some(auction) = latest_auction(&storage, &token_id(token)) else {
+                    return;
+                };
+                let msg = ExecuteMsg::CancelAuctionAndRefund {
+                    token_id: token_id(token),
+                    token_address: collection,
+                };
+                let _ = suite.execute(&auction.owner, &msg, &[]);
+            }
+            Op::Finalize { token } => {
+                let msg = ExecuteMsg::FinalizeAuctionAndTranferAssets {
+                    token_id: token_id(token),
+                    token_address: collection,
+                };
+                let _ = suite.execute(KEEPER, &msg, &[]);
+            }
+            Op::FinalizeExpired => {
+                let msg = ExecuteMsg::FinalizeExpired {
+                    start_after: None,
+                    limit: None,
+                };
+                let _ = suite.execute(KEEPER, &msg, &[]);
+            }
+            Op::Advance { seconds } => suite.advance(seconds),
+        }
+    }
+
+    fn check_invariants(suite: &Suite) -> Result<(), TestCaseError> {
+        let storage = marketplace_storage(suite);
+        let auctions: Vec<NFTAuctionState> = nft_auction_states()
+            .range(&storage, None, None, Order::Ascending)
+            .map(|item| item.unwrap().1)
+            .collect();
+
+        for denom in DENOMS {
+            let escrowed: u128 = auctions
+                .iter()
+                .filter(|auction| auction.is_open() && auction.bid_denom() == denom)
+                .map(|auction| load_bid_escrow(&storage, auction).unwrap().u128())
+                .sum();
+            let deposited = sum_balances(&storage, BALANCES, denom);
+            let locked = sum_balances(&storage, LOCKED_BALANCES, denom);
+            prop_assert_eq!(
+                suite.balance(suite.marketplace.as_str(), denom),
+                escrowed + deposited + locked,
+                "contract balance of {} must equal escrowed bids plus deposits",
+                denom
+            );
+
+            let held: u128 = SELLERS
+                .iter()
+                .chain(BIDDERS.iter())
+                .chain([KEEPER, ADMIN].iter())
+                .chain([suite.marketplace.as_str()].iter())
+                .map(|address| suite.balance(address, denom))
+                .sum();
+            prop_assert_eq!(
+                held,
+                FUNDS * (SELLERS.len() + BIDDERS.len()) as u128,
+                "total supply of {} changed",
+                denom
+            );
+        }
+
+        for token in 0..TOKENS {
+            let owner = suite.owner_of(&token_id(token));
+            match latest_auction(&storage, &token_id(token)) {
+                Some(auction) if auction.is_open() => {
+                    prop_assert_eq!(&owner, suite.marketplace.as_str());
+                }
+                Some(auction) => prop_assert!(
+                    owner == auction.owner || owner == auction.high_bidder_addr.as_str(),
+                    "{} of a closed auction went to {}",
+                    token_id(token),
+                    owner
+                ),
+                None => prop_assert_eq!(owner, SELLERS[token % SELLERS.len()]),
+            }
+        }
+        Ok(())
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn escrow_matches_contract_balance(ops in prop::collection::vec(op(), 1..40)) {
+            let mut suite = setup();
+            for op in ops {
+                apply(&mut suite, op);
+                check_invariants(&suite)?;
+            }
+        }
+    }
+}
@@ -0,0 +1,134 @@
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::ContractError,
+        merkle::{hash_pair, leaf_hash, verify, MerkleProof},
+    };
+    use cosmwasm_std::Uint128;
+    use proptest::prelude::*;
+
+    /// Reference tree builder: hashes each level pairwise and carries an odd last node up
+    /// unchanged. Returns the root and the proof of every leaf.
+    fn build_tree(leaves: &[[u8; 32]]) -> ([u8; 32], Vec<Vec<String>>) {
+        let mut proofs = vec![vec![]; leaves.len()];
+        // Position of every leaf in the current level.
+        let mut positions: Vec<usize> = (0..leaves.len()).collect();
+        let mut level = leaves.to_vec();
+        while level.len() > 1 {
+            for (leaf, position) in positions.iter_mut().enumerate() {
+                let sibling = *position ^ 1;
+                if sibling < level.len() {
+                    proofs[leaf].push(hex::encode(level[sibling]));
+                }
+                *position /= 2;
+            }
+            level = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => hash_pair(a, b),
+                    [a] => *a,
+                    _ => unreachable!(),
+                })
+                .collect();
+        }
+        (level[0], proofs)
+    }
+
+    fn allowlist() -> impl Strategy<Value = Vec<(String, Option<u128>)>> {
+        prop::collection::btree_map("[a-z0-9]{8,20}", prop::option::of(1u128..1_000_000), 1..40)
+            .prop_map(|entries| entries.into_iter().collect())
+    }
+
+    fn leaves(entries: &[(String, Option<u128>)]) -> Vec<[u8; 32]> {
+        entries
+            .iter()
+            .map(|(address, max_amount)| leaf_hash(address, max_amount.map(Uint128::new)))
+            .collect()
+    }
+
+    proptest! {
+        #[test]
+        fn every_member_verifies(entries in allowlist()) {
+            let (root, proofs) = build_tree(&leaves(&entries));
+            let root = hex::encode(root);
+            for ((address, max_amount), proof) in entries.iter().zip(proofs) {
+                let proof = MerkleProof {
+                    max_amount: max_amount.map(Uint128::new),
+                    proof,
+                };
+                prop_assert!(verify(&root, address, &proof).unwrap());
+            }
+        }
+
+        #[test]
+        fn non_members_are_rejected(entries in allowlist(), outsider in "[A-Z]{8,20}") {
+            let (root, proofs) = build_tree(&leaves(&entries));
+            let root = hex::encode(root);
+            for ((_, max_amount), proof) in entries.iter().zip(proofs) {
+                let proof = MerkleProof {
+                    max_amount: max_amount.map(Uint128::new),
+                    proof,
+                };
+                prop_assert!(!verify(&root, &outsider, &proof).unwrap());
+            }
+        }
+
+        #[test]
+        fn changed_allowance_is_rejected(entries in allowlist(), extra in 1u128..1_000) {
+            let (root, proofs) = build_tree(&leaves(&entries));
+            let root = hex::encode(root);
+            for ((address, max_amount), proof) in entries.iter().zip(proofs) {
+                let max_amount = match max_amount {
+                    Some(max_amount) => Some(Uint128::new(max_amount + extra)),
+                    None => Some(Uint128::new(extra)),
+                };
+                let proof = MerkleProof { max_amount, proof };
+                prop_assert!(!verify(&root, address, &proof).unwrap());
+            }
+        }
+
+        #[test]
+        fn proofs_do_not_verify_against_other_roots(
+            entries in allowlist(),
+            other in allowlist(),
+        ) {
+            prop_assume!(entries != other);
+            let (_, proofs) = build_tree(&leaves(&entries));
+            let (other_root, _) = build_tree(&leaves(&other));
+            let other_root = hex::encode(other_root);
+            for ((address, max_amount), proof) in entries.iter().zip(proofs) {
+                if other.contains(&(address.clone(), *max_amount)) {
+                    continue;
+                }
+                let proof = MerkleProof {
+                    max_amount: max_amount.map(Uint128::new),
+                    proof,
+                };
+                prop_assert!(!verify(&other_root, address, &proof).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_malformed_root_and_proof() {
+        let proof = MerkleProof {
+            max_amount: None,
+            proof: vec![],
+        };
+        assert_eq!(
+            ContractError::InvalidMerkleRoot {},
+            verify("abcd", "alice", &proof).unwrap_err()
+        );
+
+        let root = hex::encode(leaf_hash("alice", None));
+        assert!(verify(&root, "alice", &proof).unwrap());
+        let proof = MerkleProof {
+            max_amount: None,
+            proof: vec!["not hex".to_owned()],
+        };
+        assert_eq!(
+            ContractError::InvalidMerkleProof {},
+            verify(&root, "alice", &proof).unwrap_err()
+        );
+    }
+}
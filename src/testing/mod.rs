@@ -1 +1,4 @@
+mod escrow_invariants;
+mod merkle_tests;
+mod multitest;
 mod tests;
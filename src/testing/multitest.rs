@@ -0,0 +1,1182 @@
+#[cfg(test)]
+pub(super) mod tests {
+    use crate::{
+        msg::{Cw721CustomMsg, ExecuteMsg, InstantiateMsg, QueryMsg, StartMode},
+        state::{
+            BidderAccess, BundleItem, CancelPolicy, ExchangeRate, Operator, OrderBy, Raffle,
+            RandomnessSource, Sale, SaleStats,
+        },
+    };
+    use cosmwasm_std::{coin, coins, to_json_binary, Addr, Coin, Decimal, Empty, Uint128};
+    use cw721::{Cw721QueryMsg, Expiration, OwnerOfResponse};
+    use cw_multi_test::{error::AnyResult, App, AppResponse, Contract, ContractWrapper, Executor};
+
+    pub(crate) const DENOM: &str = "usd";
+    pub(crate) const FEE_DENOM: &str = "fee";
+    pub(crate) const ADMIN: &str = "admin";
+    pub(crate) const SELLER: &str = "seller";
+    pub(crate) const ALICE: &str = "alice";
+    pub(crate) const BOB: &str = "bob";
+    pub(crate) const KEEPER: &str = "keeper";
+    const START_DELAY: u64 = 100;
+    const DURATION: u64 = 1000;
+
+    fn marketplace_contract() -> Box<dyn Contract<Empty>> {
+        Box::new(
+            ContractWrapper::new(crate::execute, crate::instantiate, crate::query)
+                .with_reply(crate::reply)
+                .with_migrate(crate::migrate),
+        )
+    }
+
+    fn cw721_contract() -> Box<dyn Contract<Empty>> {
+        Box::new(ContractWrapper::new(
+            cw721_base::entry::execute,
+            cw721_base::entry::instantiate,
+            cw721_base::entry::query,
+        ))
+    }
+
+    pub(crate) struct Suite {
+        pub(crate) app: App,
+        pub(crate) marketplace: Addr,
+        pub(crate) collection: Addr,
+        /// Block time the auctions started by the suite are scheduled against.
+        start: u64,
+    }
+
+    impl Suite {
+        pub(crate) fn new() -> Self {
+            let mut app = App::new(|router, _, storage| {
+                for (address, funds) in [
+                    (SELLER, vec![coin(1_000, DENOM), coin(1_000, FEE_DENOM)]),
+                    (ALICE, coins(1_000, DENOM)),
+                    (BOB, coins(1_000, DENOM)),
+                ] {
+                    router
+                        .bank
+                        .init_balance(storage, &Addr::unchecked(address), funds)
+                        .unwrap();
+                }
+            });
+            let marketplace_id = app.store_code(marketplace_contract());
+            let cw721_id = app.store_code(cw721_contract());
+            let marketplace = app
+                .instantiate_contract(
+                    marketplace_id,
+                    Addr::unchecked(ADMIN),
+                    &InstantiateMsg {},
+                    &[],
+                    "marketplace",
+                    None,
+                )
+                .unwrap();
+            let collection = app
+                .instantiate_contract(
+                    cw721_id,
+                    Addr::unchecked(ADMIN),
+                    &cw721_base::InstantiateMsg {
+                        name: "Collection".to_owned(),
+                        symbol: "COL".to_owned(),
+                        minter: ADMIN.to_owned(),
+                    },
+                    &[],
+                    "collection",
+                    None,
+                )
+                .unwrap();
+            let start = app.block_info().time.seconds() + START_DELAY;
+            Suite {
+                app,
+                marketplace,
+                collection,
+                start,
+            }
+        }
+
+        pub(crate) fn mint(&mut self, owner: &str, token_id: &str) {
+            let msg = cw721_base::ExecuteMsg::<Empty, Empty>::Mint {
+                token_id: token_id.to_owned(),
+                owner: owner.to_owned(),
+                token_uri: None,
+                extension: Empty {},
+            };
+            self.app
+                .execute_contract(Addr::unchecked(ADMIN), self.collection.clone(), &msg, &[])
+                .unwrap();
+        }
+
+        pub(crate) fn send_nft(
+            &mut self,
+            owner: &str,
+            token_id: &str,
+            msg: &Cw721CustomMsg,
+        ) -> AnyResult<AppResponse> {
+            let msg = cw721_base::ExecuteMsg::<Empty, Empty>::SendNft {
+                contract: self.marketplace.to_string(),
+                token_id: token_id.to_owned(),
+                msg: to_json_binary(msg).unwrap(),
+            };
+            self.app
+                .execute_contract(Addr::unchecked(owner), self.collection.clone(), &msg, &[])
+        }
+
+        fn start_auction(&mut self, token_id: &str, reserve_price: Option<u128>) {
+            self.mint(SELLER, token_id);
+            let msg = Cw721CustomMsg::InitializeCW721TokenAuction {
+                start_time: self.start,
+                duration: DURATION,
+                coin_denomination: DENOM.to_owned(),
+                min_bid: None,
+                reserve_price: reserve_price.map(Uint128::new),
+                access: None,
+                accepted_denoms: None,
+                start_mode: None,
+            };
+            self.send_nft(SELLER, token_id, &msg).unwrap();
+        }
+
+        pub(crate) fn execute(
+            &mut self,
+            sender: &str,
+            msg: &ExecuteMsg,
+            funds: &[Coin],
+        ) -> AnyResult<AppResponse> {
+            self.app.execute_contract(
+                Addr::unchecked(sender),
+                self.marketplace.clone(),
+                msg,
+                funds,
+            )
+        }
+
+        fn bid(
+            &mut self,
+            bidder: &str,
+            token_id: &str,
+            amount: u128,
+            max_amount: Option<u128>,
+        ) -> AnyResult<AppResponse> {
+            let msg = ExecuteMsg::SubmitBid {
+                token_id: token_id.to_owned(),
+                token_address: self.collection.to_string(),
+                proof: None,
+                max_amount: max_amount.map(Uint128::new),
+                ibc_origin: None,
+            };
+            self.execute(bidder, &msg, &coins(amount, DENOM))
+        }
+
+        fn finalize(&mut self, token_id: &str) -> AnyResult<AppResponse> {
+            let msg = ExecuteMsg::FinalizeAuctionAndTranferAssets {
+                token_id: token_id.to_owned(),
+                token_address: self.collection.to_string(),
+            };
+            self.execute(KEEPER, &msg, &[])
+        }
+
+        fn cancel(&mut self, token_id: &str) -> AnyResult<AppResponse> {
+            let msg = ExecuteMsg::CancelAuctionAndRefund {
+                token_id: token_id.to_owned(),
+                token_address: self.collection.to_string(),
+            };
+            self.execute(SELLER, &msg, &[])
+        }
+
+        pub(crate) fn update_config(&mut self, msg: ExecuteMsg) {
+            self.execute(ADMIN, &msg, &[]).unwrap();
+        }
+
+        /// Moves the block time to `offset` seconds after the scheduled auction start.
+        fn advance_to(&mut self, offset: u64) {
+            let time = self.start + offset;
+            self.app.update_block(|block| {
+                block.time = cosmwasm_std::Timestamp::from_seconds(time);
+            });
+        }
+
+        /// Sets the bank balance of `address`, replacing whatever it held.
+        pub(crate) fn fund(&mut self, address: &str, funds: Vec<Coin>) {
+            self.app.init_modules(|router, _, storage| {
+                router
+                    .bank
+                    .init_balance(storage, &Addr::unchecked(address), funds)
+                    .unwrap();
+            });
+        }
+
+        /// Moves the block time forward by `seconds`.
+        pub(crate) fn advance(&mut self, seconds: u64) {
+            self.app.update_block(|block| {
+                block.time = block.time.plus_seconds(seconds);
+            });
+        }
+
+        pub(crate) fn owner_of(&self, token_id: &str) -> String {
+            let res: OwnerOfResponse = self
+                .app
+                .wrap()
+                .query_wasm_smart(
+                    self.collection.clone(),
+                    &Cw721QueryMsg::OwnerOf {
+                        token_id: token_id.to_owned(),
+                        include_expired: None,
+                    },
+                )
+                .unwrap();
+            res.owner
+        }
+
+        pub(crate) fn balance(&self, address: &str, denom: &str) -> u128 {
+            self.app
+                .wrap()
+                .query_balance(address, denom)
+                .unwrap()
+                .amount
+                .u128()
+        }
+
+        fn marketplace_balance(&self, address: &str) -> Vec<Coin> {
+            self.app
+                .wrap()
+                .query_wasm_smart(
+                    self.marketplace.clone(),
+                    &QueryMsg::Balance {
+                        address: address.to_owned(),
+                    },
+                )
+                .unwrap()
+        }
+    }
+
+    pub(crate) fn config_msg(
+        keeper_reward_bps: Option<u64>,
+        cancel_policy: Option<CancelPolicy>,
+        listing_deposit: Option<Coin>,
+    ) -> ExecuteMsg {
+        ExecuteMsg::UpdateConfig {
+            owner: None,
+            keeper_reward_bps,
+            keeper_fee: None,
+            cancel_policy,
+            listing_deposit,
+            bid_increment: None,
+            randomness_beacon: None,
+            ics721: None,
+        }
+    }
+
+    #[test]
+    fn auction_with_bids_settles_to_the_highest_bidder() {
+        let mut suite = Suite::new();
+        suite.start_auction("1", None);
+        assert_eq!(suite.marketplace.as_str(), suite.owner_of("1"));
+
+        let err = suite.bid(ALICE, "1", 100, None).unwrap_err();
+        assert!(err.root_cause().to_string().contains("Auction not started"));
+
+        suite.advance_to(10);
+        suite.bid(ALICE, "1", 100, None).unwrap();
+        suite.bid(BOB, "1", 150, None).unwrap();
+        assert_eq!(1_000, suite.balance(ALICE, DENOM));
+        assert_eq!(850, suite.balance(BOB, DENOM));
+        assert_eq!(150, suite.balance(suite.marketplace.as_str(), DENOM));
+
+        assert!(suite.finalize("1").is_err());
+        suite.advance_to(DURATION);
+        suite.finalize("1").unwrap();
+        assert_eq!(BOB, suite.owner_of("1"));
+        assert_eq!(1_150, suite.balance(SELLER, DENOM));
+        assert_eq!(0, suite.balance(suite.marketplace.as_str(), DENOM));
+        assert!(suite.finalize("1").is_err());
+    }
+
+    #[test]
+    fn auction_without_bids_returns_the_nft() {
+        let mut suite = Suite::new();
+        suite.start_auction("1", None);
+        suite.advance_to(DURATION);
+        suite.finalize("1").unwrap();
+        assert_eq!(SELLER, suite.owner_of("1"));
+        assert_eq!(1_000, suite.balance(SELLER, DENOM));
+    }
+
+    #[test]
+    fn auction_below_reserve_price_refunds_the_bidder() {
+        let mut suite = Suite::new();
+        suite.start_auction("1", Some(500));
+        suite.advance_to(10);
+        suite.bid(ALICE, "1", 100, None).unwrap();
+        suite.advance_to(DURATION);
+        suite.finalize("1").unwrap();
+        assert_eq!(SELLER, suite.owner_of("1"));
+        assert_eq!(1_000, suite.balance(ALICE, DENOM));
+        assert_eq!(1_000, suite.balance(SELLER, DENOM));
+        assert_eq!(0, suite.balance(suite.marketplace.as_str(), DENOM));
+    }
+
+    #[test]
+    fn cancelled_auction_refunds_bids_and_penalty() {
+        let mut suite = Suite::new();
+        suite.update_config(config_msg(
+            None,
+            Some(CancelPolicy::WithPenalty { penalty_bps: 1_000 }),
+            None,
+        ));
+        suite.start_auction("1", None);
+        suite
+            .execute(SELLER, &ExecuteMsg::DepositBalance {}, &coins(50, DENOM))
+            .unwrap();
+        suite.advance_to(10);
+        suite.bid(ALICE, "1", 200, None).unwrap();
+
+        suite.cancel("1").unwrap();
+        assert_eq!(SELLER, suite.owner_of("1"));
+        assert_eq!(1_020, suite.balance(ALICE, DENOM));
+        assert_eq!(vec![coin(30, DENOM)], suite.marketplace_balance(SELLER));
+        assert_eq!(30, suite.balance(suite.marketplace.as_str(), DENOM));
+        assert!(suite.bid(BOB, "1", 300, None).is_err());
+    }
+
+    #[test]
+    fn proxy_bids_pay_the_second_highest_maximum() {
+        let mut suite = Suite::new();
+        suite.start_auction("1", None);
+        suite.advance_to(10);
+        suite.bid(ALICE, "1", 500, Some(500)).unwrap();
+        suite.bid(BOB, "1", 300, None).unwrap();
+        // Bob's bid is covered by Alice's maximum and is refunded right away.
+        assert_eq!(1_000, suite.balance(BOB, DENOM));
+        assert_eq!(500, suite.balance(ALICE, DENOM));
+
+        suite.advance_to(DURATION);
+        suite.finalize("1").unwrap();
+        assert_eq!(ALICE, suite.owner_of("1"));
+        assert_eq!(699, suite.balance(ALICE, DENOM));
+        assert_eq!(1_301, suite.balance(SELLER, DENOM));
+        assert_eq!(0, suite.balance(suite.marketplace.as_str(), DENOM));
+    }
+
+    #[test]
+    fn keeper_reward_and_listing_deposit_are_paid_out() {
+        let mut suite = Suite::new();
+        suite.update_config(config_msg(Some(500), None, Some(coin(10, FEE_DENOM))));
+        suite
+            .execute(
+                SELLER,
+                &ExecuteMsg::DepositBalance {},
+                &coins(10, FEE_DENOM),
+            )
+            .unwrap();
+        suite.start_auction("1", None);
+        suite.advance_to(10);
+        suite.bid(ALICE, "1", 200, None).unwrap();
+        suite.advance_to(DURATION);
+        suite.finalize("1").unwrap();
+
+        assert_eq!(ALICE, suite.owner_of("1"));
+        assert_eq!(10, suite.balance(KEEPER, DENOM));
+        assert_eq!(1_190, suite.balance(SELLER, DENOM));
+
+        let msg = ExecuteMsg::WithdrawBalance {
+            amount: coin(10, FEE_DENOM),
+        };
+        suite.execute(SELLER, &msg, &[]).unwrap();
+        assert_eq!(1_000, suite.balance(SELLER, FEE_DENOM));
+        assert_eq!(0, suite.balance(suite.marketplace.as_str(), FEE_DENOM));
+    }
+
+    #[test]
+    fn bundle_auction_transfers_every_item() {
+        let mut suite = Suite::new();
+        suite.mint(SELLER, "1");
+        suite.mint(SELLER, "2");
+        suite.mint(SELLER, "3");
+        let items: Vec<BundleItem> = ["1", "2", "3"]
+            .iter()
+            .map(|token_id| BundleItem {
+                token_address: suite.collection.to_string(),
+                token_id: token_id.to_string(),
+            })
+            .collect();
+        let msg = ExecuteMsg::CreateBundleAuction {
+            items,
+            start_time: suite.start,
+            duration: DURATION,
+            coin_denomination: DENOM.to_owned(),
+            min_bid: None,
+            reserve_price: None,
+            accepted_denoms: None,
+        };
+        suite.execute(SELLER, &msg, &[]).unwrap();
+
+        // One item is sent in, the others are pulled through the marketplace's approval.
+        let add = Cw721CustomMsg::AddToBundle {
+            bundle_id: Uint128::one(),
+        };
+        suite.send_nft(SELLER, "1", &add).unwrap();
+        let approve = cw721_base::ExecuteMsg::<Empty, Empty>::ApproveAll {
+            operator: suite.marketplace.to_string(),
+            expires: None,
+        };
+        suite
+            .app
+            .execute_contract(
+                Addr::unchecked(SELLER),
+                suite.collection.clone(),
+                &approve,
+                &[],
+            )
+            .unwrap();
+        let msg = ExecuteMsg::EscrowBundleItems {
+            bundle_id: Uint128::one(),
+        };
+        suite.execute(SELLER, &msg, &[]).unwrap();
+        for token_id in ["1", "2", "3"] {
+            assert_eq!(suite.marketplace.as_str(), suite.owner_of(token_id));
+        }
+
+        suite.advance_to(10);
+        suite.bid(ALICE, "2", 300, None).unwrap();
+        suite.advance_to(DURATION);
+        suite.finalize("3").unwrap();
+        for token_id in ["1", "2", "3"] {
+            assert_eq!(ALICE, suite.owner_of(token_id));
+        }
+        assert_eq!(1_300, suite.balance(SELLER, DENOM));
+    }
+
+    #[test]
+    fn cancelled_bundle_returns_escrowed_items() {
+        let mut suite = Suite::new();
+        suite.mint(SELLER, "1");
+        suite.mint(SELLER, "2");
+        let items: Vec<BundleItem> = ["1", "2"]
+            .iter()
+            .map(|token_id| BundleItem {
+                token_address: suite.collection.to_string(),
+                token_id: token_id.to_string(),
+            })
+            .collect();
+        let msg = ExecuteMsg::CreateBundleAuction {
+            items,
+            start_time: suite.start,
+            duration: DURATION,
+            coin_denomination: DENOM.to_owned(),
+            min_bid: None,
+            reserve_price: None,
+            accepted_denoms: None,
+        };
+        suite.execute(SELLER, &msg, &[]).unwrap();
+        let add = Cw721CustomMsg::AddToBundle {
+            bundle_id: Uint128::one(),
+        };
+        suite.send_nft(SELLER, "1", &add).unwrap();
+        assert_eq!(suite.marketplace.as_str(), suite.owner_of("1"));
+
+        let msg = ExecuteMsg::CancelBundle {
+            bundle_id: Uint128::one(),
+        };
+        suite.execute(SELLER, &msg, &[]).unwrap();
+        assert_eq!(SELLER, suite.owner_of("1"));
+        assert_eq!(SELLER, suite.owner_of("2"));
+    }
+
+    #[test]
+    fn multi_denom_auction_pays_out_in_the_winning_denom() {
+        let mut suite = Suite::new();
+        suite.fund(BOB, coins(1_000, "atom"));
+        let rates = [(DENOM, 100), ("atom", 1_000)]
+            .into_iter()
+            .map(|(denom, percent)| ExchangeRate {
+                denom: denom.to_owned(),
+                rate: Decimal::percent(percent),
+            })
+            .collect();
+        suite
+            .execute(ADMIN, &ExecuteMsg::SetExchangeRates { rates }, &[])
+            .unwrap();
+        suite.mint(SELLER, "1");
+        let msg = Cw721CustomMsg::InitializeCW721TokenAuction {
+            start_time: suite.start,
+            duration: DURATION,
+            coin_denomination: DENOM.to_owned(),
+            min_bid: None,
+            reserve_price: None,
+            access: None,
+            accepted_denoms: Some(vec!["atom".to_owned()]),
+            start_mode: None,
+        };
+        suite.send_nft(SELLER, "1", &msg).unwrap();
+
+        suite.advance_to(10);
+        suite.bid(ALICE, "1", 100, None).unwrap();
+        let msg = ExecuteMsg::SubmitBid {
+            token_id: "1".to_owned(),
+            token_address: suite.collection.to_string(),
+            proof: None,
+            max_amount: None,
+            ibc_origin: None,
+        };
+        suite.execute(BOB, &msg, &coins(11, "atom")).unwrap();
+        assert_eq!(1_000, suite.balance(ALICE, DENOM));
+        assert_eq!(0, suite.balance(suite.marketplace.as_str(), DENOM));
+
+        suite.advance_to(DURATION);
+        suite.finalize("1").unwrap();
+        assert_eq!(BOB, suite.owner_of("1"));
+        assert_eq!(989, suite.balance(BOB, "atom"));
+        assert_eq!(11, suite.balance(SELLER, "atom"));
+        assert_eq!(1_000, suite.balance(SELLER, DENOM));
+    }
+
+    fn list_for_rent(suite: &mut Suite, token_id: &str) {
+        suite.mint(SELLER, token_id);
+        let msg = Cw721CustomMsg::ListForRent {
+            denom: DENOM.to_owned(),
+            price_per_day: Uint128::new(10),
+            collateral: Uint128::new(200),
+            max_days: 7,
+        };
+        suite.send_nft(SELLER, token_id, &msg).unwrap();
+    }
+
+    fn rent(suite: &mut Suite, renter: &str, token_id: &str, days: u64, amount: u128) {
+        let msg = ExecuteMsg::Rent {
+            token_address: suite.collection.to_string(),
+            token_id: token_id.to_owned(),
+            days,
+        };
+        suite.execute(renter, &msg, &coins(amount, DENOM)).unwrap();
+    }
+
+    #[test]
+    fn returned_rental_refunds_the_collateral() {
+        let mut suite = Suite::new();
+        list_for_rent(&mut suite, "1");
+        assert_eq!(suite.marketplace.as_str(), suite.owner_of("1"));
+
+        rent(&mut suite, ALICE, "1", 3, 230);
+        assert_eq!(ALICE, suite.owner_of("1"));
+        assert_eq!(770, suite.balance(ALICE, DENOM));
+        assert_eq!(1_030, suite.balance(SELLER, DENOM));
+
+        suite.advance(2 * 86_400);
+        suite
+            .send_nft(ALICE, "1", &Cw721CustomMsg::ReturnRental {})
+            .unwrap();
+        assert_eq!(suite.marketplace.as_str(), suite.owner_of("1"));
+        assert_eq!(970, suite.balance(ALICE, DENOM));
+
+        // The NFT stays listed for the next renter until the owner delists it.
+        rent(&mut suite, BOB, "1", 1, 210);
+        assert_eq!(BOB, suite.owner_of("1"));
+        suite
+            .send_nft(BOB, "1", &Cw721CustomMsg::ReturnRental {})
+            .unwrap();
+        let msg = ExecuteMsg::DelistRental {
+            token_address: suite.collection.to_string(),
+            token_id: "1".to_owned(),
+        };
+        suite.execute(SELLER, &msg, &[]).unwrap();
+        assert_eq!(SELLER, suite.owner_of("1"));
+        assert_eq!(1_040, suite.balance(SELLER, DENOM));
+        assert_eq!(0, suite.balance(suite.marketplace.as_str(), DENOM));
+    }
+
+    #[test]
+    fn expired_rental_forfeits_the_collateral() {
+        let mut suite = Suite::new();
+        list_for_rent(&mut suite, "1");
+        rent(&mut suite, ALICE, "1", 1, 210);
+
+        suite.advance(86_400);
+        let err = suite
+            .send_nft(ALICE, "1", &Cw721CustomMsg::ReturnRental {})
+            .unwrap_err();
+        assert!(err.root_cause().to_string().contains("Rental expired"));
+        assert_eq!(ALICE, suite.owner_of("1"));
+
+        let msg = ExecuteMsg::ClaimCollateral {
+            token_address: suite.collection.to_string(),
+            token_id: "1".to_owned(),
+        };
+        suite.execute(SELLER, &msg, &[]).unwrap();
+        assert_eq!(1_210, suite.balance(SELLER, DENOM));
+        assert_eq!(790, suite.balance(ALICE, DENOM));
+        assert_eq!(0, suite.balance(suite.marketplace.as_str(), DENOM));
+    }
+
+    #[test]
+    fn holders_only_auction_checks_the_collection() {
+        let mut suite = Suite::new();
+        suite.mint(BOB, "held");
+        suite.mint(SELLER, "1");
+        let msg = Cw721CustomMsg::InitializeCW721TokenAuction {
+            start_time: suite.start,
+            duration: DURATION,
+            coin_denomination: DENOM.to_owned(),
+            min_bid: None,
+            reserve_price: None,
+            access: Some(BidderAccess::HoldsToken {
+                collection: suite.collection.to_string(),
+            }),
+            accepted_denoms: None,
+            start_mode: None,
+        };
+        suite.send_nft(SELLER, "1", &msg).unwrap();
+
+        suite.advance_to(10);
+        let err = suite.bid(ALICE, "1", 100, None).unwrap_err();
+        assert!(err
+            .root_cause()
+            .to_string()
+            .contains("Bidder is not allowed to bid"));
+        assert_eq!(1_000, suite.balance(ALICE, DENOM));
+        suite.bid(BOB, "1", 100, None).unwrap();
+
+        suite.advance_to(DURATION);
+        suite.finalize("1").unwrap();
+        assert_eq!(BOB, suite.owner_of("1"));
+    }
+
+    #[test]
+    fn beacon_raffle_pays_the_seller_and_transfers_to_the_winner() {
+        let mut suite = Suite::new();
+        suite.update_config(ExecuteMsg::UpdateConfig {
+            owner: None,
+            keeper_reward_bps: None,
+            keeper_fee: None,
+            cancel_policy: None,
+            listing_deposit: None,
+            bid_increment: None,
+            randomness_beacon: Some(KEEPER.to_owned()),
+            ics721: None,
+        });
+        suite.mint(SELLER, "1");
+        let msg = Cw721CustomMsg::StartRaffle {
+            start_time: suite.start,
+            duration: DURATION,
+            denom: DENOM.to_owned(),
+            ticket_price: Uint128::new(25),
+            max_tickets: 10,
+            max_tickets_per_address: Some(4),
+            randomness_source: RandomnessSource::Beacon {},
+        };
+        suite.send_nft(SELLER, "1", &msg).unwrap();
+        let raffle_id = Uint128::one();
+
+        suite.advance_to(10);
+        for (buyer, count) in [(ALICE, 3u32), (BOB, 4)] {
+            let msg = ExecuteMsg::BuyTickets { raffle_id, count };
+            let funds = coins(25 * u128::from(count), DENOM);
+            suite.execute(buyer, &msg, &funds).unwrap();
+        }
+        let msg = ExecuteMsg::BuyTickets {
+            raffle_id,
+            count: 1,
+        };
+        let err = suite.execute(BOB, &msg, &coins(25, DENOM)).unwrap_err();
+        assert!(err
+            .root_cause()
+            .to_string()
+            .contains("Not enough tickets left"));
+        assert_eq!(175, suite.balance(suite.marketplace.as_str(), DENOM));
+
+        suite.advance_to(DURATION);
+        let msg = ExecuteMsg::DrawRaffle {
+            raffle_id,
+            randomness: Some("ab".repeat(32)),
+        };
+        suite.execute(KEEPER, &msg, &[]).unwrap();
+        let raffle: Raffle = suite
+            .app
+            .wrap()
+            .query_wasm_smart(&suite.marketplace, &QueryMsg::Raffle { raffle_id })
+            .unwrap();
+        let winner = raffle.draw.unwrap().winner;
+        assert!(winner == ALICE || winner == BOB);
+        assert_eq!(winner.as_str(), suite.owner_of("1"));
+        assert_eq!(1_175, suite.balance(SELLER, DENOM));
+        assert_eq!(0, suite.balance(suite.marketplace.as_str(), DENOM));
+    }
+
+    #[test]
+    fn lazy_auction_mints_to_the_winner() {
+        let mut suite = Suite::new();
+        let cw721_id = suite.app.store_code(cw721_contract());
+        let collection = suite
+            .app
+            .instantiate_contract(
+                cw721_id,
+                Addr::unchecked(SELLER),
+                &cw721_base::InstantiateMsg {
+                    name: "Lazy".to_owned(),
+                    symbol: "LZY".to_owned(),
+                    minter: suite.marketplace.to_string(),
+                },
+                &[],
+                "lazy",
+                None,
+            )
+            .unwrap();
+        let msg = ExecuteMsg::CreateLazyAuction {
+            token_address: collection.to_string(),
+            token_id: "1".to_owned(),
+            token_uri: Some("ipfs://1".to_owned()),
+            start_time: suite.start,
+            duration: DURATION,
+            coin_denomination: DENOM.to_owned(),
+            min_bid: None,
+            reserve_price: None,
+            accepted_denoms: None,
+        };
+        let err = suite.execute(ALICE, &msg, &[]).unwrap_err();
+        assert!(err.root_cause().to_string().contains("Unauthorized"));
+        suite.execute(SELLER, &msg, &[]).unwrap();
+
+        suite.advance_to(10);
+        let msg = ExecuteMsg::SubmitBid {
+            token_id: "1".to_owned(),
+            token_address: collection.to_string(),
+            proof: None,
+            max_amount: None,
+            ibc_origin: None,
+        };
+        suite.execute(ALICE, &msg, &coins(300, DENOM)).unwrap();
+        suite.advance_to(DURATION);
+        let msg = ExecuteMsg::FinalizeAuctionAndTranferAssets {
+            token_id: "1".to_owned(),
+            token_address: collection.to_string(),
+        };
+        suite.execute(KEEPER, &msg, &[]).unwrap();
+
+        let res: cw721::NftInfoResponse<Empty> = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                &collection,
+                &Cw721QueryMsg::NftInfo {
+                    token_id: "1".to_owned(),
+                },
+            )
+            .unwrap();
+        assert_eq!(Some("ipfs://1".to_owned()), res.token_uri);
+        let res: OwnerOfResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                &collection,
+                &Cw721QueryMsg::OwnerOf {
+                    token_id: "1".to_owned(),
+                    include_expired: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(ALICE, res.owner);
+        assert_eq!(1_300, suite.balance(SELLER, DENOM));
+    }
+
+    fn start_approved_auction(suite: &mut Suite, token_id: &str) {
+        suite.mint(SELLER, token_id);
+        let msg = cw721_base::ExecuteMsg::<Empty, Empty>::Approve {
+            spender: suite.marketplace.to_string(),
+            token_id: token_id.to_owned(),
+            expires: None,
+        };
+        suite
+            .app
+            .execute_contract(Addr::unchecked(SELLER), suite.collection.clone(), &msg, &[])
+            .unwrap();
+        create_approved_auction(suite, token_id);
+    }
+
+    fn create_approved_auction(suite: &mut Suite, token_id: &str) {
+        let msg = ExecuteMsg::CreateApprovedAuction {
+            token_address: suite.collection.to_string(),
+            token_id: token_id.to_owned(),
+            start_time: suite.start,
+            duration: DURATION,
+            coin_denomination: DENOM.to_owned(),
+            min_bid: None,
+            reserve_price: None,
+            access: None,
+            accepted_denoms: None,
+        };
+        suite.execute(SELLER, &msg, &[]).unwrap();
+    }
+
+    #[test]
+    fn approved_auction_transfers_from_the_seller_wallet() {
+        let mut suite = Suite::new();
+        start_approved_auction(&mut suite, "1");
+        assert_eq!(SELLER, suite.owner_of("1"));
+
+        suite.advance_to(10);
+        suite.bid(ALICE, "1", 100, None).unwrap();
+        suite.bid(BOB, "1", 150, None).unwrap();
+        suite.advance_to(DURATION);
+        suite.finalize("1").unwrap();
+        assert_eq!(BOB, suite.owner_of("1"));
+        assert_eq!(1_150, suite.balance(SELLER, DENOM));
+        assert_eq!(1_000, suite.balance(ALICE, DENOM));
+    }
+
+    #[test]
+    fn approved_auction_is_invalidated_when_the_nft_moves() {
+        let mut suite = Suite::new();
+        start_approved_auction(&mut suite, "1");
+        suite.advance_to(10);
+        suite.bid(ALICE, "1", 100, None).unwrap();
+
+        let msg = cw721_base::ExecuteMsg::<Empty, Empty>::TransferNft {
+            recipient: KEEPER.to_owned(),
+            token_id: "1".to_owned(),
+        };
+        suite
+            .app
+            .execute_contract(Addr::unchecked(SELLER), suite.collection.clone(), &msg, &[])
+            .unwrap();
+
+        let err = suite.bid(BOB, "1", 150, None).unwrap_err();
+        assert!(err
+            .root_cause()
+            .to_string()
+            .contains("The seller no longer holds the NFT"));
+        assert_eq!(1_000, suite.balance(BOB, DENOM));
+
+        // The high bidder does not have to wait for the end to be refunded.
+        let res = suite.finalize("1").unwrap();
+        assert!(res
+            .events
+            .iter()
+            .any(|event| event.ty == "wasm-auction_invalidated"));
+        assert_eq!(1_000, suite.balance(ALICE, DENOM));
+        assert_eq!(0, suite.balance(suite.marketplace.as_str(), DENOM));
+        assert_eq!(KEEPER, suite.owner_of("1"));
+        assert!(suite.bid(BOB, "1", 150, None).is_err());
+    }
+
+    #[test]
+    fn approved_auction_accepts_an_operator_approval() {
+        let mut suite = Suite::new();
+        suite.mint(SELLER, "1");
+        let msg = cw721_base::ExecuteMsg::<Empty, Empty>::ApproveAll {
+            operator: suite.marketplace.to_string(),
+            expires: None,
+        };
+        suite
+            .app
+            .execute_contract(Addr::unchecked(SELLER), suite.collection.clone(), &msg, &[])
+            .unwrap();
+        create_approved_auction(&mut suite, "1");
+
+        suite.advance_to(10);
+        suite.bid(ALICE, "1", 100, None).unwrap();
+        suite.bid(BOB, "1", 150, None).unwrap();
+        suite.advance_to(DURATION);
+        suite.finalize("1").unwrap();
+        assert_eq!(BOB, suite.owner_of("1"));
+        assert_eq!(1_150, suite.balance(SELLER, DENOM));
+    }
+
+    #[test]
+    fn approved_auction_is_invalidated_at_settlement_when_revoked() {
+        let mut suite = Suite::new();
+        start_approved_auction(&mut suite, "1");
+        suite.advance_to(10);
+        suite.bid(ALICE, "1", 100, None).unwrap();
+
+        let msg = cw721_base::ExecuteMsg::<Empty, Empty>::Revoke {
+            spender: suite.marketplace.to_string(),
+            token_id: "1".to_owned(),
+        };
+        suite
+            .app
+            .execute_contract(Addr::unchecked(SELLER), suite.collection.clone(), &msg, &[])
+            .unwrap();
+
+        suite.advance_to(DURATION);
+        suite.finalize("1").unwrap();
+        assert_eq!(SELLER, suite.owner_of("1"));
+        assert_eq!(1_000, suite.balance(ALICE, DENOM));
+        assert_eq!(1_000, suite.balance(SELLER, DENOM));
+        assert!(suite.finalize("1").is_err());
+    }
+
+    #[test]
+    fn settled_sales_are_recorded_with_collection_stats() {
+        let mut suite = Suite::new();
+        suite.start_auction("1", None);
+        suite.start_auction("2", None);
+        suite.start_auction("3", Some(500));
+        suite.advance_to(10);
+        suite.bid(ALICE, "1", 300, None).unwrap();
+        suite.bid(BOB, "2", 200, None).unwrap();
+        suite.bid(BOB, "3", 100, None).unwrap();
+        suite.advance_to(DURATION);
+        for token_id in ["1", "2", "3"] {
+            suite.finalize(token_id).unwrap();
+        }
+
+        let sales: Vec<Sale> = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.marketplace.clone(),
+                &QueryMsg::Sales {
+                    token_address: suite.collection.to_string(),
+                    start_after: None,
+                    limit: None,
+                    order_by: Some(OrderBy::Desc),
+                },
+            )
+            .unwrap();
+        assert_eq!(2, sales.len());
+        assert_eq!("2", sales[0].token_id);
+        assert_eq!(Uint128::new(200), sales[0].price);
+        assert_eq!(Addr::unchecked(BOB), sales[0].buyer);
+        assert_eq!(Addr::unchecked(SELLER), sales[0].seller);
+        assert_eq!("1", sales[1].token_id);
+        let sales: Vec<Sale> = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.marketplace.clone(),
+                &QueryMsg::Sales {
+                    token_address: suite.collection.to_string(),
+                    start_after: Some((sales[1].auction_id, sales[1].token_id.clone())),
+                    limit: Some(1),
+                    order_by: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(1, sales.len());
+        assert_eq!("2", sales[0].token_id);
+
+        let stats: Vec<SaleStats> = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.marketplace.clone(),
+                &QueryMsg::SaleStats {
+                    token_address: suite.collection.to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            vec![SaleStats {
+                token_address: suite.collection.to_string(),
+                denom: DENOM.to_owned(),
+                total_volume: Uint128::new(500),
+                sale_count: 2,
+                last_price: Uint128::new(200),
+                all_time_high: Uint128::new(300),
+            }],
+            stats
+        );
+    }
+
+    fn operators(suite: &Suite, include_expired: bool) -> Vec<Operator> {
+        suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.marketplace.clone(),
+                &QueryMsg::Operators {
+                    seller: SELLER.to_owned(),
+                    include_expired: Some(include_expired),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn operators_update_and_cancel_for_the_seller_until_they_expire() {
+        const OPERATOR: &str = "operator";
+        let mut suite = Suite::new();
+        suite.start_auction("1", None);
+        suite.start_auction("2", None);
+        let update = ExecuteMsg::UpdateAuction {
+            auction_id: Uint128::one(),
+            min_bid: Some(Uint128::new(50)),
+            end: None,
+            reserve_price: None,
+            coin_denomination: None,
+        };
+        let err = suite.execute(OPERATOR, &update, &[]).unwrap_err();
+        assert!(err.root_cause().to_string().contains("Unauthorized"));
+
+        let expires = Expiration::AtTime(cosmwasm_std::Timestamp::from_seconds(
+            suite.start + DURATION / 2,
+        ));
+        let approve = ExecuteMsg::ApproveOperator {
+            operator: OPERATOR.to_owned(),
+            expires: Some(expires),
+        };
+        suite.execute(SELLER, &approve, &[]).unwrap();
+        assert_eq!(
+            vec![Operator {
+                operator: Addr::unchecked(OPERATOR),
+                expires,
+            }],
+            operators(&suite, false)
+        );
+        suite.execute(OPERATOR, &update, &[]).unwrap();
+
+        suite.advance_to(10);
+        suite.bid(ALICE, "1", 100, None).unwrap();
+        let cancel = ExecuteMsg::CancelAuctionAndRefund {
+            token_id: "1".to_owned(),
+            token_address: suite.collection.to_string(),
+        };
+        suite.execute(OPERATOR, &cancel, &[]).unwrap();
+        assert_eq!(SELLER, suite.owner_of("1"));
+        assert_eq!(1_000, suite.balance(ALICE, DENOM));
+
+        suite.advance_to(DURATION / 2);
+        let cancel = ExecuteMsg::CancelAuctionAndRefund {
+            token_id: "2".to_owned(),
+            token_address: suite.collection.to_string(),
+        };
+        let err = suite.execute(OPERATOR, &cancel, &[]).unwrap_err();
+        assert!(err.root_cause().to_string().contains("Unauthorized"));
+        assert!(operators(&suite, false).is_empty());
+        assert_eq!(1, operators(&suite, true).len());
+
+        let revoke = ExecuteMsg::RevokeOperator {
+            operator: OPERATOR.to_owned(),
+        };
+        suite.execute(SELLER, &revoke, &[]).unwrap();
+        assert!(operators(&suite, true).is_empty());
+    }
+
+    fn start_with_mode(
+        suite: &mut Suite,
+        token_id: &str,
+        start_mode: StartMode,
+        reserve_price: Option<u128>,
+    ) -> AnyResult<AppResponse> {
+        suite.mint(SELLER, token_id);
+        let msg = Cw721CustomMsg::InitializeCW721TokenAuction {
+            start_time: 0,
+            start_mode: Some(start_mode),
+            duration: DURATION,
+            coin_denomination: DENOM.to_owned(),
+            min_bid: None,
+            reserve_price: reserve_price.map(Uint128::new),
+            access: None,
+            accepted_denoms: None,
+        };
+        suite.send_nft(SELLER, token_id, &msg)
+    }
+
+    #[test]
+    fn relative_start_modes_schedule_from_the_current_block() {
+        let mut suite = Suite::new();
+        start_with_mode(&mut suite, "1", StartMode::Now {}, None).unwrap();
+        suite.bid(ALICE, "1", 100, None).unwrap();
+
+        start_with_mode(&mut suite, "2", StartMode::After { seconds: 50 }, None).unwrap();
+        let err = suite.bid(ALICE, "2", 100, None).unwrap_err();
+        assert!(err.root_cause().to_string().contains("Auction not started"));
+        suite.advance(50);
+        suite.bid(ALICE, "2", 100, None).unwrap();
+
+        suite.advance(DURATION - 50);
+        suite.finalize("1").unwrap();
+        assert_eq!(ALICE, suite.owner_of("1"));
+        assert!(suite.finalize("2").is_err());
+        suite.advance(50);
+        suite.finalize("2").unwrap();
+        assert_eq!(ALICE, suite.owner_of("2"));
+
+        suite.mint(SELLER, "3");
+        let msg = Cw721CustomMsg::InitializeCW721TokenAuction {
+            start_time: suite.start + DURATION * 2,
+            start_mode: Some(StartMode::Now {}),
+            duration: DURATION,
+            coin_denomination: DENOM.to_owned(),
+            min_bid: None,
+            reserve_price: None,
+            access: None,
+            accepted_denoms: None,
+        };
+        let err = suite.send_nft(SELLER, "3", &msg).unwrap_err();
+        assert!(err.root_cause().to_string().contains("Invalid Start time"));
+    }
+
+    #[test]
+    fn timer_starts_on_the_first_bid_meeting_the_reserve() {
+        let mut suite = Suite::new();
+        start_with_mode(&mut suite, "1", StartMode::OnFirstBid {}, Some(500)).unwrap();
+        suite.advance(DURATION * 10);
+        assert!(suite.finalize("1").is_err());
+
+        let res = suite.bid(BOB, "1", 600, None).unwrap();
+        let end_time = (suite.app.block_info().time.seconds() + DURATION).to_string();
+        assert!(res.events.iter().any(|event| {
+            event.ty == "wasm-auction_timer_started"
+                && event
+                    .attributes
+                    .iter()
+                    .any(|attr| attr.key == "end_time" && attr.value == end_time)
+        }));
+
+        suite.advance(DURATION - 1);
+        assert!(suite.finalize("1").is_err());
+        suite.advance(1);
+        suite.finalize("1").unwrap();
+        assert_eq!(BOB, suite.owner_of("1"));
+        assert_eq!(1_600, suite.balance(SELLER, DENOM));
+    }
+
+    #[test]
+    fn auction_waiting_for_its_reserve_rejects_bids_below_it() {
+        let mut suite = Suite::new();
+        start_with_mode(&mut suite, "1", StartMode::OnFirstBid {}, Some(500)).unwrap();
+        let err = suite.bid(ALICE, "1", 100, None).unwrap_err();
+        assert!(err
+            .root_cause()
+            .to_string()
+            .contains("Bid smaller than the reserve price"));
+        // A proxy bid is accepted once its maximum covers the reserve.
+        let err = suite.bid(ALICE, "1", 400, Some(400)).unwrap_err();
+        assert!(err
+            .root_cause()
+            .to_string()
+            .contains("Bid smaller than the reserve price"));
+        let res = suite.bid(ALICE, "1", 500, Some(500)).unwrap();
+        assert!(res
+            .events
+            .iter()
+            .any(|event| event.ty == "wasm-auction_timer_started"));
+        assert_eq!(500, suite.balance(ALICE, DENOM));
+        assert_eq!(500, suite.balance(suite.marketplace.as_str(), DENOM));
+
+        // The timer is running, so the seller's cancellation policy applies again.
+        suite.update_config(config_msg(None, Some(CancelPolicy::BeforeFirstBid), None));
+        assert!(suite.cancel("1").is_err());
+    }
+
+    #[test]
+    fn start_modes_reject_times_past_the_end_of_time() {
+        let mut suite = Suite::new();
+        for (token_id, start_mode) in [
+            ("1", StartMode::After { seconds: u64::MAX }),
+            (
+                "2",
+                StartMode::After {
+                    seconds: u64::MAX / 1_000_000_000,
+                },
+            ),
+        ] {
+            let err = start_with_mode(&mut suite, token_id, start_mode, None).unwrap_err();
+            assert!(err.root_cause().to_string().contains("Invalid time"));
+        }
+    }
+}
@@ -1,26 +1,169 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
     use crate::{
+        contract::cache::StorageCache,
+        contract::helper::{delivery_reply_id, ibc_hook_sender, IBC_REFUND_REPLY_FLAG},
+        contract::ibc_refund::IBC_REFUND_TIMEOUT_SECONDS,
+        contract::ics721::ICS721_TIMEOUT_SECONDS,
+        contract::raffle::{DRAW_WINDOW_SECONDS, MAX_TICKET_HOLDERS},
         error::ContractError,
         execute, instantiate,
-        mock::{custom_mock_dependencies, MOCK_TOKEN_ADDR, MOCK_TOKEN_OWNER, MOCK_UNCLAIMED_TOKEN},
+        merkle::{hash_pair, leaf_hash, MerkleProof},
+        migrate,
+        mock::{
+            custom_mock_dependencies, MOCK_APPROVED_TOKEN, MOCK_ICS721_ADDR, MOCK_LAZY_TOKEN,
+            MOCK_TOKEN_ADDR, MOCK_TOKEN_HOLDER, MOCK_TOKEN_OWNER, MOCK_UNCLAIMED_TOKEN,
+        },
         msg::Cw721CustomMsg,
-        query,
+        msg::{
+            Cw721MinterExecuteMsg, IbcLifecycleComplete, IbcOutgoingMsg, MigrateMsg, MsgTransfer,
+            MsgTransferResponse, ProtoCoin, SudoMsg, TemplateOverrides,
+        },
+        query, reply,
         state::{
             get_bids, load_auction_details, load_bids, load_next_auction_id,
-            load_nft_auction_state, read_auction_details, save_auction_details, save_bids,
-            save_next_auction_id, save_nft_auction_state, AuctionDetails, Bid, NFTAuctionState,
-            OrderBy,
+            load_nft_auction_state, read_auction_details, read_expired_auction_ids,
+            save_auction_details, save_bids, save_next_auction_id, save_nft_auction_state,
+            AuctionDetails, AuctionTemplate, Bid, BidderAccess, BundleItem, CancelPolicy, Config,
+            ExchangeRate, IbcOrigin, Ics721Class, LazyMint, NFTAuctionState, NamedTemplate,
+            OrderBy, PendingBundle, Raffle, RandomnessSource, Rental, Sale, SaleStats,
+            TicketHolding, MAX_KEEPER_REWARD_BPS, MAX_TEMPLATE_NAME_LENGTH, RAFFLES,
         },
-        ExecuteMsg, InstantiateMsg, QueryMsg,
+        sudo, ExecuteMsg, InstantiateMsg, QueryMsg,
     };
     use cosmwasm_std::{
-        attr, coins, from_json,
-        testing::{mock_dependencies, mock_env, mock_info},
-        to_json_binary, Addr, CosmosMsg, Deps, DepsMut, Response, Timestamp, Uint128, WasmMsg,
+        attr, coin, coins, from_json,
+        testing::{mock_dependencies, mock_env, mock_info, MockStorage},
+        to_json_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, Deps, DepsMut, Empty, Env, Event,
+        Order, Reply, Response, StdResult, Storage, SubMsg, SubMsgResponse, SubMsgResult,
+        Timestamp, Uint128, WasmMsg,
     };
 
     use cw721::{Cw721ExecuteMsg, Cw721ReceiveMsg};
+    use prost::Message;
+    use sha2::{Digest, Sha256};
+
+    #[test]
+    fn test_migrate() {
+        let mut deps = mock_dependencies();
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { owner: None });
+        assert_eq!(
+            ContractError::CannotMigrate {
+                contract: String::new(),
+            },
+            res.unwrap_err()
+        );
+
+        let info = mock_info("owner", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { owner: None }).unwrap();
+        assert_eq!(
+            vec![
+                attr("action", "migrate"),
+                attr("previous_version", env!("CARGO_PKG_VERSION")),
+                attr("version", env!("CARGO_PKG_VERSION")),
+            ],
+            res.attributes
+        );
+
+        cw2::set_contract_version(deps.as_mut().storage, "crates.io:other", "1.0.0").unwrap();
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { owner: None });
+        assert_eq!(
+            ContractError::CannotMigrate {
+                contract: "crates.io:other".to_owned(),
+            },
+            res.unwrap_err()
+        );
+    }
+
+    /// `NFTAuctionState` as the first release stored it.
+    #[cosmwasm_schema::cw_serde]
+    struct BaselineAuctionState {
+        start: Timestamp,
+        end: Timestamp,
+        high_bidder_addr: Addr,
+        high_bidder_amount: Uint128,
+        coin_denomination: String,
+        auction_id: Uint128,
+        min_bid: Option<Uint128>,
+        owner: String,
+        token_id: String,
+        token_address: String,
+        is_cancelled: bool,
+    }
+
+    #[test]
+    fn test_migrate_from_baseline() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let env = mock_env();
+        let baseline_states: cw_storage_plus::Map<u128, BaselineAuctionState> =
+            cw_storage_plus::Map::new("nft_auction_state");
+        // Auction 1 was claimed and its NFT auctioned again in auction 2, which the marketplace
+        // still holds. Auction 3 was claimed and auction 4 cancelled.
+        for (auction_id, token_id, is_cancelled) in [
+            (1u128, MOCK_UNCLAIMED_TOKEN, false),
+            (2, MOCK_UNCLAIMED_TOKEN, false),
+            (3, "claimed_token", false),
+            (4, "cancelled_token", true),
+        ] {
+            let state = BaselineAuctionState {
+                start: env.block.time.minus_seconds(100),
+                end: env.block.time.minus_seconds(10),
+                high_bidder_addr: Addr::unchecked("bidder"),
+                high_bidder_amount: Uint128::new(100),
+                coin_denomination: "uluna".to_owned(),
+                auction_id: Uint128::new(auction_id),
+                min_bid: None,
+                owner: MOCK_TOKEN_OWNER.to_owned(),
+                token_id: token_id.to_owned(),
+                token_address: MOCK_TOKEN_ADDR.to_owned(),
+                is_cancelled,
+            };
+            baseline_states
+                .save(deps.as_mut().storage, auction_id, &state)
+                .unwrap();
+            let pk = token_id.to_owned() + MOCK_TOKEN_ADDR;
+            let mut details = load_auction_details(deps.as_mut().storage, &pk).unwrap_or_default();
+            details.push(Uint128::new(auction_id));
+            details.token_address = MOCK_TOKEN_ADDR.to_owned();
+            details.token_id = token_id.to_owned();
+            save_auction_details(deps.as_mut().storage, pk, details).unwrap();
+        }
+        save_next_auction_id(deps.as_mut().storage, Uint128::new(5)).unwrap();
+
+        let res = migrate(deps.as_mut(), env.clone(), MigrateMsg { owner: None });
+        assert_eq!(ContractError::MigrationOwnerRequired {}, res.unwrap_err());
+
+        let res = migrate(
+            deps.as_mut(),
+            env.clone(),
+            MigrateMsg {
+                owner: Some("owner".to_owned()),
+            },
+        )
+        .unwrap();
+        assert_eq!(attr("previous_version", "0.0.0"), res.attributes[1]);
+        let version = cw2::get_contract_version(&deps.storage).unwrap();
+        assert_eq!(env!("CARGO_PKG_VERSION"), version.version);
+
+        let config: Config =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(Config::new(Addr::unchecked("owner")), config);
+
+        let settled: Vec<bool> = (1..=4)
+            .map(|id| {
+                load_nft_auction_state(&deps.storage, id)
+                    .unwrap()
+                    .is_settled
+            })
+            .collect();
+        assert_eq!(vec![true, false, true, false], settled);
+        assert_eq!(
+            vec![2],
+            read_expired_auction_ids(&deps.storage, env.block.time, None, None).unwrap()
+        );
+    }
 
     #[test]
     fn test_save_and_load_bids() {
@@ -56,10 +199,25 @@ mod tests {
             coin_denomination: "uusd".to_string(),
             auction_id: Uint128::new(1),
             min_bid: Some(Uint128::new(50)),
+            reserve_price: None,
             owner: "owner".to_string(),
             token_id: "token1".to_string(),
             token_address: "token_address".to_string(),
             is_cancelled: false,
+            is_settled: false,
+            keeper_fee: None,
+            listing_deposit: None,
+            keeper_reward_bps: 0,
+            access: None,
+            accepted_denoms: vec![],
+            high_bid_denom: None,
+            bundle: vec![],
+            lazy_mint: None,
+            held_by_seller: false,
+            ics721_class: None,
+            timer_duration: None,
+            bid_increment: None,
+            extension: None,
         };
 
         save_nft_auction_state(&mut deps.storage, auction_id, auction_state.clone()).unwrap();
@@ -105,29 +263,204 @@ mod tests {
                 token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
                 token_address: MOCK_TOKEN_ADDR.to_owned(),
                 is_cancelled: false,
+                is_settled: false,
+                keeper_fee: None,
+                listing_deposit: None,
+                keeper_reward_bps: 0,
+                bundle: vec![],
                 min_bid,
+                reserve_price: None,
+                access: None,
+                accepted_denoms: vec![],
+                high_bid_denom: None,
+                lazy_mint: None,
+                held_by_seller: false,
+                ics721_class: None,
+                timer_duration: None,
+                bid_increment: None,
+                extension: None,
             },
             load_nft_auction_state(deps.storage, 1u128).unwrap()
         );
     }
 
-    fn start_auction(deps: DepsMut, min_bid: Option<Uint128>) {
-        let custom_msg = Cw721CustomMsg::InitializeCW721TokenAuction {
-            start_time: 100000,
-            duration: 100000,
-            coin_denomination: "usd".to_string(),
-            min_bid,
-        };
-        let msg = ExecuteMsg::AuctionStart(Cw721ReceiveMsg {
-            sender: MOCK_TOKEN_OWNER.to_owned(),
-            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
-            msg: to_json_binary(&custom_msg).unwrap(),
-        });
-        let mut env = mock_env();
-        env.block.time = Timestamp::from_seconds(0u64);
+    /// Sends `MOCK_UNCLAIMED_TOKEN` into an auction at time 0. Unless changed, the auction runs
+    /// from 100000 for 100000 seconds in "usd" with no other terms.
+    struct AuctionBuilder {
+        token_id: String,
+        start_time: u64,
+        duration: u64,
+        min_bid: Option<Uint128>,
+        reserve_price: Option<Uint128>,
+        access: Option<BidderAccess>,
+        accepted_denoms: Option<Vec<String>>,
+        listing_deposit: Option<Coin>,
+        bridged: bool,
+    }
 
-        let info = mock_info(MOCK_TOKEN_ADDR, &[]);
-        let _res = execute(deps, env, info, msg).unwrap();
+    impl AuctionBuilder {
+        fn new() -> Self {
+            AuctionBuilder {
+                token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+                start_time: 100000,
+                duration: 100000,
+                min_bid: None,
+                reserve_price: None,
+                access: None,
+                accepted_denoms: None,
+                listing_deposit: None,
+                bridged: false,
+            }
+        }
+
+        fn token(mut self, token_id: &str) -> Self {
+            self.token_id = token_id.to_owned();
+            self
+        }
+
+        fn window(mut self, start_time: u64, duration: u64) -> Self {
+            self.start_time = start_time;
+            self.duration = duration;
+            self
+        }
+
+        fn min_bid(mut self, min_bid: u128) -> Self {
+            self.min_bid = Some(Uint128::new(min_bid));
+            self
+        }
+
+        fn reserve_price(mut self, reserve_price: u128) -> Self {
+            self.reserve_price = Some(Uint128::new(reserve_price));
+            self
+        }
+
+        fn access(mut self, access: BidderAccess) -> Self {
+            self.access = Some(access);
+            self
+        }
+
+        fn accepted_denoms(mut self, denoms: &[&str]) -> Self {
+            self.accepted_denoms = Some(denoms.iter().map(|denom| denom.to_string()).collect());
+            self
+        }
+
+        /// Configures a listing deposit and deposits it for the seller first.
+        fn listing_deposit(mut self, deposit: Coin) -> Self {
+            self.listing_deposit = Some(deposit);
+            self
+        }
+
+        /// Configures `MOCK_ICS721_ADDR` first, whose proxy collection is `MOCK_TOKEN_ADDR`.
+        fn bridged(mut self) -> Self {
+            self.bridged = true;
+            self
+        }
+
+        fn start(self, mut deps: DepsMut) -> Result<Response, ContractError> {
+            if self.listing_deposit.is_some() || self.bridged {
+                let msg = ExecuteMsg::UpdateConfig {
+                    owner: None,
+                    keeper_reward_bps: None,
+                    keeper_fee: None,
+                    cancel_policy: None,
+                    listing_deposit: self.listing_deposit.clone(),
+                    bid_increment: None,
+                    randomness_beacon: None,
+                    ics721: self.bridged.then(|| MOCK_ICS721_ADDR.to_owned()),
+                };
+                execute(deps.branch(), mock_env(), mock_info("owner", &[]), msg)?;
+            }
+            if let Some(deposit) = &self.listing_deposit {
+                let info = mock_info(MOCK_TOKEN_OWNER, std::slice::from_ref(deposit));
+                execute(
+                    deps.branch(),
+                    mock_env(),
+                    info,
+                    ExecuteMsg::DepositBalance {},
+                )?;
+            }
+            let custom_msg = Cw721CustomMsg::InitializeCW721TokenAuction {
+                start_time: self.start_time,
+                start_mode: None,
+                duration: self.duration,
+                coin_denomination: "usd".to_string(),
+                min_bid: self.min_bid,
+                reserve_price: self.reserve_price,
+                access: self.access,
+                accepted_denoms: self.accepted_denoms,
+            };
+            let msg = ExecuteMsg::AuctionStart(Cw721ReceiveMsg {
+                sender: MOCK_TOKEN_OWNER.to_owned(),
+                token_id: self.token_id,
+                msg: to_json_binary(&custom_msg).unwrap(),
+            });
+            let mut env = mock_env();
+            env.block.time = Timestamp::from_seconds(0u64);
+            execute(deps, env, mock_info(MOCK_TOKEN_ADDR, &[]), msg)
+        }
+    }
+
+    /// Bids on the auction of `MOCK_UNCLAIMED_TOKEN`, or of another token of `MOCK_TOKEN_ADDR`.
+    /// Unless changed, the bid is a plain "usd" bid at 150000.
+    struct BidBuilder {
+        token_id: String,
+        bidder: String,
+        funds: Coin,
+        proof: Option<MerkleProof>,
+        proxy: bool,
+        ibc_origin: Option<IbcOrigin>,
+    }
+
+    impl BidBuilder {
+        fn new(bidder: &str, amount: u128) -> Self {
+            BidBuilder {
+                token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+                bidder: bidder.to_owned(),
+                funds: coin(amount, "usd"),
+                proof: None,
+                proxy: false,
+                ibc_origin: None,
+            }
+        }
+
+        fn denom(mut self, denom: &str) -> Self {
+            self.funds.denom = denom.to_owned();
+            self
+        }
+
+        fn token(mut self, token_id: &str) -> Self {
+            self.token_id = token_id.to_owned();
+            self
+        }
+
+        fn proof(mut self, proof: MerkleProof) -> Self {
+            self.proof = Some(proof);
+            self
+        }
+
+        /// Makes the attached funds the bidder's proxy maximum.
+        fn proxy(mut self) -> Self {
+            self.proxy = true;
+            self
+        }
+
+        fn ibc(mut self, origin: IbcOrigin) -> Self {
+            self.ibc_origin = Some(origin);
+            self
+        }
+
+        fn submit(self, deps: DepsMut) -> Result<Response, ContractError> {
+            let mut env = mock_env();
+            env.block.time = Timestamp::from_seconds(150000);
+            let msg = ExecuteMsg::SubmitBid {
+                token_id: self.token_id,
+                token_address: MOCK_TOKEN_ADDR.to_string(),
+                proof: self.proof,
+                max_amount: self.proxy.then_some(self.funds.amount),
+                ibc_origin: self.ibc_origin,
+            };
+            execute(deps, env, mock_info(&self.bidder, &[self.funds]), msg)
+        }
     }
 
     #[test]
@@ -142,6 +475,10 @@ mod tests {
             duration: 100000,
             coin_denomination: "usd".to_string(),
             min_bid: None,
+            reserve_price: None,
+            access: None,
+            accepted_denoms: None,
+            start_mode: None,
         };
         let msg = ExecuteMsg::AuctionStart(Cw721ReceiveMsg {
             sender: MOCK_TOKEN_OWNER.to_owned(),
@@ -156,17 +493,42 @@ mod tests {
 
         assert_eq!(
             res,
-            Response::new().add_attributes(vec![
-                attr("action", "start_auction"),
-                attr("start_time", "100000.000000000"),
-                attr("end_time", "200000.000000000"),
-                attr("coin_denomination", "usd"),
-                attr("auction_id", "1"),
-            ]),
+            Response::new()
+                .add_attribute("action", "start_auction")
+                .add_event(
+                    Event::new("auction_created")
+                        .add_attribute("auction_id", "1")
+                        .add_attribute("seller", MOCK_TOKEN_OWNER)
+                        .add_attribute("token_address", MOCK_TOKEN_ADDR)
+                        .add_attribute("token_id", MOCK_UNCLAIMED_TOKEN)
+                        .add_attribute("lot_size", "1")
+                        .add_attribute("start_time", "100000")
+                        .add_attribute("end_time", "200000")
+                        .add_attribute("denom", "usd")
+                ),
         );
         check_auction_created(deps.as_ref(), None);
     }
 
+    #[test]
+    fn test_receive_nft_accepts_its_old_name() {
+        let receive = Cw721ReceiveMsg {
+            sender: MOCK_TOKEN_OWNER.to_owned(),
+            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+            msg: to_json_binary(&Empty {}).unwrap(),
+        };
+        let body = String::from_utf8(to_json_binary(&receive).unwrap().to_vec()).unwrap();
+        for name in ["receive_nft", "auction_start"] {
+            let msg: ExecuteMsg = from_json(format!(r#"{{"{name}":{body}}}"#)).unwrap();
+            assert_eq!(ExecuteMsg::AuctionStart(receive.clone()), msg);
+        }
+        let sent = to_json_binary(&ExecuteMsg::AuctionStart(receive)).unwrap();
+        assert_eq!(
+            format!(r#"{{"receive_nft":{body}}}"#).as_bytes(),
+            sent.as_slice()
+        );
+    }
+
     #[test]
     fn test_submit_bid_for_auction_non_existing_auction() {
         let mut deps = mock_dependencies();
@@ -178,6 +540,9 @@ mod tests {
         let msg = ExecuteMsg::SubmitBid {
             token_id: MOCK_UNCLAIMED_TOKEN.to_string(),
             token_address: MOCK_TOKEN_ADDR.to_string(),
+            proof: None,
+            max_amount: None,
+            ibc_origin: None,
         };
         let info = mock_info("bidder", &coins(100, "usd"));
         let res = execute(deps.as_mut(), env, info, msg);
@@ -192,15 +557,18 @@ mod tests {
         let msg = InstantiateMsg {};
         let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
-        start_auction(deps.as_mut(), None);
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
         check_auction_created(deps.as_ref(), None);
 
         let msg = ExecuteMsg::SubmitBid {
             token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
             token_address: MOCK_TOKEN_ADDR.to_string(),
+            proof: None,
+            max_amount: None,
+            ibc_origin: None,
         };
 
-        env.block.time = Timestamp::from_seconds(300);
+        env.block.time = Timestamp::from_seconds(300000);
 
         let info = mock_info("sender", &coins(100, "usd".to_string()));
         let res = execute(deps.as_mut(), env, info, msg);
@@ -220,6 +588,10 @@ mod tests {
             duration: 1,
             coin_denomination: "usd".to_string(),
             min_bid: None,
+            reserve_price: None,
+            access: None,
+            accepted_denoms: None,
+            start_mode: None,
         };
         let msg = ExecuteMsg::AuctionStart(Cw721ReceiveMsg {
             sender: MOCK_TOKEN_OWNER.to_owned(),
@@ -248,6 +620,10 @@ mod tests {
             duration: 0,
             coin_denomination: "usd".to_string(),
             min_bid: None,
+            reserve_price: None,
+            access: None,
+            accepted_denoms: None,
+            start_mode: None,
         };
         let msg = ExecuteMsg::AuctionStart(Cw721ReceiveMsg {
             sender: MOCK_TOKEN_OWNER.to_owned(),
@@ -271,28 +647,37 @@ mod tests {
         let msg = InstantiateMsg {};
         let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
-        start_auction(deps.as_mut(), None);
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
 
         let msg = ExecuteMsg::CancelAuctionAndRefund {
             token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
             token_address: MOCK_TOKEN_ADDR.to_string(),
         };
 
-        env.block.time = Timestamp::from_seconds(200000);
+        env.block.time = Timestamp::from_seconds(150000);
 
         let info = mock_info(MOCK_TOKEN_OWNER, &[]);
         let res = execute(deps.as_mut(), env, info, msg).unwrap();
 
         assert_eq!(
-            Response::new().add_message(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: MOCK_TOKEN_ADDR.to_owned(),
-                msg: to_json_binary(&Cw721ExecuteMsg::TransferNft {
-                    recipient: MOCK_TOKEN_OWNER.to_owned(),
-                    token_id: MOCK_UNCLAIMED_TOKEN.to_owned()
-                })
-                .unwrap(),
-                funds: vec![],
-            })),
+            Response::new()
+                .add_attribute("action", "cancel_auction")
+                .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: MOCK_TOKEN_ADDR.to_owned(),
+                    msg: to_json_binary(&Cw721ExecuteMsg::TransferNft {
+                        recipient: MOCK_TOKEN_OWNER.to_owned(),
+                        token_id: MOCK_UNCLAIMED_TOKEN.to_owned()
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                }))
+                .add_event(
+                    Event::new("auction_cancelled")
+                        .add_attribute("auction_id", "1")
+                        .add_attribute("seller", MOCK_TOKEN_OWNER)
+                        .add_attribute("had_bids", "false")
+                        .add_attribute("penalty", "0")
+                ),
             res
         );
 
@@ -311,7 +696,7 @@ mod tests {
         let msg = InstantiateMsg {};
         let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
-        start_auction(deps.as_mut(), None);
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
 
         let msg = ExecuteMsg::CancelAuctionAndRefund {
             token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
@@ -333,14 +718,14 @@ mod tests {
         let msg = InstantiateMsg {};
         let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
-        start_auction(deps.as_mut(), None);
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
 
         let msg = ExecuteMsg::CancelAuctionAndRefund {
             token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
             token_address: MOCK_TOKEN_ADDR.to_string(),
         };
 
-        env.block.time = Timestamp::from_seconds(300);
+        env.block.time = Timestamp::from_seconds(300000);
 
         let info = mock_info(MOCK_TOKEN_OWNER, &[]);
         let res = execute(deps.as_mut(), env, info, msg);
@@ -355,9 +740,9 @@ mod tests {
         let msg = InstantiateMsg {};
         let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
-        start_auction(deps.as_mut(), None);
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
 
-        env.block.time = Timestamp::from_seconds(250);
+        env.block.time = Timestamp::from_seconds(250000);
 
         let msg = ExecuteMsg::FinalizeAuctionAndTranferAssets {
             token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
@@ -368,21 +753,30 @@ mod tests {
         let res = execute(deps.as_mut(), env, info, msg).unwrap();
         assert_eq!(
             Response::new()
-                .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
-                    contract_addr: MOCK_TOKEN_ADDR.to_owned(),
-                    msg: to_json_binary(&Cw721ExecuteMsg::TransferNft {
-                        recipient: MOCK_TOKEN_OWNER.to_owned(),
-                        token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
-                    })
-                    .unwrap(),
-                    funds: vec![],
-                }))
+                .add_submessage(delivery(
+                    1,
+                    0,
+                    WasmMsg::Execute {
+                        contract_addr: MOCK_TOKEN_ADDR.to_owned(),
+                        msg: to_json_binary(&Cw721ExecuteMsg::TransferNft {
+                            recipient: MOCK_TOKEN_OWNER.to_owned(),
+                            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+                        })
+                        .unwrap(),
+                        funds: vec![],
+                    }
+                ))
                 .add_attribute("action", "claim")
-                .add_attribute("token_id", MOCK_UNCLAIMED_TOKEN)
-                .add_attribute("token_contract", MOCK_TOKEN_ADDR)
-                .add_attribute("recipient", MOCK_TOKEN_OWNER)
-                .add_attribute("winning_bid_amount", Uint128::zero())
-                .add_attribute("auction_id", "1"),
+                .add_event(
+                    Event::new("auction_settled")
+                        .add_attribute("auction_id", "1")
+                        .add_attribute("seller", MOCK_TOKEN_OWNER)
+                        .add_attribute("recipient", MOCK_TOKEN_OWNER)
+                        .add_attribute("winning_bid", "0")
+                        .add_attribute("denom", "usd")
+                        .add_attribute("reserve_met", "true")
+                        .add_attribute("keeper", "any_user")
+                ),
             res
         );
     }
@@ -400,6 +794,10 @@ mod tests {
             duration: 100000,
             coin_denomination: "usd".to_string(),
             min_bid: None,
+            reserve_price: None,
+            access: None,
+            accepted_denoms: None,
+            start_mode: None,
         };
         let msg = ExecuteMsg::AuctionStart(Cw721ReceiveMsg {
             sender: MOCK_TOKEN_OWNER.to_owned(),
@@ -413,7 +811,7 @@ mod tests {
         let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
         // Auction is over.
-        env.block.time = Timestamp::from_seconds(300);
+        env.block.time = Timestamp::from_seconds(300000);
 
         let msg = ExecuteMsg::FinalizeAuctionAndTranferAssets {
             token_id: "claimed_token".to_string(),
@@ -426,204 +824,3842 @@ mod tests {
     }
 
     #[test]
-    fn test_query_start_auction() {
-        let mut deps = mock_dependencies();
-        let env = mock_env();
+    fn test_submit_bid_and_finalize_auction() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let mut env = mock_env();
         let info = mock_info("owner", &[]);
-        let _res = instantiate(deps.as_mut(), env, info, InstantiateMsg {}).unwrap();
+        let _res = instantiate(deps.as_mut(), env.clone(), info, InstantiateMsg {}).unwrap();
 
-        let custom_msg = Cw721CustomMsg::InitializeCW721TokenAuction {
-            start_time: 100000,
-            duration: 100000,
-            coin_denomination: "usd".to_string(),
-            min_bid: None,
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
+
+        env.block.time = Timestamp::from_seconds(150000);
+        let msg = ExecuteMsg::SubmitBid {
+            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+            token_address: MOCK_TOKEN_ADDR.to_string(),
+            proof: None,
+            max_amount: None,
+            ibc_origin: None,
         };
-        let msg = ExecuteMsg::AuctionStart(Cw721ReceiveMsg {
-            sender: MOCK_TOKEN_OWNER.to_owned(),
+        let info = mock_info("bidder", &coins(100, "usd"));
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::FinalizeAuctionAndTranferAssets {
             token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
-            msg: to_json_binary(&custom_msg).unwrap(),
-        });
-        let mut env = mock_env();
-        env.block.time = Timestamp::from_seconds(0u64);
+            token_address: MOCK_TOKEN_ADDR.to_string(),
+        };
+        let info = mock_info("any_user", &[]);
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+        assert_eq!(ContractError::AuctionNotEnded {}, res.unwrap_err());
 
-        let info = mock_info(MOCK_TOKEN_ADDR, &[]);
-        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        env.block.time = Timestamp::from_seconds(200000);
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+        assert_eq!(
+            res.messages
+                .into_iter()
+                .map(|sub_msg| sub_msg.msg)
+                .collect::<Vec<_>>(),
+            vec![
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: MOCK_TOKEN_OWNER.to_owned(),
+                    amount: coins(100, "usd"),
+                }),
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: MOCK_TOKEN_ADDR.to_owned(),
+                    msg: to_json_binary(&Cw721ExecuteMsg::TransferNft {
+                        recipient: "bidder".to_owned(),
+                        token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                }),
+            ]
+        );
+        assert!(
+            load_nft_auction_state(deps.as_ref().storage, 1u128)
+                .unwrap()
+                .is_settled
+        );
 
-        let msg = ExecuteMsg::AuctionStart(Cw721ReceiveMsg {
-            sender: "foo_token_owner".to_owned(),
-            token_id: "foo_token".to_owned(),
-            msg: to_json_binary(&custom_msg).unwrap(),
-        });
+        let res = execute(deps.as_mut(), env, info, msg);
+        assert_eq!(ContractError::AuctionAlreadyClaimed {}, res.unwrap_err());
+    }
 
-        let info = mock_info(MOCK_TOKEN_ADDR, &[]);
-        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    #[test]
+    fn test_finalize_many_skips_unsettleable_auctions() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let mut env = mock_env();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), env.clone(), info, InstantiateMsg {}).unwrap();
 
-        check_auction_created(deps.as_ref(), None);
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
 
-        let query_msg = QueryMsg::AuctionDetails {
-            token_address: Some(MOCK_TOKEN_ADDR.to_string()),
-            start_after: Some("e".to_string()),
-            limit: Some(10),
+        env.block.time = Timestamp::from_seconds(250000);
+        let msg = ExecuteMsg::FinalizeMany {
+            auction_ids: vec![1u128.into(), 1u128.into(), 7u128.into()],
         };
-        let res: Vec<AuctionDetails> =
-            from_json(&query(deps.as_ref(), env.clone(), query_msg).unwrap()).unwrap();
+        let info = mock_info("keeper", &[]);
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+
+        assert_eq!(res.messages.len(), 1);
         assert_eq!(
+            res.events,
             vec![
-                AuctionDetails {
-                    auction_ids: vec![Uint128::from(2u128)],
-                    token_address: MOCK_TOKEN_ADDR.to_string(),
-                    token_id: "foo_token".to_string(),
-                },
-                AuctionDetails {
-                    auction_ids: vec![Uint128::from(1u128)],
-                    token_address: MOCK_TOKEN_ADDR.to_string(),
-                    token_id: "mock_unclaimed_token".to_string(),
-                }
-            ],
-            res
+                Event::new("auction_settled")
+                    .add_attribute("auction_id", "1")
+                    .add_attribute("seller", MOCK_TOKEN_OWNER)
+                    .add_attribute("recipient", MOCK_TOKEN_OWNER)
+                    .add_attribute("winning_bid", "0")
+                    .add_attribute("denom", "usd")
+                    .add_attribute("reserve_met", "true")
+                    .add_attribute("keeper", "keeper"),
+                Event::new("auction_settle_skipped")
+                    .add_attribute("auction_id", "1")
+                    .add_attribute(
+                        "reason",
+                        ContractError::AuctionAlreadyClaimed {}.to_string()
+                    ),
+                Event::new("auction_settle_skipped")
+                    .add_attribute("auction_id", "7")
+                    .add_attribute("reason", ContractError::AuctionDoesNotExist {}.to_string()),
+            ]
         );
+    }
 
-        let query_msg = QueryMsg::AuctionDetails {
-            token_address: Some(MOCK_TOKEN_ADDR.to_string()),
-            start_after: Some("g".to_string()),
-            limit: Some(10),
+    #[test]
+    fn test_finalize_expired_settles_only_ended_auctions() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let mut env = mock_env();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), env.clone(), info, InstantiateMsg {}).unwrap();
+
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
+
+        // A second auction that ends later than the first one.
+        let hook_msg = Cw721CustomMsg::InitializeCW721TokenAuction {
+            start_time: 100000,
+            duration: 500000,
+            coin_denomination: "usd".to_string(),
+            min_bid: None,
+            reserve_price: None,
+            access: None,
+            accepted_denoms: None,
+            start_mode: None,
         };
-        let res: Vec<AuctionDetails> =
-            from_json(&query(deps.as_ref(), env.clone(), query_msg).unwrap()).unwrap();
-        assert_eq!(
-            vec![AuctionDetails {
-                auction_ids: vec![Uint128::from(1u128)],
-                token_address: MOCK_TOKEN_ADDR.to_string(),
-                token_id: "mock_unclaimed_token".to_string(),
-            }],
-            res
-        );
+        let msg = ExecuteMsg::AuctionStart(Cw721ReceiveMsg {
+            sender: MOCK_TOKEN_OWNER.to_owned(),
+            token_id: "later_token".to_string(),
+            msg: to_json_binary(&hook_msg).unwrap(),
+        });
+        env.block.time = Timestamp::from_seconds(0);
+        let info = mock_info(MOCK_TOKEN_ADDR, &[]);
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
-        let query_msg = QueryMsg::AuctionDetails {
-            token_address: None,
+        env.block.time = Timestamp::from_seconds(250000);
+        let msg = ExecuteMsg::FinalizeExpired {
             start_after: None,
-            limit: Some(10),
+            limit: None,
         };
-        let res: Vec<AuctionDetails> =
-            from_json(&query(deps.as_ref(), env, query_msg).unwrap()).unwrap();
+        let info = mock_info("keeper", &[]);
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.events.len(), 1);
+        assert!(
+            load_nft_auction_state(deps.as_ref().storage, 1u128)
+                .unwrap()
+                .is_settled
+        );
+        assert!(
+            !load_nft_auction_state(deps.as_ref().storage, 2u128)
+                .unwrap()
+                .is_settled
+        );
+
+        // Already settled auctions are not visited again.
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert!(res.messages.is_empty());
+        assert!(res.events.is_empty());
         assert_eq!(
-            vec![
-                AuctionDetails {
-                    auction_ids: vec![Uint128::from(2u128)],
-                    token_address: MOCK_TOKEN_ADDR.to_string(),
-                    token_id: "foo_token".to_string(),
-                },
-                AuctionDetails {
-                    auction_ids: vec![Uint128::from(1u128)],
-                    token_address: MOCK_TOKEN_ADDR.to_string(),
-                    token_id: "mock_unclaimed_token".to_string(),
-                }
-            ],
-            res
+            vec![2],
+            read_expired_auction_ids(
+                deps.as_ref().storage,
+                Timestamp::from_seconds(700000),
+                None,
+                None
+            )
+            .unwrap()
         );
     }
 
     #[test]
-    fn test_get_bids_defaults() {
-        let mut deps = mock_dependencies();
-        let auction_id: u128 = 1;
-        // Assume some Bids are already stored
-        let sample_bids = vec![
-            Bid {
-                bidder: "Alice".to_string(),
-                amount: Uint128::from(100u128),
-                timestamp: Timestamp::from_seconds(1000),
-            },
-            Bid {
-                bidder: "Bob".to_string(),
-                amount: Uint128::from(200u128),
-                timestamp: Timestamp::from_seconds(1001),
-            },
-        ];
-        save_bids(&mut deps.storage, auction_id, sample_bids).unwrap();
+    fn test_finalize_expired_pages_past_auctions_that_cannot_settle() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        // The marketplace never received the NFTs of the first two auctions.
+        for token_id in ["not_held_1", "not_held_2", MOCK_UNCLAIMED_TOKEN] {
+            AuctionBuilder::new()
+                .token(token_id)
+                .start(deps.as_mut())
+                .unwrap();
+        }
 
-        let result = get_bids(&deps.storage, auction_id, None, None, None).unwrap();
-        assert_eq!(result.len(), 2); // Default limit is 20, so should show both bids
-        assert_eq!(result[0].bidder, "Alice");
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(250000);
+        let info = mock_info("keeper", &[]);
+        let finalize = |start_after: Option<u128>| ExecuteMsg::FinalizeExpired {
+            start_after: start_after.map(Uint128::new),
+            limit: Some(2),
+        };
+        for _ in 0..2 {
+            let res = execute(deps.as_mut(), env.clone(), info.clone(), finalize(None)).unwrap();
+            assert_eq!(
+                vec!["auction_settle_skipped", "auction_settle_skipped"],
+                res.events.iter().map(|e| e.ty.as_str()).collect::<Vec<_>>()
+            );
+        }
+
+        let res = execute(deps.as_mut(), env, info, finalize(Some(2))).unwrap();
+        assert_eq!("auction_settled", res.events[0].ty);
+        assert!(
+            load_nft_auction_state(deps.as_ref().storage, 3u128)
+                .unwrap()
+                .is_settled
+        );
+    }
+
+    fn set_keeper_config(deps: DepsMut, keeper_reward_bps: Option<u64>, keeper_fee: Option<Coin>) {
+        let msg = ExecuteMsg::UpdateConfig {
+            owner: None,
+            keeper_reward_bps,
+            keeper_fee,
+            cancel_policy: None,
+            listing_deposit: None,
+            bid_increment: None,
+            randomness_beacon: None,
+            ics721: None,
+        };
+        let info = mock_info("owner", &[]);
+        execute(deps, mock_env(), info, msg).unwrap();
     }
 
     #[test]
-    fn test_get_bids_desc_order() {
+    fn test_update_config() {
         let mut deps = mock_dependencies();
-        let auction_id: u128 = 1;
-        let sample_bids = vec![
-            Bid {
-                bidder: "Alice".to_string(),
-                amount: Uint128::from(100u128),
-                timestamp: Timestamp::from_seconds(1000),
+        let env = mock_env();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), env.clone(), info, InstantiateMsg {}).unwrap();
+
+        let msg = ExecuteMsg::UpdateConfig {
+            owner: None,
+            keeper_reward_bps: Some(100),
+            keeper_fee: None,
+            cancel_policy: None,
+            listing_deposit: None,
+            bid_increment: None,
+            randomness_beacon: None,
+            ics721: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg);
+        assert_eq!(ContractError::Unauthorized {}, res.unwrap_err());
+
+        let msg = ExecuteMsg::UpdateConfig {
+            owner: None,
+            keeper_reward_bps: Some(501),
+            keeper_fee: None,
+            cancel_policy: None,
+            listing_deposit: None,
+            bid_increment: None,
+            randomness_beacon: None,
+            ics721: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg);
+        assert_eq!(
+            ContractError::KeeperRewardTooHigh {
+                max: MAX_KEEPER_REWARD_BPS,
             },
-            Bid {
-                bidder: "Bob".to_string(),
-                amount: Uint128::from(200u128),
-                timestamp: Timestamp::from_seconds(1001),
+            res.unwrap_err()
+        );
+
+        set_keeper_config(deps.as_mut(), Some(100), Some(coin(10, "usd")));
+        let config: Config =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(
+            Config {
+                owner: Addr::unchecked("owner"),
+                keeper_reward_bps: 100,
+                keeper_fee: Some(coin(10, "usd")),
+                cancel_policy: CancelPolicy::Unrestricted,
+                listing_deposit: None,
+                bid_increment: Uint128::one(),
+                randomness_beacon: None,
+                ics721: None,
             },
-        ];
-        save_bids(&mut deps.storage, auction_id, sample_bids).unwrap();
+            config
+        );
 
-        let result = get_bids(
-            &deps.storage,
-            auction_id,
-            None,
-            Some(10),
-            Some(OrderBy::Desc),
+        set_keeper_config(deps.as_mut(), None, Some(coin(0, "usd")));
+        let config: Config =
+            from_json(query(deps.as_ref(), env, QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(None, config.keeper_fee);
+        assert_eq!(100, config.keeper_reward_bps);
+    }
+
+    #[test]
+    fn test_deposit_and_withdraw_balance() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), env.clone(), info, InstantiateMsg {}).unwrap();
+
+        let info = mock_info("seller", &coins(100, "usd"));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            ExecuteMsg::DepositBalance {},
         )
         .unwrap();
-        assert_eq!(result.len(), 2);
-        assert_eq!(result[0].bidder, "Bob"); // Order should be descending by timestamp
+
+        let msg = ExecuteMsg::WithdrawBalance {
+            amount: coin(101, "usd"),
+        };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("seller", &[]), msg);
+        assert_eq!(ContractError::InsufficientBalance {}, res.unwrap_err());
+
+        let msg = ExecuteMsg::WithdrawBalance {
+            amount: coin(40, "usd"),
+        };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("seller", &[]), msg).unwrap();
+        assert_eq!(
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "seller".to_string(),
+                amount: coins(40, "usd"),
+            })],
+            res.messages
+        );
+
+        let msg = QueryMsg::Balance {
+            address: "seller".to_string(),
+        };
+        let balance: Vec<Coin> = from_json(query(deps.as_ref(), env, msg).unwrap()).unwrap();
+        assert_eq!(coins(60, "usd"), balance);
     }
 
     #[test]
-    fn test_read_auction_details_no_token_address() {
-        let mut deps = mock_dependencies();
+    fn test_finalize_pays_keeper_reward_from_winning_bid() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let mut env = mock_env();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), env.clone(), info, InstantiateMsg {}).unwrap();
+        set_keeper_config(deps.as_mut(), Some(250), Some(coin(10, "usd")));
 
-        // Setup an example AuctionDetails entry
-        let details = AuctionDetails {
-            auction_ids: vec![Uint128::new(1)],
-            token_address: "cosmos_token_address".to_string(),
-            token_id: "cosmos_token_id".to_string(),
+        let info = mock_info(MOCK_TOKEN_OWNER, &coins(10, "usd"));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            ExecuteMsg::DepositBalance {},
+        )
+        .unwrap();
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
+
+        env.block.time = Timestamp::from_seconds(150000);
+        let msg = ExecuteMsg::SubmitBid {
+            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+            token_address: MOCK_TOKEN_ADDR.to_string(),
+            proof: None,
+            max_amount: None,
+            ibc_origin: None,
         };
-        save_auction_details(
-            &mut deps.storage,
-            "cosmos_token_address".to_string(),
-            details,
+        let info = mock_info("bidder", &coins(1000, "usd"));
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        // The auction keeps the reward rate it was created under.
+        set_keeper_config(deps.as_mut(), Some(500), None);
+
+        env.block.time = Timestamp::from_seconds(200000);
+        let msg = ExecuteMsg::FinalizeAuctionAndTranferAssets {
+            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+            token_address: MOCK_TOKEN_ADDR.to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("keeper", &[]), msg).unwrap();
+        assert_eq!(
+            vec![
+                SubMsg::new(BankMsg::Send {
+                    to_address: MOCK_TOKEN_OWNER.to_owned(),
+                    amount: coins(975, "usd"),
+                }),
+                delivery(
+                    1,
+                    0,
+                    WasmMsg::Execute {
+                        contract_addr: MOCK_TOKEN_ADDR.to_owned(),
+                        msg: to_json_binary(&Cw721ExecuteMsg::TransferNft {
+                            recipient: "bidder".to_owned(),
+                            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+                        })
+                        .unwrap(),
+                        funds: vec![],
+                    }
+                ),
+                SubMsg::new(BankMsg::Send {
+                    to_address: "keeper".to_owned(),
+                    amount: coins(25, "usd"),
+                }),
+            ],
+            res.messages
+        );
+
+        // The escrowed flat fee is returned to the seller.
+        let msg = QueryMsg::Balance {
+            address: MOCK_TOKEN_OWNER.to_string(),
+        };
+        let balance: Vec<Coin> = from_json(query(deps.as_ref(), env, msg).unwrap()).unwrap();
+        assert_eq!(coins(10, "usd"), balance);
+    }
+
+    #[test]
+    fn test_finalize_pays_keeper_fee_without_bids() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let mut env = mock_env();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), env.clone(), info, InstantiateMsg {}).unwrap();
+        set_keeper_config(deps.as_mut(), Some(250), Some(coin(10, "usd")));
+
+        // The seller has not escrowed the keeper fee yet.
+        let custom_msg = Cw721CustomMsg::InitializeCW721TokenAuction {
+            start_time: 100000,
+            duration: 100000,
+            coin_denomination: "usd".to_string(),
+            min_bid: None,
+            reserve_price: None,
+            access: None,
+            accepted_denoms: None,
+            start_mode: None,
+        };
+        let msg = ExecuteMsg::AuctionStart(Cw721ReceiveMsg {
+            sender: MOCK_TOKEN_OWNER.to_owned(),
+            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+            msg: to_json_binary(&custom_msg).unwrap(),
+        });
+        env.block.time = Timestamp::from_seconds(0);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(MOCK_TOKEN_ADDR, &[]),
+            msg,
+        );
+        assert_eq!(ContractError::InsufficientBalance {}, res.unwrap_err());
+
+        let info = mock_info(MOCK_TOKEN_OWNER, &coins(10, "usd"));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            ExecuteMsg::DepositBalance {},
         )
         .unwrap();
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
+        assert_eq!(
+            Some(coin(10, "usd")),
+            load_nft_auction_state(deps.as_ref().storage, 1u128)
+                .unwrap()
+                .keeper_fee
+        );
 
-        let result = read_auction_details(&deps.storage, None, None, None).unwrap();
-        assert_eq!(result.len(), 1);
-        assert_eq!(result[0].token_address, "cosmos_token_address");
+        env.block.time = Timestamp::from_seconds(200000);
+        let msg = ExecuteMsg::FinalizeAuctionAndTranferAssets {
+            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+            token_address: MOCK_TOKEN_ADDR.to_string(),
+        };
+        let res = execute(deps.as_mut(), env, mock_info("keeper", &[]), msg).unwrap();
+        assert_eq!(
+            SubMsg::new(BankMsg::Send {
+                to_address: "keeper".to_owned(),
+                amount: coins(10, "usd"),
+            }),
+            res.messages[1]
+        );
+        assert_eq!(2, res.messages.len());
+    }
+
+    fn create_bundle(deps: DepsMut) {
+        let msg = ExecuteMsg::CreateBundleAuction {
+            items: vec![
+                BundleItem {
+                    token_address: MOCK_TOKEN_ADDR.to_string(),
+                    token_id: MOCK_UNCLAIMED_TOKEN.to_string(),
+                },
+                BundleItem {
+                    token_address: MOCK_TOKEN_ADDR.to_string(),
+                    token_id: "token_b".to_string(),
+                },
+            ],
+            start_time: 100000,
+            duration: 100000,
+            coin_denomination: "usd".to_string(),
+            min_bid: None,
+            reserve_price: None,
+            accepted_denoms: None,
+        };
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(0);
+        execute(deps, env, mock_info(MOCK_TOKEN_OWNER, &[]), msg).unwrap();
+    }
+
+    fn send_to_bundle(deps: DepsMut, token_id: &str) -> Result<Response, ContractError> {
+        let msg = ExecuteMsg::AuctionStart(Cw721ReceiveMsg {
+            sender: MOCK_TOKEN_OWNER.to_owned(),
+            token_id: token_id.to_owned(),
+            msg: to_json_binary(&Cw721CustomMsg::AddToBundle {
+                bundle_id: Uint128::new(1),
+            })
+            .unwrap(),
+        });
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(0);
+        execute(deps, env, mock_info(MOCK_TOKEN_ADDR, &[]), msg)
     }
 
     #[test]
-    fn test_read_auction_details_with_token_address() {
+    fn test_create_bundle_auction_invalid_items() {
         let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), env.clone(), info, InstantiateMsg {}).unwrap();
 
-        // Setup multiple AuctionDetails entries
-        let details = AuctionDetails {
-            auction_ids: vec![Uint128::new(1), Uint128::new(2)],
-            token_address: "specific_token_address".to_string(),
-            token_id: "specific_token_id".to_string(),
+        let item = BundleItem {
+            token_address: MOCK_TOKEN_ADDR.to_string(),
+            token_id: "token_a".to_string(),
         };
-        save_auction_details(
-            &mut deps.storage,
-            "specific_token_address".to_string(),
-            details,
+        env.block.time = Timestamp::from_seconds(0);
+        for items in [vec![item.clone()], vec![item.clone(), item]] {
+            let msg = ExecuteMsg::CreateBundleAuction {
+                items,
+                start_time: 100000,
+                duration: 100000,
+                coin_denomination: "usd".to_string(),
+                min_bid: None,
+                reserve_price: None,
+                accepted_denoms: None,
+            };
+            let res = execute(deps.as_mut(), env.clone(), mock_info("seller", &[]), msg);
+            assert!(matches!(
+                res.unwrap_err(),
+                ContractError::InvalidBundle { .. }
+            ));
+        }
+    }
+
+    #[test]
+    fn test_bundle_auction_opens_once_complete_and_settles_all_items() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let mut env = mock_env();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), env.clone(), info, InstantiateMsg {}).unwrap();
+
+        create_bundle(deps.as_mut());
+        send_to_bundle(deps.as_mut(), MOCK_UNCLAIMED_TOKEN).unwrap();
+        assert_eq!(
+            ContractError::TokenNotInBundle {},
+            send_to_bundle(deps.as_mut(), MOCK_UNCLAIMED_TOKEN).unwrap_err()
+        );
+        assert_eq!(
+            ContractError::TokenNotInBundle {},
+            send_to_bundle(deps.as_mut(), "token_c").unwrap_err()
+        );
+
+        let bundle: PendingBundle = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::PendingBundle {
+                    bundle_id: Uint128::new(1),
+                },
+            )
+            .unwrap(),
         )
         .unwrap();
+        assert_eq!(1, bundle.escrowed.len());
+        assert!(load_nft_auction_state(deps.as_ref().storage, 1u128).is_err());
 
-        let result = read_auction_details(
-            &deps.storage,
-            Some("specific_token_address".to_string()),
-            None,
-            None,
+        // The remaining item is pulled in through the marketplace's approval.
+        env.block.time = Timestamp::from_seconds(0);
+        let msg = ExecuteMsg::EscrowBundleItems {
+            bundle_id: Uint128::new(1),
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(MOCK_TOKEN_OWNER, &[]),
+            msg,
         )
         .unwrap();
-        assert_eq!(result.len(), 1);
-        assert_eq!(result[0].token_id, "specific_token_id");
+        assert_eq!(
+            vec![SubMsg::new(WasmMsg::Execute {
+                contract_addr: MOCK_TOKEN_ADDR.to_owned(),
+                msg: to_json_binary(&Cw721ExecuteMsg::TransferNft {
+                    recipient: env.contract.address.to_string(),
+                    token_id: "token_b".to_owned(),
+                })
+                .unwrap(),
+                funds: vec![],
+            })],
+            res.messages
+        );
+        let state = load_nft_auction_state(deps.as_ref().storage, 1u128).unwrap();
+        assert_eq!(MOCK_UNCLAIMED_TOKEN, state.token_id);
+        assert_eq!(
+            vec![BundleItem {
+                token_address: MOCK_TOKEN_ADDR.to_string(),
+                token_id: "token_b".to_string(),
+            }],
+            state.bundle
+        );
+
+        // Bidding through any token of the lot reaches the bundle auction.
+        BidBuilder::new("bidder", 100)
+            .token("token_b")
+            .submit(deps.as_mut())
+            .unwrap();
+
+        env.block.time = Timestamp::from_seconds(200000);
+        let msg = ExecuteMsg::FinalizeMany {
+            auction_ids: vec![Uint128::new(1)],
+        };
+        let res = execute(deps.as_mut(), env, mock_info("keeper", &[]), msg).unwrap();
+        let transfers: Vec<SubMsg> = [MOCK_UNCLAIMED_TOKEN, "token_b"]
+            .iter()
+            .enumerate()
+            .map(|(index, token_id)| {
+                delivery(
+                    1,
+                    index,
+                    WasmMsg::Execute {
+                        contract_addr: MOCK_TOKEN_ADDR.to_owned(),
+                        msg: to_json_binary(&Cw721ExecuteMsg::TransferNft {
+                            recipient: "bidder".to_owned(),
+                            token_id: token_id.to_string(),
+                        })
+                        .unwrap(),
+                        funds: vec![],
+                    },
+                )
+            })
+            .collect();
+        assert_eq!(transfers, res.messages[1..]);
+
+        // Each item of the lot is recorded, but the collection made a single sale.
+        let sales = sales(deps.as_ref());
+        assert_eq!(
+            vec![MOCK_UNCLAIMED_TOKEN, "token_b"],
+            sales
+                .iter()
+                .map(|sale| sale.token_id.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert!(sales
+            .iter()
+            .all(|sale| sale.price == Uint128::new(100) && sale.lot_size == 2));
+        let stats = sale_stats(deps.as_ref());
+        assert_eq!(1, stats.sale_count);
+        assert_eq!(Uint128::new(100), stats.total_volume);
+    }
+
+    fn sales(deps: Deps) -> Vec<Sale> {
+        let msg = QueryMsg::Sales {
+            token_address: MOCK_TOKEN_ADDR.to_string(),
+            start_after: None,
+            limit: None,
+            order_by: None,
+        };
+        from_json(query(deps, mock_env(), msg).unwrap()).unwrap()
+    }
+
+    fn sale_stats(deps: Deps) -> SaleStats {
+        let msg = QueryMsg::SaleStats {
+            token_address: MOCK_TOKEN_ADDR.to_string(),
+            start_after: None,
+            limit: None,
+        };
+        let stats: Vec<SaleStats> = from_json(query(deps, mock_env(), msg).unwrap()).unwrap();
+        stats[0].clone()
+    }
+
+    #[test]
+    fn test_bundle_completed_late_starts_when_it_opens() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let mut env = mock_env();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), env.clone(), info, InstantiateMsg {}).unwrap();
+        create_bundle(deps.as_mut());
+        send_to_bundle(deps.as_mut(), MOCK_UNCLAIMED_TOKEN).unwrap();
+
+        env.block.time = Timestamp::from_seconds(150000);
+        let msg = ExecuteMsg::EscrowBundleItems {
+            bundle_id: Uint128::new(1),
+        };
+        execute(deps.as_mut(), env, mock_info(MOCK_TOKEN_OWNER, &[]), msg).unwrap();
+        let state = load_nft_auction_state(deps.as_ref().storage, 1u128).unwrap();
+        assert_eq!(Timestamp::from_seconds(150000), state.start);
+        assert_eq!(Timestamp::from_seconds(250000), state.end);
+    }
+
+    #[test]
+    fn test_cancel_bundle_returns_escrowed_items() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let env = mock_env();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), env.clone(), info, InstantiateMsg {}).unwrap();
+
+        create_bundle(deps.as_mut());
+        send_to_bundle(deps.as_mut(), "token_b").unwrap();
+
+        let msg = ExecuteMsg::CancelBundle {
+            bundle_id: Uint128::new(1),
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            msg.clone(),
+        );
+        assert_eq!(ContractError::Unauthorized {}, res.unwrap_err());
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(MOCK_TOKEN_OWNER, &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            vec![SubMsg::new(WasmMsg::Execute {
+                contract_addr: MOCK_TOKEN_ADDR.to_owned(),
+                msg: to_json_binary(&Cw721ExecuteMsg::TransferNft {
+                    recipient: MOCK_TOKEN_OWNER.to_owned(),
+                    token_id: "token_b".to_owned(),
+                })
+                .unwrap(),
+                funds: vec![],
+            })],
+            res.messages
+        );
+        assert_eq!(
+            vec![Event::new("bundle_cancelled")
+                .add_attribute("bundle_id", "1")
+                .add_attribute("seller", MOCK_TOKEN_OWNER)
+                .add_attribute("returned", "1")],
+            res.events
+        );
+
+        let res = execute(deps.as_mut(), env, mock_info(MOCK_TOKEN_OWNER, &[]), msg);
+        assert_eq!(ContractError::BundleDoesNotExist {}, res.unwrap_err());
+    }
+
+    #[test]
+    fn test_private_auction_allowlist() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        let access = BidderAccess::Allowlist {
+            addresses: vec![Addr::unchecked("alice")],
+        };
+        AuctionBuilder::new()
+            .access(access.clone())
+            .start(deps.as_mut())
+            .unwrap();
+        assert_eq!(
+            Some(access),
+            load_nft_auction_state(deps.as_ref().storage, 1u128)
+                .unwrap()
+                .access
+        );
+
+        let res = BidBuilder::new("bob", 100).submit(deps.as_mut());
+        assert_eq!(ContractError::BidderNotAllowed {}, res.unwrap_err());
+        BidBuilder::new("alice", 100).submit(deps.as_mut()).unwrap();
+    }
+
+    #[test]
+    fn test_private_auction_merkle_root() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+
+        let leaves = [
+            leaf_hash("alice", None),
+            leaf_hash("bob", Some(Uint128::new(150))),
+            leaf_hash("carol", None),
+        ];
+        let alice_bob = hash_pair(&leaves[0], &leaves[1]);
+        let root = hash_pair(&alice_bob, &leaves[2]);
+
+        let res = AuctionBuilder::new()
+            .access(BidderAccess::MerkleRoot {
+                root: "not hex".to_owned(),
+            })
+            .start(deps.as_mut());
+        assert_eq!(ContractError::InvalidMerkleRoot {}, res.unwrap_err());
+        AuctionBuilder::new()
+            .access(BidderAccess::MerkleRoot {
+                root: hex::encode(root),
+            })
+            .start(deps.as_mut())
+            .unwrap();
+
+        let res = BidBuilder::new("alice", 100).submit(deps.as_mut());
+        assert_eq!(ContractError::BidderNotAllowed {}, res.unwrap_err());
+        let bad_proof = MerkleProof {
+            max_amount: None,
+            proof: vec!["zz".to_owned()],
+        };
+        let res = BidBuilder::new("alice", 100)
+            .proof(bad_proof)
+            .submit(deps.as_mut());
+        assert_eq!(ContractError::InvalidMerkleProof {}, res.unwrap_err());
+
+        let bob_proof = MerkleProof {
+            max_amount: Some(Uint128::new(150)),
+            proof: vec![hex::encode(leaves[0]), hex::encode(leaves[2])],
+        };
+        let res = BidBuilder::new("dave", 100)
+            .proof(bob_proof.clone())
+            .submit(deps.as_mut());
+        assert_eq!(ContractError::BidderNotAllowed {}, res.unwrap_err());
+        let res = BidBuilder::new("bob", 200)
+            .proof(bob_proof.clone())
+            .submit(deps.as_mut());
+        assert_eq!(ContractError::BidExceedsAllowance {}, res.unwrap_err());
+        let uncapped = MerkleProof {
+            max_amount: None,
+            ..bob_proof.clone()
+        };
+        let res = BidBuilder::new("bob", 200)
+            .proof(uncapped)
+            .submit(deps.as_mut());
+        assert_eq!(ContractError::BidderNotAllowed {}, res.unwrap_err());
+        BidBuilder::new("bob", 150)
+            .proof(bob_proof)
+            .submit(deps.as_mut())
+            .unwrap();
+
+        let carol_proof = MerkleProof {
+            max_amount: None,
+            proof: vec![hex::encode(alice_bob)],
+        };
+        BidBuilder::new("carol", 200)
+            .proof(carol_proof)
+            .submit(deps.as_mut())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_private_auction_holders_only() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        let access = BidderAccess::HoldsToken {
+            collection: MOCK_TOKEN_ADDR.to_owned(),
+        };
+        AuctionBuilder::new()
+            .access(access)
+            .start(deps.as_mut())
+            .unwrap();
+
+        let res = BidBuilder::new("bob", 100).submit(deps.as_mut());
+        assert_eq!(ContractError::BidderNotAllowed {}, res.unwrap_err());
+        BidBuilder::new(MOCK_TOKEN_HOLDER, 100)
+            .submit(deps.as_mut())
+            .unwrap();
+    }
+
+    fn query_bid_path(deps: Deps) -> Vec<(String, u128)> {
+        let msg = QueryMsg::Bids {
+            auction_id: Uint128::new(1),
+            start_after: None,
+            limit: None,
+            order_by: None,
+        };
+        let bids: Vec<Bid> = from_json(query(deps, mock_env(), msg).unwrap()).unwrap();
+        bids.into_iter()
+            .map(|bid| (bid.bidder, bid.amount.u128()))
+            .collect()
+    }
+
+    /// The settlement delivery of item `index` of auction `auction_id`.
+    fn delivery(auction_id: u128, index: usize, msg: impl Into<CosmosMsg>) -> SubMsg {
+        SubMsg::reply_on_error(msg, delivery_reply_id(auction_id.into(), index).unwrap())
+    }
+
+    fn refund_msg(to_address: &str, amount: u128) -> SubMsg {
+        SubMsg::new(BankMsg::Send {
+            to_address: to_address.to_owned(),
+            amount: coins(amount, "usd"),
+        })
+    }
+
+    #[test]
+    fn test_proxy_bidding() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        AuctionBuilder::new()
+            .min_bid(10)
+            .start(deps.as_mut())
+            .unwrap();
+
+        // Alice's proxy opens at the minimum bid.
+        let res = BidBuilder::new("alice", 500)
+            .proxy()
+            .submit(deps.as_mut())
+            .unwrap();
+        assert!(res.messages.is_empty());
+        assert_eq!(
+            vec![("alice".to_owned(), 10)],
+            query_bid_path(deps.as_ref())
+        );
+
+        // Bob's bid is covered by Alice's maximum, so he is refunded and her proxy raises.
+        let res = BidBuilder::new("bob", 100).submit(deps.as_mut()).unwrap();
+        assert_eq!(vec![refund_msg("bob", 100)], res.messages);
+        let state = load_nft_auction_state(deps.as_ref().storage, 1u128).unwrap();
+        assert_eq!(Addr::unchecked("alice"), state.high_bidder_addr);
+        assert_eq!(Uint128::new(101), state.high_bidder_amount);
+
+        // Carol's maximum beats Alice's, so she leads one increment above it.
+        let res = BidBuilder::new("carol", 800)
+            .proxy()
+            .submit(deps.as_mut())
+            .unwrap();
+        assert_eq!(vec![refund_msg("alice", 500)], res.messages);
+        assert_eq!(
+            vec![
+                ("alice".to_owned(), 10),
+                ("bob".to_owned(), 100),
+                ("alice".to_owned(), 101),
+                ("carol".to_owned(), 501),
+            ],
+            query_bid_path(deps.as_ref())
+        );
+
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(250000);
+        let msg = ExecuteMsg::FinalizeAuctionAndTranferAssets {
+            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+            token_address: MOCK_TOKEN_ADDR.to_string(),
+        };
+        let res = execute(deps.as_mut(), env, mock_info("keeper", &[]), msg).unwrap();
+        assert_eq!(refund_msg(MOCK_TOKEN_OWNER, 501), res.messages[0]);
+        assert_eq!(refund_msg("carol", 299), res.messages[1]);
+        assert_eq!(3, res.messages.len());
+    }
+
+    #[test]
+    fn test_proxy_bid_tie_goes_to_the_earlier_bid() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
+
+        BidBuilder::new("alice", 300)
+            .proxy()
+            .submit(deps.as_mut())
+            .unwrap();
+        let res = BidBuilder::new("bob", 300)
+            .proxy()
+            .submit(deps.as_mut())
+            .unwrap();
+        assert_eq!(vec![refund_msg("bob", 300)], res.messages);
+        let state = load_nft_auction_state(deps.as_ref().storage, 1u128).unwrap();
+        assert_eq!(Addr::unchecked("alice"), state.high_bidder_addr);
+        assert_eq!(Uint128::new(300), state.high_bidder_amount);
+    }
+
+    #[test]
+    fn test_proxy_bid_meets_reserve_price() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
+        let msg = ExecuteMsg::UpdateAuction {
+            auction_id: Uint128::new(1),
+            min_bid: None,
+            end: None,
+            reserve_price: Some(Uint128::new(200)),
+            coin_denomination: None,
+        };
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(0);
+        let info = mock_info(MOCK_TOKEN_OWNER, &[]);
+        execute(deps.as_mut(), env, info, msg).unwrap();
+
+        BidBuilder::new("alice", 150)
+            .proxy()
+            .submit(deps.as_mut())
+            .unwrap();
+        let state = load_nft_auction_state(deps.as_ref().storage, 1u128).unwrap();
+        assert_eq!(Uint128::new(1), state.high_bidder_amount);
+        BidBuilder::new("bob", 400)
+            .proxy()
+            .submit(deps.as_mut())
+            .unwrap();
+        let state = load_nft_auction_state(deps.as_ref().storage, 1u128).unwrap();
+        assert_eq!(Uint128::new(200), state.high_bidder_amount);
+    }
+
+    #[test]
+    fn test_bid_increment_and_proxy_funds() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
+
+        let msg = ExecuteMsg::UpdateConfig {
+            owner: None,
+            keeper_reward_bps: None,
+            keeper_fee: None,
+            cancel_policy: None,
+            listing_deposit: None,
+            bid_increment: Some(Uint128::zero()),
+            randomness_beacon: None,
+            ics721: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg);
+        assert_eq!(ContractError::InvalidBidIncrement {}, res.unwrap_err());
+        let msg = ExecuteMsg::UpdateConfig {
+            owner: None,
+            keeper_reward_bps: None,
+            keeper_fee: None,
+            cancel_policy: None,
+            listing_deposit: None,
+            bid_increment: Some(Uint128::new(10)),
+            randomness_beacon: None,
+            ics721: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(150000);
+        let msg = ExecuteMsg::SubmitBid {
+            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+            token_address: MOCK_TOKEN_ADDR.to_string(),
+            proof: None,
+            max_amount: Some(Uint128::new(200)),
+            ibc_origin: None,
+        };
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info("alice", &coins(100, "usd")),
+            msg,
+        );
+        assert_eq!(
+            ContractError::InvalidFunds {
+                msg: "Proxy bids must escrow exactly their max_amount".to_string(),
+            },
+            res.unwrap_err()
+        );
+
+        BidBuilder::new("alice", 100).submit(deps.as_mut()).unwrap();
+        let res = BidBuilder::new("bob", 109).submit(deps.as_mut());
+        assert_eq!(ContractError::BidSmallerThanHighestBid {}, res.unwrap_err());
+        BidBuilder::new("bob", 110).submit(deps.as_mut()).unwrap();
+    }
+
+    fn bid_event(bidder: &str, high_bidder: &str, high_bid: &str) -> Event {
+        Event::new("auction_bid")
+            .add_attribute("auction_id", "1")
+            .add_attribute("bidder", bidder)
+            .add_attribute("high_bidder", high_bidder)
+            .add_attribute("high_bid", high_bid)
+            .add_attribute("denom", "usd")
+    }
+
+    fn outbid_event(bidder: &str, high_bidder: &str) -> Event {
+        Event::new("auction_outbid")
+            .add_attribute("auction_id", "1")
+            .add_attribute("bidder", bidder)
+            .add_attribute("high_bidder", high_bidder)
+    }
+
+    fn refunded_event(recipient: &str, amount: &str, reason: &str) -> Event {
+        Event::new("auction_refunded")
+            .add_attribute("auction_id", "1")
+            .add_attribute("recipient", recipient)
+            .add_attribute("amount", amount)
+            .add_attribute("denom", "usd")
+            .add_attribute("reason", reason)
+    }
+
+    #[test]
+    fn test_bid_events() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
+
+        let res = BidBuilder::new("alice", 100).submit(deps.as_mut()).unwrap();
+        assert_eq!(vec![attr("action", "bid")], res.attributes);
+        assert_eq!(vec![bid_event("alice", "alice", "100")], res.events);
+
+        let res = BidBuilder::new("bob", 150).submit(deps.as_mut()).unwrap();
+        assert_eq!(
+            vec![
+                bid_event("bob", "bob", "150"),
+                outbid_event("alice", "bob"),
+                refunded_event("alice", "100", "outbid"),
+            ],
+            res.events
+        );
+
+        // A bid covered by a proxy maximum is outbid straight away.
+        let res = BidBuilder::new("carol", 400)
+            .proxy()
+            .submit(deps.as_mut())
+            .unwrap();
+        assert_eq!(
+            vec![
+                bid_event("carol", "carol", "151"),
+                outbid_event("bob", "carol"),
+                refunded_event("bob", "150", "outbid"),
+            ],
+            res.events
+        );
+        let res = BidBuilder::new("dave", 200).submit(deps.as_mut()).unwrap();
+        assert_eq!(
+            vec![
+                bid_event("dave", "carol", "201"),
+                Event::new("auction_proxy_outbid")
+                    .add_attribute("auction_id", "1")
+                    .add_attribute("bidder", "dave")
+                    .add_attribute("high_bidder", "carol"),
+                refunded_event("dave", "200", "proxy_outbid"),
+            ],
+            res.events
+        );
+    }
+
+    #[test]
+    fn test_bid_increment_overflow_is_an_error() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
+
+        BidBuilder::new("alice", u128::MAX)
+            .submit(deps.as_mut())
+            .unwrap();
+        let res = BidBuilder::new("bob", u128::MAX).submit(deps.as_mut());
+        assert_eq!(ContractError::Overflow {}, res.unwrap_err());
+    }
+
+    #[test]
+    fn test_cancel_with_bids_events() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
+        set_cancel_policy(
+            deps.as_mut(),
+            CancelPolicy::WithPenalty { penalty_bps: 1000 },
+        );
+        let info = mock_info(MOCK_TOKEN_OWNER, &coins(10, "usd"));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::DepositBalance {},
+        )
+        .unwrap();
+
+        BidBuilder::new("alice", 300)
+            .proxy()
+            .submit(deps.as_mut())
+            .unwrap();
+        BidBuilder::new("bob", 99).submit(deps.as_mut()).unwrap();
+        let res = cancel(deps.as_mut()).unwrap();
+        assert_eq!(vec![attr("action", "cancel_auction")], res.attributes);
+        assert_eq!(
+            vec![
+                Event::new("auction_cancelled")
+                    .add_attribute("auction_id", "1")
+                    .add_attribute("seller", MOCK_TOKEN_OWNER)
+                    .add_attribute("had_bids", "true")
+                    .add_attribute("penalty", "10"),
+                refunded_event("alice", "310", "cancelled"),
+            ],
+            res.events
+        );
+    }
+
+    #[test]
+    fn test_settle_with_proxy_excess_events() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        set_keeper_config(deps.as_mut(), Some(500), None);
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
+
+        BidBuilder::new("alice", 300)
+            .proxy()
+            .submit(deps.as_mut())
+            .unwrap();
+        BidBuilder::new("bob", 199).submit(deps.as_mut()).unwrap();
+
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(250000);
+        let msg = ExecuteMsg::FinalizeAuctionAndTranferAssets {
+            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+            token_address: MOCK_TOKEN_ADDR.to_string(),
+        };
+        let res = execute(deps.as_mut(), env, mock_info("keeper", &[]), msg).unwrap();
+        assert_eq!(vec![attr("action", "claim")], res.attributes);
+        assert_eq!(
+            vec![
+                Event::new("auction_settled")
+                    .add_attribute("auction_id", "1")
+                    .add_attribute("seller", MOCK_TOKEN_OWNER)
+                    .add_attribute("recipient", "alice")
+                    .add_attribute("winning_bid", "200")
+                    .add_attribute("denom", "usd")
+                    .add_attribute("reserve_met", "true")
+                    .add_attribute("keeper", "keeper")
+                    .add_attribute("keeper_reward", "10usd"),
+                refunded_event("alice", "100", "proxy_excess"),
+            ],
+            res.events
+        );
+    }
+
+    fn set_exchange_rates(
+        deps: DepsMut,
+        sender: &str,
+        rates: &[(&str, u64)],
+    ) -> Result<Response, ContractError> {
+        let rates = rates
+            .iter()
+            .map(|(denom, percent)| ExchangeRate {
+                denom: denom.to_string(),
+                rate: Decimal::percent(*percent),
+            })
+            .collect();
+        let msg = ExecuteMsg::SetExchangeRates { rates };
+        execute(deps, mock_env(), mock_info(sender, &[]), msg)
+    }
+
+    fn finalize(deps: DepsMut) -> Response {
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(250000);
+        let msg = ExecuteMsg::FinalizeAuctionAndTranferAssets {
+            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+            token_address: MOCK_TOKEN_ADDR.to_string(),
+        };
+        execute(deps, env, mock_info("keeper", &[]), msg).unwrap()
+    }
+
+    #[test]
+    fn test_set_exchange_rates() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+
+        let res = set_exchange_rates(deps.as_mut(), "anyone", &[("usd", 100)]);
+        assert_eq!(ContractError::Unauthorized {}, res.unwrap_err());
+
+        set_exchange_rates(deps.as_mut(), "owner", &[("usd", 100), ("atom", 1000)]).unwrap();
+        set_exchange_rates(deps.as_mut(), "owner", &[("eur", 110), ("usd", 0)]).unwrap();
+        let rates: Vec<ExchangeRate> =
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::ExchangeRates {}).unwrap())
+                .unwrap();
+        assert_eq!(
+            vec![
+                ExchangeRate {
+                    denom: "atom".to_string(),
+                    rate: Decimal::percent(1000),
+                },
+                ExchangeRate {
+                    denom: "eur".to_string(),
+                    rate: Decimal::percent(110),
+                },
+            ],
+            rates
+        );
+    }
+
+    #[test]
+    fn test_multi_denom_auction_requires_exchange_rates() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        set_exchange_rates(deps.as_mut(), "owner", &[("atom", 1000)]).unwrap();
+
+        let res = AuctionBuilder::new()
+            .accepted_denoms(&["atom"])
+            .start(deps.as_mut());
+        assert_eq!(
+            ContractError::MissingExchangeRate {
+                denom: "usd".to_string()
+            },
+            res.unwrap_err()
+        );
+        let res = AuctionBuilder::new()
+            .accepted_denoms(&["atom", "atom"])
+            .start(deps.as_mut());
+        assert_eq!(
+            ContractError::InvalidAcceptedDenoms {
+                msg: "Duplicate denom atom".to_string()
+            },
+            res.unwrap_err()
+        );
+
+        set_exchange_rates(deps.as_mut(), "owner", &[("usd", 100)]).unwrap();
+        let res = AuctionBuilder::new()
+            .accepted_denoms(&["atom"])
+            .start(deps.as_mut())
+            .unwrap();
+        assert_eq!(
+            Some("atom"),
+            res.events[0]
+                .attributes
+                .iter()
+                .find(|attr| attr.key == "accepted_denoms")
+                .map(|attr| attr.value.as_str())
+        );
+    }
+
+    #[test]
+    fn test_exchange_rates_of_open_auctions_cannot_be_removed() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        set_exchange_rates(
+            deps.as_mut(),
+            "owner",
+            &[("usd", 100), ("atom", 1000), ("eur", 110)],
+        )
+        .unwrap();
+        AuctionBuilder::new()
+            .accepted_denoms(&["atom"])
+            .start(deps.as_mut())
+            .unwrap();
+
+        for denom in ["atom", "usd"] {
+            let res = set_exchange_rates(deps.as_mut(), "owner", &[(denom, 0)]);
+            assert_eq!(
+                ContractError::ExchangeRateInUse {
+                    denom: denom.to_string()
+                },
+                res.unwrap_err()
+            );
+        }
+        set_exchange_rates(deps.as_mut(), "owner", &[("eur", 0), ("atom", 900)]).unwrap();
+
+        let msg = ExecuteMsg::CancelAuctionAndRefund {
+            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+            token_address: MOCK_TOKEN_ADDR.to_string(),
+        };
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(150000);
+        execute(deps.as_mut(), env, mock_info(MOCK_TOKEN_OWNER, &[]), msg).unwrap();
+        set_exchange_rates(deps.as_mut(), "owner", &[("atom", 0), ("usd", 0)]).unwrap();
+        let rates: Vec<ExchangeRate> =
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::ExchangeRates {}).unwrap())
+                .unwrap();
+        assert!(rates.is_empty());
+    }
+
+    #[test]
+    fn test_multi_denom_bids_are_ranked_by_value() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        set_exchange_rates(deps.as_mut(), "owner", &[("usd", 100), ("atom", 1000)]).unwrap();
+        AuctionBuilder::new()
+            .accepted_denoms(&["atom"])
+            .start(deps.as_mut())
+            .unwrap();
+
+        let res = BidBuilder::new("bob", 100)
+            .denom("eur")
+            .submit(deps.as_mut());
+        assert_eq!(
+            ContractError::InvalidFunds {
+                msg: "No usd or atom assets are provided for the auction".to_string()
+            },
+            res.unwrap_err()
+        );
+        let res = BidBuilder::new("bob", 100).proxy().submit(deps.as_mut());
+        assert_eq!(ContractError::ProxyBidNotSupported {}, res.unwrap_err());
+
+        BidBuilder::new("alice", 100).submit(deps.as_mut()).unwrap();
+        // 10atom are worth exactly the 100usd high bid.
+        let res = BidBuilder::new("bob", 10)
+            .denom("atom")
+            .submit(deps.as_mut());
+        assert_eq!(ContractError::BidSmallerThanHighestBid {}, res.unwrap_err());
+
+        let res = BidBuilder::new("bob", 11)
+            .denom("atom")
+            .submit(deps.as_mut())
+            .unwrap();
+        assert_eq!(vec![refund_msg("alice", 100)], res.messages);
+        assert_eq!(
+            Event::new("auction_bid")
+                .add_attribute("auction_id", "1")
+                .add_attribute("bidder", "bob")
+                .add_attribute("high_bidder", "bob")
+                .add_attribute("high_bid", "11")
+                .add_attribute("denom", "atom"),
+            res.events[0]
+        );
+
+        let res = BidBuilder::new("carol", 110).submit(deps.as_mut());
+        assert_eq!(ContractError::BidSmallerThanHighestBid {}, res.unwrap_err());
+        let res = BidBuilder::new("carol", 111).submit(deps.as_mut()).unwrap();
+        assert_eq!(
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "bob".to_string(),
+                amount: coins(11, "atom"),
+            })],
+            res.messages
+        );
+        let state = load_nft_auction_state(deps.as_ref().storage, 1u128).unwrap();
+        assert_eq!(coin(111, "usd"), state.high_bid());
+    }
+
+    #[test]
+    fn test_multi_denom_auction_settles_in_winning_denom() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        set_exchange_rates(deps.as_mut(), "owner", &[("usd", 100), ("atom", 1000)]).unwrap();
+        set_keeper_config(deps.as_mut(), Some(500), None);
+        AuctionBuilder::new()
+            .accepted_denoms(&["atom"])
+            .reserve_price(100)
+            .start(deps.as_mut())
+            .unwrap();
+        BidBuilder::new("alice", 40)
+            .denom("atom")
+            .submit(deps.as_mut())
+            .unwrap();
+
+        let res = finalize(deps.as_mut());
+        assert_eq!(
+            vec![
+                SubMsg::new(BankMsg::Send {
+                    to_address: MOCK_TOKEN_OWNER.to_string(),
+                    amount: coins(38, "atom"),
+                }),
+                delivery(
+                    1,
+                    0,
+                    WasmMsg::Execute {
+                        contract_addr: MOCK_TOKEN_ADDR.to_owned(),
+                        msg: to_json_binary(&Cw721ExecuteMsg::TransferNft {
+                            recipient: "alice".to_owned(),
+                            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+                        })
+                        .unwrap(),
+                        funds: vec![],
+                    }
+                ),
+                SubMsg::new(BankMsg::Send {
+                    to_address: "keeper".to_string(),
+                    amount: coins(2, "atom"),
+                }),
+            ],
+            res.messages
+        );
+    }
+
+    #[test]
+    fn test_multi_denom_reserve_price_is_compared_by_value() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        set_exchange_rates(deps.as_mut(), "owner", &[("usd", 100), ("atom", 1000)]).unwrap();
+        AuctionBuilder::new()
+            .accepted_denoms(&["atom"])
+            .reserve_price(200)
+            .start(deps.as_mut())
+            .unwrap();
+        BidBuilder::new("alice", 19)
+            .denom("atom")
+            .submit(deps.as_mut())
+            .unwrap();
+
+        let res = finalize(deps.as_mut());
+        assert_eq!(
+            SubMsg::new(BankMsg::Send {
+                to_address: "alice".to_string(),
+                amount: coins(19, "atom"),
+            }),
+            res.messages[0]
+        );
+        assert_eq!(
+            Event::new("auction_refunded")
+                .add_attribute("auction_id", "1")
+                .add_attribute("recipient", "alice")
+                .add_attribute("amount", "19")
+                .add_attribute("denom", "atom")
+                .add_attribute("reason", "reserve_not_met"),
+            res.events[1]
+        );
+    }
+
+    fn list_for_rent(deps: DepsMut) {
+        let custom_msg = Cw721CustomMsg::ListForRent {
+            denom: "usd".to_string(),
+            price_per_day: Uint128::new(10),
+            collateral: Uint128::new(500),
+            max_days: 7,
+        };
+        let msg = ExecuteMsg::AuctionStart(Cw721ReceiveMsg {
+            sender: MOCK_TOKEN_OWNER.to_owned(),
+            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+            msg: to_json_binary(&custom_msg).unwrap(),
+        });
+        execute(deps, mock_env(), mock_info(MOCK_TOKEN_ADDR, &[]), msg).unwrap();
+    }
+
+    fn rent_msg(days: u64) -> ExecuteMsg {
+        ExecuteMsg::Rent {
+            token_address: MOCK_TOKEN_ADDR.to_string(),
+            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+            days,
+        }
+    }
+
+    fn return_rental_msg(renter: &str) -> ExecuteMsg {
+        let custom_msg = Cw721CustomMsg::ReturnRental {};
+        ExecuteMsg::AuctionStart(Cw721ReceiveMsg {
+            sender: renter.to_owned(),
+            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+            msg: to_json_binary(&custom_msg).unwrap(),
+        })
+    }
+
+    #[test]
+    fn test_rent_requires_fee_and_collateral() {
+        let mut deps = mock_dependencies();
+        list_for_rent(deps.as_mut());
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("renter", &coins(530, "usd")),
+            rent_msg(8),
+        );
+        assert_eq!(
+            ContractError::InvalidRental {
+                msg: "Rentals last between 1 and 7 days".to_string()
+            },
+            res.unwrap_err()
+        );
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("renter", &coins(500, "usd")),
+            rent_msg(3),
+        );
+        assert_eq!(
+            ContractError::InvalidFunds {
+                msg: "Renting requires exactly 530usd".to_string()
+            },
+            res.unwrap_err()
+        );
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("renter", &coins(530, "usd")),
+            rent_msg(3),
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                SubMsg::new(WasmMsg::Execute {
+                    contract_addr: MOCK_TOKEN_ADDR.to_owned(),
+                    msg: to_json_binary(&Cw721ExecuteMsg::TransferNft {
+                        recipient: "renter".to_owned(),
+                        token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: MOCK_TOKEN_OWNER.to_string(),
+                    amount: coins(30, "usd"),
+                }),
+            ],
+            res.messages
+        );
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("other", &coins(530, "usd")),
+            rent_msg(3),
+        );
+        assert_eq!(ContractError::AlreadyRented {}, res.unwrap_err());
+    }
+
+    #[test]
+    fn test_rental_must_be_returned_before_expiry() {
+        let mut deps = mock_dependencies();
+        list_for_rent(deps.as_mut());
+        let info = mock_info("renter", &coins(520, "usd"));
+        execute(deps.as_mut(), mock_env(), info, rent_msg(2)).unwrap();
+        let claim = ExecuteMsg::ClaimCollateral {
+            token_address: MOCK_TOKEN_ADDR.to_string(),
+            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+        };
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(2 * 86_400 - 1);
+        let info = mock_info(MOCK_TOKEN_OWNER, &[]);
+        let res = execute(deps.as_mut(), env.clone(), info, claim.clone());
+        assert_eq!(ContractError::RentalNotExpired {}, res.unwrap_err());
+        env.block.time = env.block.time.plus_seconds(1);
+        let info = mock_info(MOCK_TOKEN_ADDR, &[]);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            return_rental_msg("renter"),
+        );
+        assert_eq!(ContractError::RentalExpired {}, res.unwrap_err());
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("renter", &[]),
+            claim.clone(),
+        );
+        assert_eq!(ContractError::Unauthorized {}, res.unwrap_err());
+
+        let info = mock_info(MOCK_TOKEN_OWNER, &[]);
+        let res = execute(deps.as_mut(), env, info, claim).unwrap();
+        assert_eq!(
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: MOCK_TOKEN_OWNER.to_string(),
+                amount: coins(500, "usd"),
+            })],
+            res.messages
+        );
+        let msg = QueryMsg::Rental {
+            token_address: MOCK_TOKEN_ADDR.to_string(),
+            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg);
+        assert_eq!(ContractError::RentalDoesNotExist {}, res.unwrap_err());
+    }
+
+    #[test]
+    fn test_rental_requires_collateral_and_can_be_returned_by_the_holder() {
+        let mut deps = mock_dependencies();
+        let custom_msg = Cw721CustomMsg::ListForRent {
+            denom: "usd".to_string(),
+            price_per_day: Uint128::new(10),
+            collateral: Uint128::zero(),
+            max_days: 7,
+        };
+        let msg = ExecuteMsg::AuctionStart(Cw721ReceiveMsg {
+            sender: MOCK_TOKEN_OWNER.to_owned(),
+            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+            msg: to_json_binary(&custom_msg).unwrap(),
+        });
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_TOKEN_ADDR, &[]),
+            msg,
+        );
+        assert_eq!(
+            ContractError::InvalidRental {
+                msg: "Rentals require collateral".to_string()
+            },
+            res.unwrap_err()
+        );
+
+        list_for_rent(deps.as_mut());
+        let info = mock_info("renter", &coins(510, "usd"));
+        execute(deps.as_mut(), mock_env(), info, rent_msg(1)).unwrap();
+        // The renter passed the NFT on; its new holder sends it back.
+        let info = mock_info(MOCK_TOKEN_ADDR, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, return_rental_msg("holder")).unwrap();
+        assert_eq!(
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "renter".to_string(),
+                amount: coins(500, "usd"),
+            })],
+            res.messages
+        );
+        assert_eq!(
+            vec![Event::new("rental_returned")
+                .add_attribute("token_address", MOCK_TOKEN_ADDR)
+                .add_attribute("token_id", MOCK_UNCLAIMED_TOKEN)
+                .add_attribute("renter", "renter")
+                .add_attribute("returned_by", "holder")],
+            res.events
+        );
+    }
+
+    #[test]
+    fn test_returned_rental_can_be_delisted() {
+        let mut deps = mock_dependencies();
+        list_for_rent(deps.as_mut());
+        let info = mock_info("renter", &coins(510, "usd"));
+        execute(deps.as_mut(), mock_env(), info, rent_msg(1)).unwrap();
+        let delist = ExecuteMsg::DelistRental {
+            token_address: MOCK_TOKEN_ADDR.to_string(),
+            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+        };
+        let info = mock_info(MOCK_TOKEN_OWNER, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, delist.clone());
+        assert_eq!(ContractError::AlreadyRented {}, res.unwrap_err());
+
+        let info = mock_info(MOCK_TOKEN_ADDR, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, return_rental_msg("renter")).unwrap();
+        assert_eq!(
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "renter".to_string(),
+                amount: coins(500, "usd"),
+            })],
+            res.messages
+        );
+        let msg = QueryMsg::Rentals {
+            start_after: None,
+            limit: None,
+        };
+        let rentals: Vec<Rental> =
+            from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(1, rentals.len());
+        assert!(!rentals[0].is_rented());
+
+        let info = mock_info(MOCK_TOKEN_OWNER, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, delist).unwrap();
+        assert_eq!(1, res.messages.len());
+        let msg = QueryMsg::Rentals {
+            start_after: None,
+            limit: None,
+        };
+        let rentals: Vec<Rental> =
+            from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert!(rentals.is_empty());
+    }
+
+    const RAFFLE_SECRET: &str = "73656372657420736565642063686f73656e2062792074686520736565646572";
+
+    fn raffle_commitment() -> String {
+        hex::encode(Sha256::digest(hex::decode(RAFFLE_SECRET).unwrap()))
+    }
+
+    fn start_raffle(
+        deps: DepsMut,
+        randomness_source: RandomnessSource,
+    ) -> Result<Response, ContractError> {
+        let custom_msg = Cw721CustomMsg::StartRaffle {
+            start_time: mock_env().block.time.seconds() + 1,
+            duration: 1000,
+            denom: "usd".to_string(),
+            ticket_price: Uint128::new(10),
+            max_tickets: 5,
+            max_tickets_per_address: Some(3),
+            randomness_source,
+        };
+        let msg = ExecuteMsg::AuctionStart(Cw721ReceiveMsg {
+            sender: MOCK_TOKEN_OWNER.to_owned(),
+            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+            msg: to_json_binary(&custom_msg).unwrap(),
+        });
+        execute(deps, mock_env(), mock_info(MOCK_TOKEN_ADDR, &[]), msg)
+    }
+
+    fn raffle_env(seconds_after_start: u64) -> Env {
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(1 + seconds_after_start);
+        env
+    }
+
+    fn buy_tickets(deps: DepsMut, buyer: &str, count: u32) -> Result<Response, ContractError> {
+        let msg = ExecuteMsg::BuyTickets {
+            raffle_id: Uint128::one(),
+            count,
+        };
+        let info = mock_info(buyer, &coins(10 * u128::from(count), "usd"));
+        execute(deps, raffle_env(0), info, msg)
+    }
+
+    fn draw_raffle_msg(randomness: Option<&str>) -> ExecuteMsg {
+        ExecuteMsg::DrawRaffle {
+            raffle_id: Uint128::one(),
+            randomness: randomness.map(str::to_owned),
+        }
+    }
+
+    fn raffle_nft_msg(recipient: &str) -> SubMsg {
+        SubMsg::new(WasmMsg::Execute {
+            contract_addr: MOCK_TOKEN_ADDR.to_owned(),
+            msg: to_json_binary(&Cw721ExecuteMsg::TransferNft {
+                recipient: recipient.to_owned(),
+                token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+            })
+            .unwrap(),
+            funds: vec![],
+        })
+    }
+
+    #[test]
+    fn test_buy_tickets_enforces_caps_and_price() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("owner", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        let commitment = RandomnessSource::CommitReveal {
+            commitment: raffle_commitment(),
+        };
+        start_raffle(deps.as_mut(), commitment).unwrap();
+
+        let msg = ExecuteMsg::BuyTickets {
+            raffle_id: Uint128::one(),
+            count: 1,
+        };
+        let info = mock_info("alice", &coins(10, "usd"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        assert_eq!(ContractError::RaffleNotOpen {}, res.unwrap_err());
+        let res = buy_tickets(deps.as_mut(), MOCK_TOKEN_OWNER, 1);
+        assert_eq!(
+            ContractError::InvalidRaffle {
+                msg: "Owners cannot buy tickets for their own raffle".to_string()
+            },
+            res.unwrap_err()
+        );
+        let msg = ExecuteMsg::BuyTickets {
+            raffle_id: Uint128::one(),
+            count: 2,
+        };
+        let info = mock_info("alice", &coins(10, "usd"));
+        let res = execute(deps.as_mut(), raffle_env(0), info, msg);
+        assert_eq!(
+            ContractError::InvalidFunds {
+                msg: "2 tickets cost exactly 20usd".to_string()
+            },
+            res.unwrap_err()
+        );
+
+        buy_tickets(deps.as_mut(), "alice", 2).unwrap();
+        let res = buy_tickets(deps.as_mut(), "alice", 2);
+        assert_eq!(ContractError::TicketCapExceeded {}, res.unwrap_err());
+        buy_tickets(deps.as_mut(), "alice", 1).unwrap();
+        let res = buy_tickets(deps.as_mut(), "bob", 3);
+        assert_eq!(ContractError::TicketCapExceeded {}, res.unwrap_err());
+        buy_tickets(deps.as_mut(), "bob", 2).unwrap();
+
+        let msg = QueryMsg::RaffleTickets {
+            raffle_id: Uint128::one(),
+            start_after: None,
+            limit: None,
+        };
+        let holdings: Vec<TicketHolding> =
+            from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(
+            vec![
+                TicketHolding {
+                    holder: Addr::unchecked("alice"),
+                    tickets: 3,
+                },
+                TicketHolding {
+                    holder: Addr::unchecked("bob"),
+                    tickets: 2,
+                },
+            ],
+            holdings
+        );
+        let msg = QueryMsg::Raffle {
+            raffle_id: Uint128::one(),
+        };
+        let raffle: Raffle = from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(5, raffle.tickets_sold);
+    }
+
+    #[test]
+    fn test_commit_reveal_raffle_draw() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("owner", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        let res = start_raffle(
+            deps.as_mut(),
+            RandomnessSource::CommitReveal {
+                commitment: "1234".to_string(),
+            },
+        );
+        assert_eq!(
+            ContractError::InvalidRaffle {
+                msg: "Commitment must be a hex-encoded sha256 hash".to_string()
+            },
+            res.unwrap_err()
+        );
+        let commitment = RandomnessSource::CommitReveal {
+            commitment: raffle_commitment(),
+        };
+        start_raffle(deps.as_mut(), commitment).unwrap();
+        buy_tickets(deps.as_mut(), "alice", 3).unwrap();
+        buy_tickets(deps.as_mut(), "bob", 2).unwrap();
+
+        let info = mock_info(MOCK_TOKEN_OWNER, &[]);
+        let msg = draw_raffle_msg(Some(RAFFLE_SECRET));
+        let res = execute(deps.as_mut(), raffle_env(999), info.clone(), msg.clone());
+        assert_eq!(ContractError::RaffleNotEnded {}, res.unwrap_err());
+        let res = execute(
+            deps.as_mut(),
+            raffle_env(1000),
+            info.clone(),
+            draw_raffle_msg(Some("00")),
+        );
+        assert_eq!(ContractError::InvalidRandomness {}, res.unwrap_err());
+
+        let res = execute(deps.as_mut(), raffle_env(1000), info.clone(), msg.clone()).unwrap();
+        let raffle_query = QueryMsg::Raffle {
+            raffle_id: Uint128::one(),
+        };
+        let raffle: Raffle =
+            from_json(query(deps.as_ref(), mock_env(), raffle_query).unwrap()).unwrap();
+        let draw = raffle.draw.unwrap();
+        assert_eq!(RAFFLE_SECRET, draw.randomness);
+        assert!(draw.winning_ticket.unwrap() < 5);
+        let expected_winner = if draw.winning_ticket.unwrap() < 3 {
+            "alice"
+        } else {
+            "bob"
+        };
+        assert_eq!(Addr::unchecked(expected_winner), draw.winner);
+        assert_eq!(
+            vec![
+                SubMsg::new(BankMsg::Send {
+                    to_address: MOCK_TOKEN_OWNER.to_string(),
+                    amount: coins(50, "usd"),
+                }),
+                raffle_nft_msg(expected_winner),
+            ],
+            res.messages
+        );
+        let sales = sales(deps.as_ref());
+        assert_eq!(1, sales.len());
+        assert_eq!(Uint128::new(50), sales[0].price);
+        assert_eq!(Addr::unchecked(expected_winner), sales[0].buyer);
+        let res = execute(deps.as_mut(), raffle_env(1000), info, msg);
+        assert_eq!(ContractError::RaffleClosed {}, res.unwrap_err());
+    }
+
+    #[test]
+    fn test_beacon_raffle_only_accepts_the_beacon() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("owner", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        let res = start_raffle(deps.as_mut(), RandomnessSource::Beacon {});
+        assert_eq!(
+            ContractError::InvalidRaffle {
+                msg: "No randomness beacon is configured".to_string()
+            },
+            res.unwrap_err()
+        );
+        let msg = ExecuteMsg::UpdateConfig {
+            owner: None,
+            keeper_reward_bps: None,
+            keeper_fee: None,
+            cancel_policy: None,
+            listing_deposit: None,
+            bid_increment: None,
+            randomness_beacon: Some("beacon".to_string()),
+            ics721: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        start_raffle(deps.as_mut(), RandomnessSource::Beacon {}).unwrap();
+        buy_tickets(deps.as_mut(), "alice", 1).unwrap();
+
+        let randomness = "ab".repeat(32);
+        let info = mock_info(MOCK_TOKEN_OWNER, &[]);
+        let res = execute(
+            deps.as_mut(),
+            raffle_env(1000),
+            info,
+            draw_raffle_msg(Some(&randomness)),
+        );
+        assert_eq!(ContractError::Unauthorized {}, res.unwrap_err());
+        let info = mock_info("beacon", &[]);
+        let res = execute(
+            deps.as_mut(),
+            raffle_env(1000),
+            info.clone(),
+            draw_raffle_msg(Some("abab")),
+        );
+        assert_eq!(ContractError::InvalidRandomness {}, res.unwrap_err());
+        let res = execute(
+            deps.as_mut(),
+            raffle_env(1000),
+            info,
+            draw_raffle_msg(Some(&randomness)),
+        )
+        .unwrap();
+        assert_eq!(raffle_nft_msg("alice"), res.messages[1]);
+    }
+
+    #[test]
+    fn test_undrawn_raffle_is_refunded() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("owner", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        let commitment = RandomnessSource::CommitReveal {
+            commitment: raffle_commitment(),
+        };
+        start_raffle(deps.as_mut(), commitment).unwrap();
+        buy_tickets(deps.as_mut(), "alice", 3).unwrap();
+        buy_tickets(deps.as_mut(), "bob", 1).unwrap();
+
+        let msg = ExecuteMsg::RefundRaffle {
+            raffle_id: Uint128::one(),
+        };
+        let info = mock_info("bob", &[]);
+        let env = raffle_env(1000 + DRAW_WINDOW_SECONDS - 1);
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+        assert_eq!(ContractError::DrawWindowOpen {}, res.unwrap_err());
+
+        let claim = ExecuteMsg::ClaimRaffleRefund {
+            raffle_id: Uint128::one(),
+        };
+        let res = execute(deps.as_mut(), env, info.clone(), claim.clone());
+        assert_eq!(ContractError::RaffleNotRefunded {}, res.unwrap_err());
+
+        let env = raffle_env(1000 + DRAW_WINDOW_SECONDS);
+        // A seller who held back their secret can no longer reveal it once refunds open.
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(MOCK_TOKEN_OWNER, &[]),
+            draw_raffle_msg(Some(RAFFLE_SECRET)),
+        );
+        assert_eq!(ContractError::DrawWindowClosed {}, res.unwrap_err());
+        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert_eq!(vec![raffle_nft_msg(MOCK_TOKEN_OWNER)], res.messages);
+        assert_eq!(
+            vec![Event::new("raffle_refunded")
+                .add_attribute("raffle_id", "1")
+                .add_attribute("seller", MOCK_TOKEN_OWNER)],
+            res.events
+        );
+        let info = mock_info(MOCK_TOKEN_OWNER, &[]);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            draw_raffle_msg(Some(RAFFLE_SECRET)),
+        );
+        assert_eq!(ContractError::RaffleClosed {}, res.unwrap_err());
+
+        for (holder, refund) in [("alice", 30), ("bob", 10)] {
+            let info = mock_info(holder, &[]);
+            let res = execute(deps.as_mut(), env.clone(), info.clone(), claim.clone()).unwrap();
+            assert_eq!(
+                vec![SubMsg::new(BankMsg::Send {
+                    to_address: holder.to_string(),
+                    amount: coins(refund, "usd"),
+                })],
+                res.messages
+            );
+            assert_eq!(
+                vec![Event::new("raffle_refund_claimed")
+                    .add_attribute("raffle_id", "1")
+                    .add_attribute("holder", holder)
+                    .add_attribute("tickets", (refund / 10).to_string())
+                    .add_attribute("amount", refund.to_string())
+                    .add_attribute("denom", "usd")],
+                res.events
+            );
+            let res = execute(deps.as_mut(), env.clone(), info, claim.clone());
+            assert_eq!(ContractError::NoTicketsToRefund {}, res.unwrap_err());
+        }
+    }
+
+    #[test]
+    fn test_raffle_caps_its_ticket_holders() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("owner", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        let commitment = RandomnessSource::CommitReveal {
+            commitment: raffle_commitment(),
+        };
+        start_raffle(deps.as_mut(), commitment).unwrap();
+        RAFFLES
+            .update(deps.as_mut().storage, 1, |raffle| -> StdResult<_> {
+                let mut raffle = raffle.unwrap();
+                raffle.ticket_holders = MAX_TICKET_HOLDERS - 1;
+                Ok(raffle)
+            })
+            .unwrap();
+
+        buy_tickets(deps.as_mut(), "alice", 1).unwrap();
+        let res = buy_tickets(deps.as_mut(), "bob", 1);
+        assert_eq!(ContractError::TicketHolderCapExceeded {}, res.unwrap_err());
+        // Existing holders can still buy more.
+        buy_tickets(deps.as_mut(), "alice", 1).unwrap();
+    }
+
+    #[test]
+    fn test_raffle_without_tickets_returns_the_nft() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("owner", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        let commitment = RandomnessSource::CommitReveal {
+            commitment: raffle_commitment(),
+        };
+        start_raffle(deps.as_mut(), commitment).unwrap();
+
+        let info = mock_info("anyone", &[]);
+        let res = execute(deps.as_mut(), raffle_env(1000), info, draw_raffle_msg(None)).unwrap();
+        assert_eq!(vec![raffle_nft_msg(MOCK_TOKEN_OWNER)], res.messages);
+    }
+
+    fn update_auction_msg(end: Option<u64>) -> ExecuteMsg {
+        ExecuteMsg::UpdateAuction {
+            auction_id: Uint128::new(1),
+            min_bid: Some(Uint128::new(50)),
+            end,
+            reserve_price: Some(Uint128::new(500)),
+            coin_denomination: None,
+        }
+    }
+
+    fn operator_msg(deps: DepsMut, msg: ExecuteMsg) -> Result<Response, ContractError> {
+        execute(deps, mock_env(), mock_info(MOCK_TOKEN_OWNER, &[]), msg)
+    }
+
+    #[test]
+    fn test_operator_can_update_and_cancel_until_revoked() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), env.clone(), info, InstantiateMsg {}).unwrap();
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
+        env.block.time = Timestamp::from_seconds(50000);
+
+        let revoke = ExecuteMsg::RevokeOperator {
+            operator: "operator".to_string(),
+        };
+        let res = operator_msg(deps.as_mut(), revoke.clone());
+        assert_eq!(ContractError::OperatorNotApproved {}, res.unwrap_err());
+
+        let approve = ExecuteMsg::ApproveOperator {
+            operator: "operator".to_string(),
+            expires: None,
+        };
+        operator_msg(deps.as_mut(), approve.clone()).unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("operator", &[]),
+            update_auction_msg(Some(300000)),
+        )
+        .unwrap();
+        let state = load_nft_auction_state(deps.as_ref().storage, 1u128).unwrap();
+        assert_eq!(Timestamp::from_seconds(300000), state.end);
+
+        operator_msg(deps.as_mut(), revoke.clone()).unwrap();
+        let res = operator_msg(deps.as_mut(), revoke);
+        assert_eq!(ContractError::OperatorNotApproved {}, res.unwrap_err());
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("operator", &[]),
+            update_auction_msg(Some(400000)),
+        );
+        assert_eq!(ContractError::Unauthorized {}, res.unwrap_err());
+        let cancel = ExecuteMsg::CancelAuctionAndRefund {
+            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+            token_address: MOCK_TOKEN_ADDR.to_string(),
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("operator", &[]),
+            cancel.clone(),
+        );
+        assert_eq!(ContractError::Unauthorized {}, res.unwrap_err());
+
+        // Approved again, the operator can cancel on the seller's behalf.
+        operator_msg(deps.as_mut(), approve).unwrap();
+        execute(deps.as_mut(), env, mock_info("operator", &[]), cancel).unwrap();
+        let state = load_nft_auction_state(deps.as_ref().storage, 1u128).unwrap();
+        assert!(state.is_cancelled);
+    }
+
+    #[test]
+    fn test_update_auction_before_first_bid() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), env.clone(), info, InstantiateMsg {}).unwrap();
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
+
+        env.block.time = Timestamp::from_seconds(50000);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            update_auction_msg(None),
+        );
+        assert_eq!(ContractError::Unauthorized {}, res.unwrap_err());
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(MOCK_TOKEN_OWNER, &[]),
+            update_auction_msg(Some(90000)),
+        );
+        assert_eq!(ContractError::InValidTime {}, res.unwrap_err());
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(MOCK_TOKEN_OWNER, &[]),
+            update_auction_msg(Some(300000)),
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                Event::new("auction_updated")
+                    .add_attribute("auction_id", "1")
+                    .add_attribute("denom", "usd")
+                    .add_attribute("min_bid", "50")
+                    .add_attribute("reserve_price", "500"),
+                Event::new("auction_rescheduled")
+                    .add_attribute("auction_id", "1")
+                    .add_attribute("previous_end_time", "200000")
+                    .add_attribute("end_time", "300000"),
+            ],
+            res.events
+        );
+        let state = load_nft_auction_state(deps.as_ref().storage, 1u128).unwrap();
+        assert_eq!(Some(Uint128::new(50)), state.min_bid);
+        assert_eq!(Some(Uint128::new(500)), state.reserve_price);
+        assert_eq!(Timestamp::from_seconds(300000), state.end);
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(MOCK_TOKEN_OWNER, &[]),
+            update_auction_msg(Some(u64::MAX)),
+        );
+        assert_eq!(ContractError::InValidTime {}, res.unwrap_err());
+        let msg = ExecuteMsg::UpdateAuction {
+            auction_id: Uint128::new(1),
+            min_bid: None,
+            end: None,
+            reserve_price: Some(Uint128::zero()),
+            coin_denomination: None,
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(MOCK_TOKEN_OWNER, &[]),
+            msg,
+        )
+        .unwrap();
+        let state = load_nft_auction_state(deps.as_ref().storage, 1u128).unwrap();
+        assert_eq!(Some(Uint128::new(50)), state.min_bid);
+        assert_eq!(None, state.reserve_price);
+
+        assert_eq!(
+            ContractError::BidSmallerThanMinimumBid {},
+            BidBuilder::new("bidder", 40)
+                .submit(deps.as_mut())
+                .unwrap_err()
+        );
+        BidBuilder::new("bidder", 60).submit(deps.as_mut()).unwrap();
+
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info(MOCK_TOKEN_OWNER, &[]),
+            update_auction_msg(None),
+        );
+        assert_eq!(ContractError::AuctionHasBids {}, res.unwrap_err());
+    }
+
+    #[test]
+    fn test_finalize_refunds_bid_below_reserve_price() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let mut env = mock_env();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), env.clone(), info, InstantiateMsg {}).unwrap();
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
+
+        env.block.time = Timestamp::from_seconds(50000);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(MOCK_TOKEN_OWNER, &[]),
+            update_auction_msg(None),
+        )
+        .unwrap();
+        BidBuilder::new("bidder", 100)
+            .submit(deps.as_mut())
+            .unwrap();
+
+        env.block.time = Timestamp::from_seconds(200000);
+        let msg = ExecuteMsg::FinalizeAuctionAndTranferAssets {
+            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+            token_address: MOCK_TOKEN_ADDR.to_string(),
+        };
+        let res = execute(deps.as_mut(), env, mock_info("keeper", &[]), msg).unwrap();
+        assert_eq!(
+            vec![
+                SubMsg::new(BankMsg::Send {
+                    to_address: "bidder".to_owned(),
+                    amount: coins(100, "usd"),
+                }),
+                delivery(
+                    1,
+                    0,
+                    WasmMsg::Execute {
+                        contract_addr: MOCK_TOKEN_ADDR.to_owned(),
+                        msg: to_json_binary(&Cw721ExecuteMsg::TransferNft {
+                            recipient: MOCK_TOKEN_OWNER.to_owned(),
+                            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+                        })
+                        .unwrap(),
+                        funds: vec![],
+                    }
+                ),
+            ],
+            res.messages
+        );
+        assert_eq!(
+            vec![
+                Event::new("auction_settled")
+                    .add_attribute("auction_id", "1")
+                    .add_attribute("seller", MOCK_TOKEN_OWNER)
+                    .add_attribute("recipient", MOCK_TOKEN_OWNER)
+                    .add_attribute("winning_bid", "0")
+                    .add_attribute("denom", "usd")
+                    .add_attribute("reserve_met", "false")
+                    .add_attribute("keeper", "keeper"),
+                Event::new("auction_refunded")
+                    .add_attribute("auction_id", "1")
+                    .add_attribute("recipient", "bidder")
+                    .add_attribute("amount", "100")
+                    .add_attribute("denom", "usd")
+                    .add_attribute("reason", "reserve_not_met"),
+            ],
+            res.events
+        );
+    }
+
+    fn set_cancel_policy(deps: DepsMut, cancel_policy: CancelPolicy) {
+        let msg = ExecuteMsg::UpdateConfig {
+            owner: None,
+            keeper_reward_bps: None,
+            keeper_fee: None,
+            cancel_policy: Some(cancel_policy),
+            listing_deposit: None,
+            bid_increment: None,
+            randomness_beacon: None,
+            ics721: None,
+        };
+        execute(deps, mock_env(), mock_info("owner", &[]), msg).unwrap();
+    }
+
+    fn cancel(deps: DepsMut) -> Result<Response, ContractError> {
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(160000);
+        let msg = ExecuteMsg::CancelAuctionAndRefund {
+            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+            token_address: MOCK_TOKEN_ADDR.to_string(),
+        };
+        execute(deps, env, mock_info(MOCK_TOKEN_OWNER, &[]), msg)
+    }
+
+    #[test]
+    fn test_cancel_policy_before_first_bid() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        set_cancel_policy(deps.as_mut(), CancelPolicy::BeforeFirstBid);
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
+
+        BidBuilder::new("bidder", 100)
+            .submit(deps.as_mut())
+            .unwrap();
+        assert_eq!(
+            ContractError::CancelNotAllowed {},
+            cancel(deps.as_mut()).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn test_cancel_policy_with_penalty() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), env.clone(), info, InstantiateMsg {}).unwrap();
+
+        let msg = ExecuteMsg::UpdateConfig {
+            owner: None,
+            keeper_reward_bps: None,
+            keeper_fee: None,
+            cancel_policy: Some(CancelPolicy::WithPenalty { penalty_bps: 10001 }),
+            listing_deposit: None,
+            bid_increment: None,
+            randomness_beacon: None,
+            ics721: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg);
+        assert_eq!(ContractError::InvalidBasisPoints {}, res.unwrap_err());
+
+        set_cancel_policy(
+            deps.as_mut(),
+            CancelPolicy::WithPenalty { penalty_bps: 1000 },
+        );
+        let config: Config =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(
+            CancelPolicy::WithPenalty { penalty_bps: 1000 },
+            config.cancel_policy
+        );
+
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
+        BidBuilder::new("bidder", 1000)
+            .submit(deps.as_mut())
+            .unwrap();
+
+        // The seller has no bond to pay the penalty from.
+        assert_eq!(
+            ContractError::InsufficientBalance {},
+            cancel(deps.as_mut()).unwrap_err()
+        );
+
+        let info = mock_info(MOCK_TOKEN_OWNER, &coins(150, "usd"));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            ExecuteMsg::DepositBalance {},
+        )
+        .unwrap();
+        let res = cancel(deps.as_mut()).unwrap();
+        assert_eq!(
+            SubMsg::new(BankMsg::Send {
+                to_address: "bidder".to_owned(),
+                amount: coins(1100, "usd"),
+            }),
+            res.messages[1]
+        );
+
+        let msg = QueryMsg::Balance {
+            address: MOCK_TOKEN_OWNER.to_string(),
+        };
+        let balance: Vec<Coin> = from_json(query(deps.as_ref(), env, msg).unwrap()).unwrap();
+        assert_eq!(coins(50, "usd"), balance);
+    }
+
+    fn query_balances(deps: Deps, address: &str) -> (Vec<Coin>, Vec<Coin>) {
+        let msg = QueryMsg::Balance {
+            address: address.to_string(),
+        };
+        let available = from_json(query(deps, mock_env(), msg).unwrap()).unwrap();
+        let msg = QueryMsg::LockedBalance {
+            address: address.to_string(),
+        };
+        let locked = from_json(query(deps, mock_env(), msg).unwrap()).unwrap();
+        (available, locked)
+    }
+
+    #[test]
+    fn test_listing_deposit_refunded_on_settlement() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let mut env = mock_env();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), env.clone(), info, InstantiateMsg {}).unwrap();
+        AuctionBuilder::new()
+            .listing_deposit(coin(5, "fee"))
+            .start(deps.as_mut())
+            .unwrap();
+        assert_eq!(
+            (vec![], coins(5, "fee")),
+            query_balances(deps.as_ref(), MOCK_TOKEN_OWNER)
+        );
+        assert_eq!(
+            Some(coin(5, "fee")),
+            load_nft_auction_state(deps.as_ref().storage, 1u128)
+                .unwrap()
+                .listing_deposit
+        );
+
+        env.block.time = Timestamp::from_seconds(200000);
+        let msg = ExecuteMsg::FinalizeExpired {
+            start_after: None,
+            limit: None,
+        };
+        execute(deps.as_mut(), env, mock_info("keeper", &[]), msg).unwrap();
+        assert_eq!(
+            (coins(5, "fee"), vec![]),
+            query_balances(deps.as_ref(), MOCK_TOKEN_OWNER)
+        );
+    }
+
+    #[test]
+    fn test_listing_deposit_slashed_on_cancel_after_bids() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        AuctionBuilder::new()
+            .listing_deposit(coin(5, "fee"))
+            .start(deps.as_mut())
+            .unwrap();
+
+        BidBuilder::new("bidder", 100)
+            .submit(deps.as_mut())
+            .unwrap();
+        let res = cancel(deps.as_mut()).unwrap();
+        assert_eq!(
+            SubMsg::new(BankMsg::Send {
+                to_address: "owner".to_owned(),
+                amount: coins(5, "fee"),
+            }),
+            res.messages[2]
+        );
+        assert_eq!(
+            (vec![], vec![]),
+            query_balances(deps.as_ref(), MOCK_TOKEN_OWNER)
+        );
+    }
+
+    #[test]
+    fn test_listing_deposit_refunded_on_cancel_without_bids() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        AuctionBuilder::new()
+            .listing_deposit(coin(5, "fee"))
+            .start(deps.as_mut())
+            .unwrap();
+
+        let res = cancel(deps.as_mut()).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_eq!(
+            (coins(5, "fee"), vec![]),
+            query_balances(deps.as_ref(), MOCK_TOKEN_OWNER)
+        );
+    }
+
+    #[test]
+    fn test_query_start_auction() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), env, info, InstantiateMsg {}).unwrap();
+
+        let custom_msg = Cw721CustomMsg::InitializeCW721TokenAuction {
+            start_time: 100000,
+            duration: 100000,
+            coin_denomination: "usd".to_string(),
+            min_bid: None,
+            reserve_price: None,
+            access: None,
+            accepted_denoms: None,
+            start_mode: None,
+        };
+        let msg = ExecuteMsg::AuctionStart(Cw721ReceiveMsg {
+            sender: MOCK_TOKEN_OWNER.to_owned(),
+            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+            msg: to_json_binary(&custom_msg).unwrap(),
+        });
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(0u64);
+
+        let info = mock_info(MOCK_TOKEN_ADDR, &[]);
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::AuctionStart(Cw721ReceiveMsg {
+            sender: "foo_token_owner".to_owned(),
+            token_id: "foo_token".to_owned(),
+            msg: to_json_binary(&custom_msg).unwrap(),
+        });
+
+        let info = mock_info(MOCK_TOKEN_ADDR, &[]);
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        check_auction_created(deps.as_ref(), None);
+
+        let query_msg = QueryMsg::AuctionDetails {
+            token_address: Some(MOCK_TOKEN_ADDR.to_string()),
+            start_after: Some("e".to_string()),
+            limit: Some(10),
+        };
+        let res: Vec<AuctionDetails> =
+            from_json(query(deps.as_ref(), env.clone(), query_msg).unwrap()).unwrap();
+        assert_eq!(
+            vec![
+                AuctionDetails {
+                    auction_ids: vec![Uint128::from(2u128)],
+                    token_address: MOCK_TOKEN_ADDR.to_string(),
+                    token_id: "foo_token".to_string(),
+                },
+                AuctionDetails {
+                    auction_ids: vec![Uint128::from(1u128)],
+                    token_address: MOCK_TOKEN_ADDR.to_string(),
+                    token_id: "mock_unclaimed_token".to_string(),
+                }
+            ],
+            res
+        );
+
+        let query_msg = QueryMsg::AuctionDetails {
+            token_address: Some(MOCK_TOKEN_ADDR.to_string()),
+            start_after: Some("g".to_string()),
+            limit: Some(10),
+        };
+        let res: Vec<AuctionDetails> =
+            from_json(query(deps.as_ref(), env.clone(), query_msg).unwrap()).unwrap();
+        assert_eq!(
+            vec![AuctionDetails {
+                auction_ids: vec![Uint128::from(1u128)],
+                token_address: MOCK_TOKEN_ADDR.to_string(),
+                token_id: "mock_unclaimed_token".to_string(),
+            }],
+            res
+        );
+
+        let query_msg = QueryMsg::AuctionDetails {
+            token_address: None,
+            start_after: None,
+            limit: Some(10),
+        };
+        let res: Vec<AuctionDetails> =
+            from_json(query(deps.as_ref(), env, query_msg).unwrap()).unwrap();
+        assert_eq!(
+            vec![
+                AuctionDetails {
+                    auction_ids: vec![Uint128::from(2u128)],
+                    token_address: MOCK_TOKEN_ADDR.to_string(),
+                    token_id: "foo_token".to_string(),
+                },
+                AuctionDetails {
+                    auction_ids: vec![Uint128::from(1u128)],
+                    token_address: MOCK_TOKEN_ADDR.to_string(),
+                    token_id: "mock_unclaimed_token".to_string(),
+                }
+            ],
+            res
+        );
+    }
+
+    fn query_auction_ids(deps: Deps, env: Env, msg: QueryMsg) -> Vec<Uint128> {
+        let res: Vec<NFTAuctionState> = from_json(query(deps, env, msg).unwrap()).unwrap();
+        res.into_iter().map(|state| state.auction_id).collect()
+    }
+
+    #[test]
+    fn test_query_live_upcoming_and_ending_soon() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info("owner", &[]);
+        let _res = instantiate(deps.as_mut(), env.clone(), info, InstantiateMsg {}).unwrap();
+
+        AuctionBuilder::new()
+            .token("token_1")
+            .window(100000, 100000)
+            .start(deps.as_mut())
+            .unwrap();
+        AuctionBuilder::new()
+            .token("token_2")
+            .window(150000, 250000)
+            .start(deps.as_mut())
+            .unwrap();
+        AuctionBuilder::new()
+            .token("token_3")
+            .window(300000, 50000)
+            .start(deps.as_mut())
+            .unwrap();
+        AuctionBuilder::new()
+            .token("token_4")
+            .window(100000, 50000)
+            .start(deps.as_mut())
+            .unwrap();
+
+        env.block.time = Timestamp::from_seconds(160000);
+
+        let msg = QueryMsg::Live {
+            start_after: None,
+            limit: None,
+        };
+        assert_eq!(
+            vec![Uint128::new(1), Uint128::new(2)],
+            query_auction_ids(deps.as_ref(), env.clone(), msg)
+        );
+        let msg = QueryMsg::Live {
+            start_after: Some(Uint128::new(1)),
+            limit: None,
+        };
+        assert_eq!(
+            vec![Uint128::new(2)],
+            query_auction_ids(deps.as_ref(), env.clone(), msg)
+        );
+
+        let msg = QueryMsg::Upcoming {
+            start_after: None,
+            limit: None,
+        };
+        assert_eq!(
+            vec![Uint128::new(3)],
+            query_auction_ids(deps.as_ref(), env.clone(), msg)
+        );
+
+        let msg = QueryMsg::EndingSoon {
+            within_seconds: 50000,
+            limit: None,
+        };
+        assert_eq!(
+            vec![Uint128::new(1)],
+            query_auction_ids(deps.as_ref(), env.clone(), msg)
+        );
+        let msg = QueryMsg::EndingSoon {
+            within_seconds: 300000,
+            limit: Some(1),
+        };
+        assert_eq!(
+            vec![Uint128::new(1)],
+            query_auction_ids(deps.as_ref(), env.clone(), msg)
+        );
+
+        // Cancelled auctions are no longer listed.
+        let msg = ExecuteMsg::CancelAuctionAndRefund {
+            token_id: "token_1".to_owned(),
+            token_address: MOCK_TOKEN_ADDR.to_string(),
+        };
+        let info = mock_info(MOCK_TOKEN_OWNER, &[]);
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let msg = QueryMsg::EndingSoon {
+            within_seconds: 300000,
+            limit: None,
+        };
+        assert_eq!(
+            vec![Uint128::new(2)],
+            query_auction_ids(deps.as_ref(), env.clone(), msg)
+        );
+        let msg = QueryMsg::EndingSoon {
+            within_seconds: u64::MAX,
+            limit: None,
+        };
+        assert_eq!(
+            vec![Uint128::new(2)],
+            query_auction_ids(deps.as_ref(), env, msg)
+        );
+    }
+
+    #[test]
+    fn test_get_bids_defaults() {
+        let mut deps = mock_dependencies();
+        let auction_id: u128 = 1;
+        // Assume some Bids are already stored
+        let sample_bids = vec![
+            Bid {
+                bidder: "Alice".to_string(),
+                amount: Uint128::from(100u128),
+                timestamp: Timestamp::from_seconds(1000),
+            },
+            Bid {
+                bidder: "Bob".to_string(),
+                amount: Uint128::from(200u128),
+                timestamp: Timestamp::from_seconds(1001),
+            },
+        ];
+        save_bids(&mut deps.storage, auction_id, sample_bids).unwrap();
+
+        let result = get_bids(&deps.storage, auction_id, None, None, None).unwrap();
+        assert_eq!(result.len(), 2); // Default limit is 20, so should show both bids
+        assert_eq!(result[0].bidder, "Alice");
+    }
+
+    #[test]
+    fn test_get_bids_desc_order() {
+        let mut deps = mock_dependencies();
+        let auction_id: u128 = 1;
+        let sample_bids = vec![
+            Bid {
+                bidder: "Alice".to_string(),
+                amount: Uint128::from(100u128),
+                timestamp: Timestamp::from_seconds(1000),
+            },
+            Bid {
+                bidder: "Bob".to_string(),
+                amount: Uint128::from(200u128),
+                timestamp: Timestamp::from_seconds(1001),
+            },
+        ];
+        save_bids(&mut deps.storage, auction_id, sample_bids).unwrap();
+
+        let result = get_bids(
+            &deps.storage,
+            auction_id,
+            None,
+            Some(10),
+            Some(OrderBy::Desc),
+        )
+        .unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].bidder, "Bob"); // Order should be descending by timestamp
+    }
+
+    #[test]
+    fn test_read_auction_details_no_token_address() {
+        let mut deps = mock_dependencies();
+
+        // Setup an example AuctionDetails entry
+        let details = AuctionDetails {
+            auction_ids: vec![Uint128::new(1)],
+            token_address: "cosmos_token_address".to_string(),
+            token_id: "cosmos_token_id".to_string(),
+        };
+        save_auction_details(
+            &mut deps.storage,
+            "cosmos_token_address".to_string(),
+            details,
+        )
+        .unwrap();
+
+        let result = read_auction_details(&deps.storage, None, None, None).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].token_address, "cosmos_token_address");
+    }
+
+    #[test]
+    fn test_read_auction_details_with_token_address() {
+        let mut deps = mock_dependencies();
+
+        // Setup multiple AuctionDetails entries
+        let details = AuctionDetails {
+            auction_ids: vec![Uint128::new(1), Uint128::new(2)],
+            token_address: "specific_token_address".to_string(),
+            token_id: "specific_token_id".to_string(),
+        };
+        save_auction_details(
+            &mut deps.storage,
+            "specific_token_address".to_string(),
+            details,
+        )
+        .unwrap();
+
+        let result = read_auction_details(
+            &deps.storage,
+            Some("specific_token_address".to_string()),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].token_id, "specific_token_id");
+    }
+
+    fn create_lazy_auction(
+        deps: DepsMut,
+        sender: &str,
+        token_id: &str,
+    ) -> Result<Response, ContractError> {
+        let msg = ExecuteMsg::CreateLazyAuction {
+            token_address: MOCK_TOKEN_ADDR.to_string(),
+            token_id: token_id.to_owned(),
+            token_uri: Some("ipfs://lazy".to_owned()),
+            start_time: 100000,
+            duration: 100000,
+            coin_denomination: "usd".to_string(),
+            min_bid: None,
+            reserve_price: Some(Uint128::new(100)),
+            accepted_denoms: None,
+        };
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(0u64);
+        execute(deps, env, mock_info(sender, &[]), msg)
+    }
+
+    fn finalize_lazy_auction(deps: DepsMut) -> Result<Response, ContractError> {
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(200000);
+        let msg = ExecuteMsg::FinalizeAuctionAndTranferAssets {
+            token_id: MOCK_LAZY_TOKEN.to_owned(),
+            token_address: MOCK_TOKEN_ADDR.to_string(),
+        };
+        execute(deps, env, mock_info("keeper", &[]), msg)
+    }
+
+    #[test]
+    fn test_create_lazy_auction_checks_the_collection() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let info = mock_info("owner", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+
+        let res = create_lazy_auction(deps.as_mut(), "someone", MOCK_LAZY_TOKEN);
+        assert_eq!(ContractError::Unauthorized {}, res.unwrap_err());
+        let res = create_lazy_auction(deps.as_mut(), MOCK_TOKEN_OWNER, MOCK_UNCLAIMED_TOKEN);
+        assert_eq!(
+            ContractError::InvalidLazyMint {
+                msg: "Token has already been minted".to_string()
+            },
+            res.unwrap_err()
+        );
+
+        create_lazy_auction(deps.as_mut(), MOCK_TOKEN_OWNER, MOCK_LAZY_TOKEN).unwrap();
+        let auction = load_nft_auction_state(deps.as_ref().storage, 1u128).unwrap();
+        assert_eq!(
+            Some(LazyMint {
+                token_uri: Some("ipfs://lazy".to_owned())
+            }),
+            auction.lazy_mint
+        );
+        let res = create_lazy_auction(deps.as_mut(), MOCK_TOKEN_OWNER, MOCK_LAZY_TOKEN);
+        assert_eq!(
+            ContractError::InvalidLazyMint {
+                msg: "Token is already being auctioned".to_string()
+            },
+            res.unwrap_err()
+        );
+    }
+
+    #[test]
+    fn test_lazy_auction_mints_to_the_winner() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let info = mock_info("owner", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        create_lazy_auction(deps.as_mut(), MOCK_TOKEN_OWNER, MOCK_LAZY_TOKEN).unwrap();
+
+        BidBuilder::new("bidder", 100)
+            .token(MOCK_LAZY_TOKEN)
+            .submit(deps.as_mut())
+            .unwrap();
+
+        let res = finalize_lazy_auction(deps.as_mut()).unwrap();
+        assert_eq!(
+            vec![
+                SubMsg::new(BankMsg::Send {
+                    to_address: MOCK_TOKEN_OWNER.to_owned(),
+                    amount: coins(100, "usd"),
+                }),
+                delivery(
+                    1,
+                    0,
+                    WasmMsg::Execute {
+                        contract_addr: MOCK_TOKEN_ADDR.to_owned(),
+                        msg: to_json_binary(&Cw721MinterExecuteMsg::Mint {
+                            token_id: MOCK_LAZY_TOKEN.to_owned(),
+                            owner: "bidder".to_owned(),
+                            token_uri: Some("ipfs://lazy".to_owned()),
+                            extension: Empty {},
+                        })
+                        .unwrap(),
+                        funds: vec![],
+                    }
+                ),
+            ],
+            res.messages
+        );
+    }
+
+    #[test]
+    fn test_lazy_auction_is_invalidated_when_the_minter_changes() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let info = mock_info("owner", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        create_lazy_auction(deps.as_mut(), MOCK_TOKEN_OWNER, MOCK_LAZY_TOKEN).unwrap();
+        BidBuilder::new("bidder", 100)
+            .token(MOCK_LAZY_TOKEN)
+            .submit(deps.as_mut())
+            .unwrap();
+
+        deps.querier.minter = "new_minter".to_owned();
+        let res = finalize_lazy_auction(deps.as_mut()).unwrap();
+        assert_eq!(vec![refund_msg("bidder", 100)], res.messages);
+        assert_eq!("auction_invalidated", res.events[0].ty);
+        let auction = load_nft_auction_state(deps.as_ref().storage, 1u128).unwrap();
+        assert!(auction.is_cancelled && !auction.is_settled);
+    }
+
+    #[test]
+    fn test_unsold_lazy_auction_mints_nothing() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let info = mock_info("owner", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        create_lazy_auction(deps.as_mut(), MOCK_TOKEN_OWNER, MOCK_LAZY_TOKEN).unwrap();
+
+        let res = finalize_lazy_auction(deps.as_mut()).unwrap();
+        assert!(res.messages.is_empty());
+        assert!(
+            load_nft_auction_state(deps.as_ref().storage, 1u128)
+                .unwrap()
+                .is_settled
+        );
+
+        // The token can be auctioned again once the previous auction is over.
+        create_lazy_auction(deps.as_mut(), MOCK_TOKEN_OWNER, MOCK_LAZY_TOKEN).unwrap();
+        let msg = ExecuteMsg::CancelAuctionAndRefund {
+            token_id: MOCK_LAZY_TOKEN.to_owned(),
+            token_address: MOCK_TOKEN_ADDR.to_string(),
+        };
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(150000);
+        let info = mock_info(MOCK_TOKEN_OWNER, &[]);
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert!(res.messages.is_empty());
+    }
+
+    fn create_approved_auction(deps: DepsMut, token_id: &str) -> Result<Response, ContractError> {
+        let msg = ExecuteMsg::CreateApprovedAuction {
+            token_address: MOCK_TOKEN_ADDR.to_string(),
+            token_id: token_id.to_owned(),
+            start_time: 100000,
+            duration: 100000,
+            coin_denomination: "usd".to_string(),
+            min_bid: None,
+            reserve_price: None,
+            access: None,
+            accepted_denoms: None,
+        };
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(0u64);
+        execute(deps, env, mock_info(MOCK_TOKEN_OWNER, &[]), msg)
+    }
+
+    #[test]
+    fn test_create_approved_auction_requires_owner_and_approval() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let info = mock_info("owner", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+
+        let res = create_approved_auction(deps.as_mut(), MOCK_UNCLAIMED_TOKEN);
+        assert_eq!(ContractError::Unauthorized {}, res.unwrap_err());
+        let res = create_approved_auction(deps.as_mut(), "not_approved");
+        assert_eq!(ContractError::NftNotApproved {}, res.unwrap_err());
+
+        create_approved_auction(deps.as_mut(), MOCK_APPROVED_TOKEN).unwrap();
+        assert!(
+            load_nft_auction_state(deps.as_ref().storage, 1u128)
+                .unwrap()
+                .held_by_seller
+        );
+        let res = create_approved_auction(deps.as_mut(), MOCK_APPROVED_TOKEN);
+        assert_eq!(ContractError::AuctionAlreadyOpen {}, res.unwrap_err());
+    }
+
+    #[test]
+    fn test_approved_auction_transfers_from_the_seller() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let info = mock_info("owner", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        create_approved_auction(deps.as_mut(), MOCK_APPROVED_TOKEN).unwrap();
+
+        let mut env = mock_env();
+        BidBuilder::new("bidder", 100)
+            .token(MOCK_APPROVED_TOKEN)
+            .submit(deps.as_mut())
+            .unwrap();
+
+        env.block.time = Timestamp::from_seconds(200000);
+        let msg = ExecuteMsg::FinalizeAuctionAndTranferAssets {
+            token_id: MOCK_APPROVED_TOKEN.to_owned(),
+            token_address: MOCK_TOKEN_ADDR.to_string(),
+        };
+        let res = execute(deps.as_mut(), env, mock_info("keeper", &[]), msg).unwrap();
+        assert_eq!(
+            vec![
+                SubMsg::new(BankMsg::Send {
+                    to_address: MOCK_TOKEN_OWNER.to_owned(),
+                    amount: coins(100, "usd"),
+                }),
+                delivery(
+                    1,
+                    0,
+                    WasmMsg::Execute {
+                        contract_addr: MOCK_TOKEN_ADDR.to_owned(),
+                        msg: to_json_binary(&Cw721ExecuteMsg::TransferNft {
+                            recipient: "bidder".to_owned(),
+                            token_id: MOCK_APPROVED_TOKEN.to_owned(),
+                        })
+                        .unwrap(),
+                        funds: vec![],
+                    }
+                ),
+            ],
+            res.messages
+        );
+    }
+
+    fn ibc_origin() -> IbcOrigin {
+        IbcOrigin {
+            channel_id: "channel-0".to_owned(),
+            sender: "cosmos1remote".to_owned(),
+            nft_recipient: "local_recipient".to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_ibc_hook_bid_requires_the_derived_sender() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let info = mock_info("owner", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
+
+        let res = BidBuilder::new("bidder", 100)
+            .ibc(ibc_origin())
+            .submit(deps.as_mut());
+        assert_eq!(ContractError::InvalidIbcOrigin {}, res.unwrap_err());
+        let other_channel = IbcOrigin {
+            channel_id: "channel-1".to_owned(),
+            ..ibc_origin()
+        };
+        let sender = ibc_hook_sender(&other_channel, "osmo").unwrap();
+        let res = BidBuilder::new(sender.as_str(), 100)
+            .ibc(ibc_origin())
+            .submit(deps.as_mut());
+        assert_eq!(ContractError::InvalidIbcOrigin {}, res.unwrap_err());
+    }
+
+    #[test]
+    fn test_ibc_hook_bid_is_refunded_over_ibc() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let info = mock_info("owner", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
+
+        let sender = ibc_hook_sender(&ibc_origin(), "osmo").unwrap();
+        BidBuilder::new(sender.as_str(), 100)
+            .ibc(ibc_origin())
+            .submit(deps.as_mut())
+            .unwrap();
+        let res = BidBuilder::new("bidder", 200)
+            .submit(deps.as_mut())
+            .unwrap();
+        let env = mock_env();
+        let transfer = MsgTransfer {
+            source_port: "transfer".to_owned(),
+            source_channel: "channel-0".to_owned(),
+            token: Some(ProtoCoin {
+                denom: "usd".to_owned(),
+                amount: "100".to_owned(),
+            }),
+            sender: env.contract.address.to_string(),
+            receiver: "cosmos1remote".to_owned(),
+            timeout_timestamp: Timestamp::from_seconds(150000)
+                .plus_seconds(IBC_REFUND_TIMEOUT_SECONDS)
+                .nanos(),
+            memo: format!(r#"{{"ibc_callback":"{}"}}"#, env.contract.address),
+        };
+        assert_eq!(
+            vec![SubMsg::reply_always(
+                CosmosMsg::Stargate {
+                    type_url: "/ibc.applications.transfer.v1.MsgTransfer".to_owned(),
+                    value: transfer.encode_to_vec().into(),
+                },
+                IBC_REFUND_REPLY_FLAG,
+            )],
+            res.messages
+        );
+        let refund = res
+            .events
+            .iter()
+            .find(|event| event.ty == "auction_refunded")
+            .unwrap();
+        assert!(refund
+            .attributes
+            .contains(&attr("ibc_recipient", "cosmos1remote")));
+    }
+
+    /// Replies to the first IBC refund with packet sequence 7.
+    fn reply_ibc_refund_sequence(deps: DepsMut) {
+        let response = MsgTransferResponse { sequence: 7 };
+        let msg = Reply {
+            id: IBC_REFUND_REPLY_FLAG,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: Some(response.encode_to_vec().into()),
+            }),
+        };
+        reply(deps, mock_env(), msg).unwrap();
+    }
+
+    #[test]
+    fn test_failed_ibc_refund_is_credited_to_the_local_recipient() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let info = mock_info("owner", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
+        let sender = ibc_hook_sender(&ibc_origin(), "osmo").unwrap();
+        BidBuilder::new(sender.as_str(), 100)
+            .ibc(ibc_origin())
+            .submit(deps.as_mut())
+            .unwrap();
+        BidBuilder::new("bidder", 200)
+            .submit(deps.as_mut())
+            .unwrap();
+        reply_ibc_refund_sequence(deps.as_mut());
+
+        // Only the packet the refund was sent in is matched.
+        let timeout = |sequence| {
+            SudoMsg::IbcLifecycleComplete(IbcLifecycleComplete::IbcTimeout {
+                channel: "channel-0".to_owned(),
+                sequence,
+            })
+        };
+        assert!(sudo(deps.as_mut(), mock_env(), timeout(8)).is_err());
+        let res = sudo(deps.as_mut(), mock_env(), timeout(7)).unwrap();
+        assert_eq!(
+            vec![Event::new("auction_refund_credited")
+                .add_attribute("auction_id", "1")
+                .add_attribute("recipient", "local_recipient")
+                .add_attribute("amount", "100")
+                .add_attribute("denom", "usd")],
+            res.events
+        );
+        assert_eq!(
+            coins(100, "usd"),
+            query_balances(deps.as_ref(), "local_recipient").0
+        );
+        // Each refund is credited once.
+        assert!(sudo(deps.as_mut(), mock_env(), timeout(7)).is_err());
+    }
+
+    #[test]
+    fn test_ibc_refund_that_cannot_be_sent_is_credited_to_the_local_recipient() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let info = mock_info("owner", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
+        let sender = ibc_hook_sender(&ibc_origin(), "osmo").unwrap();
+        BidBuilder::new(sender.as_str(), 100)
+            .ibc(ibc_origin())
+            .submit(deps.as_mut())
+            .unwrap();
+        BidBuilder::new("bidder", 200)
+            .submit(deps.as_mut())
+            .unwrap();
+
+        let msg = Reply {
+            id: IBC_REFUND_REPLY_FLAG,
+            result: SubMsgResult::Err("channel not found".to_owned()),
+        };
+        let res = reply(deps.as_mut(), mock_env(), msg.clone()).unwrap();
+        assert_eq!(
+            vec![Event::new("auction_refund_credited")
+                .add_attribute("auction_id", "1")
+                .add_attribute("recipient", "local_recipient")
+                .add_attribute("amount", "100")
+                .add_attribute("denom", "usd")],
+            res.events
+        );
+        assert_eq!(
+            coins(100, "usd"),
+            query_balances(deps.as_ref(), "local_recipient").0
+        );
+        // The outbid stands, and the refund is credited once.
+        let auction = load_nft_auction_state(&deps.storage, 1).unwrap();
+        assert_eq!(Addr::unchecked("bidder"), auction.high_bidder_addr);
+        assert!(reply(deps.as_mut(), mock_env(), msg).is_err());
+    }
+
+    #[test]
+    fn test_acknowledged_ibc_refund_is_not_credited() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let info = mock_info("owner", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
+        let sender = ibc_hook_sender(&ibc_origin(), "osmo").unwrap();
+        BidBuilder::new(sender.as_str(), 100)
+            .ibc(ibc_origin())
+            .submit(deps.as_mut())
+            .unwrap();
+        BidBuilder::new("bidder", 200)
+            .submit(deps.as_mut())
+            .unwrap();
+        reply_ibc_refund_sequence(deps.as_mut());
+
+        let msg = SudoMsg::IbcLifecycleComplete(IbcLifecycleComplete::IbcAck {
+            channel: "channel-0".to_owned(),
+            sequence: 7,
+            ack: "eyJyZXN1bHQiOiJBUT09In0=".to_owned(),
+            success: true,
+        });
+        let res = sudo(deps.as_mut(), mock_env(), msg).unwrap();
+        assert!(res.events.is_empty());
+        assert!(query_balances(deps.as_ref(), "local_recipient")
+            .0
+            .is_empty());
+    }
+
+    #[test]
+    fn test_storage_cache_buffers_writes_until_committed() {
+        let mut storage = MockStorage::new();
+        storage.set(b"a", b"1");
+        storage.set(b"b", b"2");
+        storage.set(b"d", b"4");
+
+        let mut cache = StorageCache::new(&storage);
+        cache.set(b"c", b"3");
+        cache.set(b"d", b"5");
+        cache.remove(b"b");
+        assert_eq!(None, cache.get(b"b"));
+        assert_eq!(Some(b"5".to_vec()), cache.get(b"d"));
+        let expected = vec![
+            (b"a".to_vec(), b"1".to_vec()),
+            (b"c".to_vec(), b"3".to_vec()),
+            (b"d".to_vec(), b"5".to_vec()),
+        ];
+        assert_eq!(
+            expected,
+            cache
+                .range(None, None, Order::Ascending)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            expected.iter().rev().cloned().collect::<Vec<_>>(),
+            cache
+                .range(None, None, Order::Descending)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![(b"c".to_vec(), b"3".to_vec())],
+            cache
+                .range(Some(b"b"), Some(b"d"), Order::Ascending)
+                .collect::<Vec<_>>()
+        );
+        // Empty and inverted ranges yield nothing, as they do on the base storage.
+        for (start, end) in [(b"c", b"c"), (b"d", b"b")] {
+            for order in [Order::Ascending, Order::Descending] {
+                assert_eq!(0, storage.range(Some(start), Some(end), order).count());
+                assert_eq!(0, cache.range(Some(start), Some(end), order).count());
+            }
+        }
+
+        // Nothing reaches the base storage before the commit.
+        let writes = cache.into_writes();
+        assert_eq!(Some(b"2".to_vec()), storage.get(b"b"));
+        writes.commit(&mut storage);
+        assert_eq!(
+            expected,
+            storage
+                .range(None, None, Order::Ascending)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_failed_delivery_leaves_the_nft_claimable() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let info = mock_info("owner", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
+        BidBuilder::new("bidder", 100)
+            .submit(deps.as_mut())
+            .unwrap();
+        finalize_at(deps.as_mut(), 200000).unwrap();
+
+        let failure = Reply {
+            id: delivery_reply_id(Uint128::one(), 0).unwrap(),
+            result: SubMsgResult::Err("transfer failed".to_owned()),
+        };
+        reply(deps.as_mut(), mock_env(), failure).unwrap();
+        assert!(
+            load_nft_auction_state(deps.as_ref().storage, 1u128)
+                .unwrap()
+                .is_settled
+        );
+        let res = claim_nft(deps.as_mut()).unwrap();
+        assert_eq!(
+            vec![SubMsg::new(WasmMsg::Execute {
+                contract_addr: MOCK_TOKEN_ADDR.to_owned(),
+                msg: to_json_binary(&Cw721ExecuteMsg::TransferNft {
+                    recipient: "bidder".to_owned(),
+                    token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+                })
+                .unwrap(),
+                funds: vec![],
+            })],
+            res.messages
+        );
+        let res = claim_nft(deps.as_mut());
+        assert_eq!(ContractError::NftNotClaimable {}, res.unwrap_err());
+    }
+
+    #[test]
+    fn test_ibc_hook_bidder_wins_to_the_nft_recipient() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let info = mock_info("owner", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
+
+        let sender = ibc_hook_sender(&ibc_origin(), "osmo").unwrap();
+        BidBuilder::new(sender.as_str(), 100)
+            .ibc(ibc_origin())
+            .submit(deps.as_mut())
+            .unwrap();
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(200000);
+        let msg = ExecuteMsg::FinalizeAuctionAndTranferAssets {
+            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+            token_address: MOCK_TOKEN_ADDR.to_string(),
+        };
+        let res = execute(deps.as_mut(), env, mock_info("keeper", &[]), msg).unwrap();
+        assert_eq!(
+            delivery(
+                1,
+                0,
+                WasmMsg::Execute {
+                    contract_addr: MOCK_TOKEN_ADDR.to_owned(),
+                    msg: to_json_binary(&Cw721ExecuteMsg::TransferNft {
+                        recipient: "local_recipient".to_owned(),
+                        token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                }
+            ),
+            res.messages[1]
+        );
+    }
+
+    fn finalize_at(deps: DepsMut, time: u64) -> Result<Response, ContractError> {
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(time);
+        let msg = ExecuteMsg::FinalizeAuctionAndTranferAssets {
+            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+            token_address: MOCK_TOKEN_ADDR.to_string(),
+        };
+        execute(deps, env, mock_info("keeper", &[]), msg)
+    }
+
+    fn claim_nft(deps: DepsMut) -> Result<Response, ContractError> {
+        let msg = ExecuteMsg::ClaimNft {
+            auction_id: Uint128::one(),
+        };
+        execute(deps, mock_env(), mock_info("anyone", &[]), msg)
+    }
+
+    #[test]
+    fn test_bridged_auction_sends_the_nft_back_over_ics721() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let info = mock_info("owner", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        AuctionBuilder::new()
+            .bridged()
+            .start(deps.as_mut())
+            .unwrap();
+        assert_eq!(
+            Some(Ics721Class {
+                ics721: Addr::unchecked(MOCK_ICS721_ADDR),
+                class_id: format!("wasm.{MOCK_ICS721_ADDR}/channel-5/origin-class"),
+                channel_id: "channel-5".to_owned(),
+            }),
+            load_nft_auction_state(deps.as_ref().storage, 1u128)
+                .unwrap()
+                .ics721_class
+        );
+
+        let msg = ExecuteMsg::SetIcs721Receiver {
+            auction_id: Uint128::one(),
+            receiver: "stars1winner".to_owned(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bidder", &[]), msg).unwrap();
+        BidBuilder::new("bidder", 100)
+            .submit(deps.as_mut())
+            .unwrap();
+        let res = finalize_at(deps.as_mut(), 200000).unwrap();
+        let timeout = Timestamp::from_seconds(200000).plus_seconds(ICS721_TIMEOUT_SECONDS);
+        assert_eq!(
+            delivery(
+                1,
+                0,
+                WasmMsg::Execute {
+                    contract_addr: MOCK_TOKEN_ADDR.to_owned(),
+                    msg: to_json_binary(&Cw721ExecuteMsg::SendNft {
+                        contract: MOCK_ICS721_ADDR.to_owned(),
+                        token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+                        msg: to_json_binary(&IbcOutgoingMsg {
+                            receiver: "stars1winner".to_owned(),
+                            channel_id: "channel-5".to_owned(),
+                            timeout: timeout.into(),
+                            memo: None,
+                        })
+                        .unwrap(),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                },
+            ),
+            res.messages[1]
+        );
+        // Nothing can be claimed while ICS-721 holds the NFT.
+        deps.querier
+            .token_owners
+            .insert(MOCK_UNCLAIMED_TOKEN.to_owned(), MOCK_ICS721_ADDR.to_owned());
+        let res = claim_nft(deps.as_mut());
+        assert_eq!(ContractError::NftNotClaimable {}, res.unwrap_err());
+    }
+
+    #[test]
+    fn test_nft_returned_by_ics721_can_be_claimed_locally() {
+        // ICS-721 returns the NFT with a plain transfer both on an error acknowledgement and on a
+        // timeout, so the two only differ in when the NFT comes back.
+        for (failure, returned_at) in [("ack_error", 200010), ("timeout", 200000 + 600)] {
+            let mut deps = custom_mock_dependencies(&[]);
+            let info = mock_info("owner", &[]);
+            instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+            AuctionBuilder::new()
+                .bridged()
+                .start(deps.as_mut())
+                .unwrap();
+            let msg = ExecuteMsg::SetIcs721Receiver {
+                auction_id: Uint128::one(),
+                receiver: "stars1winner".to_owned(),
+            };
+            execute(deps.as_mut(), mock_env(), mock_info("bidder", &[]), msg).unwrap();
+            BidBuilder::new("bidder", 100)
+                .submit(deps.as_mut())
+                .unwrap();
+            finalize_at(deps.as_mut(), 200000).unwrap();
+            deps.querier
+                .token_owners
+                .insert(MOCK_UNCLAIMED_TOKEN.to_owned(), MOCK_ICS721_ADDR.to_owned());
+            let res = claim_nft(deps.as_mut());
+            assert_eq!(
+                ContractError::NftNotClaimable {},
+                res.unwrap_err(),
+                "{failure}"
+            );
+
+            deps.querier.token_owners.clear();
+            let mut env = mock_env();
+            env.block.time = Timestamp::from_seconds(returned_at);
+            let msg = ExecuteMsg::ClaimNft {
+                auction_id: Uint128::one(),
+            };
+            let res = execute(deps.as_mut(), env, mock_info("anyone", &[]), msg).unwrap();
+            assert_eq!(
+                vec![SubMsg::new(WasmMsg::Execute {
+                    contract_addr: MOCK_TOKEN_ADDR.to_owned(),
+                    msg: to_json_binary(&Cw721ExecuteMsg::TransferNft {
+                        recipient: "bidder".to_owned(),
+                        token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                })],
+                res.messages,
+                "{failure}"
+            );
+            let res = claim_nft(deps.as_mut());
+            assert_eq!(
+                ContractError::NftNotClaimable {},
+                res.unwrap_err(),
+                "{failure}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_nft_returned_by_ics721_is_not_claimable_once_auctioned_again() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let info = mock_info("owner", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        AuctionBuilder::new()
+            .bridged()
+            .start(deps.as_mut())
+            .unwrap();
+        let msg = ExecuteMsg::SetIcs721Receiver {
+            auction_id: Uint128::one(),
+            receiver: "stars1winner".to_owned(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bidder", &[]), msg).unwrap();
+        BidBuilder::new("bidder", 100)
+            .submit(deps.as_mut())
+            .unwrap();
+        finalize_at(deps.as_mut(), 200000).unwrap();
+        // The winner received the NFT on its origin chain and bridged it back for a new auction.
+        AuctionBuilder::new()
+            .window(300000, 100000)
+            .start(deps.as_mut())
+            .unwrap();
+        let res = claim_nft(deps.as_mut());
+        assert_eq!(ContractError::NftNotClaimable {}, res.unwrap_err());
+    }
+
+    #[test]
+    fn test_failed_ics721_send_back_falls_back_to_a_local_claim() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let info = mock_info("owner", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        AuctionBuilder::new()
+            .bridged()
+            .start(deps.as_mut())
+            .unwrap();
+        let msg = ExecuteMsg::SetIcs721Receiver {
+            auction_id: Uint128::one(),
+            receiver: "stars1winner".to_owned(),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bidder", &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        BidBuilder::new("bidder", 100)
+            .submit(deps.as_mut())
+            .unwrap();
+        let res = claim_nft(deps.as_mut());
+        assert_eq!(ContractError::NftNotClaimable {}, res.unwrap_err());
+        finalize_at(deps.as_mut(), 200000).unwrap();
+        let id = delivery_reply_id(Uint128::one(), 0).unwrap();
+        let res = reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply {
+                id,
+                result: SubMsgResult::Ok(SubMsgResponse {
+                    events: vec![],
+                    data: None,
+                }),
+            },
+        );
+        assert_eq!(ContractError::UnexpectedReply { id }, res.unwrap_err());
+        let res = execute(deps.as_mut(), mock_env(), mock_info("bidder", &[]), msg);
+        assert_eq!(ContractError::AuctionAlreadyClaimed {}, res.unwrap_err());
+
+        let res = reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply {
+                id,
+                result: SubMsgResult::Err("channel closed".to_owned()),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            vec![Event::new("auction_delivery_failed")
+                .add_attribute("auction_id", "1")
+                .add_attribute("token_address", MOCK_TOKEN_ADDR)
+                .add_attribute("token_id", MOCK_UNCLAIMED_TOKEN)
+                .add_attribute("recipient", "bidder")
+                .add_attribute("error", "channel closed")],
+            res.events
+        );
+        let res = claim_nft(deps.as_mut()).unwrap();
+        assert_eq!(
+            vec![SubMsg::new(WasmMsg::Execute {
+                contract_addr: MOCK_TOKEN_ADDR.to_owned(),
+                msg: to_json_binary(&Cw721ExecuteMsg::TransferNft {
+                    recipient: "bidder".to_owned(),
+                    token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+                })
+                .unwrap(),
+                funds: vec![],
+            })],
+            res.messages
+        );
+        let res = claim_nft(deps.as_mut());
+        assert_eq!(ContractError::NftNotClaimable {}, res.unwrap_err());
+    }
+
+    #[test]
+    fn test_ics721_receiver_requires_a_bridged_auction() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let info = mock_info("owner", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        AuctionBuilder::new().start(deps.as_mut()).unwrap();
+        let msg = ExecuteMsg::SetIcs721Receiver {
+            auction_id: Uint128::one(),
+            receiver: "stars1winner".to_owned(),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("bidder", &[]), msg);
+        assert_eq!(ContractError::NotBridged {}, res.unwrap_err());
+    }
+
+    fn save_template(deps: DepsMut, name: &str, duration: u64) -> Result<Response, ContractError> {
+        let msg = ExecuteMsg::SaveTemplate {
+            name: name.to_owned(),
+            params: AuctionTemplate {
+                duration,
+                coin_denomination: "usd".to_string(),
+                min_bid: Some(Uint128::new(50)),
+                reserve_price: Some(Uint128::new(200)),
+                access: None,
+                accepted_denoms: None,
+                bid_increment: Some(Uint128::new(25)),
+                extension: Some(600),
+            },
+        };
+        execute(deps, mock_env(), mock_info(MOCK_TOKEN_OWNER, &[]), msg)
+    }
+
+    fn start_from_template(
+        deps: DepsMut,
+        name: &str,
+        overrides: Option<TemplateOverrides>,
+    ) -> Result<Response, ContractError> {
+        let custom_msg = Cw721CustomMsg::StartFromTemplate {
+            name: name.to_owned(),
+            start_time: 100000,
+            overrides,
+            start_mode: None,
+        };
+        let msg = ExecuteMsg::AuctionStart(Cw721ReceiveMsg {
+            sender: MOCK_TOKEN_OWNER.to_owned(),
+            token_id: MOCK_UNCLAIMED_TOKEN.to_owned(),
+            msg: to_json_binary(&custom_msg).unwrap(),
+        });
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(0u64);
+        execute(deps, env, mock_info(MOCK_TOKEN_ADDR, &[]), msg)
+    }
+
+    #[test]
+    fn test_start_auction_from_template() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let info = mock_info("owner", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        assert_eq!(
+            ContractError::InValidTime {},
+            save_template(deps.as_mut(), "weekly", 0).unwrap_err()
+        );
+        for name in ["", &"a".repeat(MAX_TEMPLATE_NAME_LENGTH + 1)] {
+            assert_eq!(
+                ContractError::InvalidTemplate {
+                    msg: format!(
+                        "Template names are 1 to {MAX_TEMPLATE_NAME_LENGTH} characters long"
+                    ),
+                },
+                save_template(deps.as_mut(), name, 100000).unwrap_err()
+            );
+        }
+        for name in ["week ly", "wöchentlich", "weekly\n"] {
+            assert_eq!(
+                ContractError::InvalidTemplate {
+                    msg: "Template names may only contain ASCII letters, digits, '-', '_' and '.'"
+                        .to_string(),
+                },
+                save_template(deps.as_mut(), name, 100000).unwrap_err()
+            );
+        }
+        save_template(deps.as_mut(), "weekly", 100000).unwrap();
+        let templates: Vec<NamedTemplate> = from_json(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Templates {
+                    seller: MOCK_TOKEN_OWNER.to_owned(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            vec!["weekly"],
+            templates.iter().map(|t| &t.name).collect::<Vec<_>>()
+        );
+
+        let res = start_from_template(deps.as_mut(), "daily", None);
+        assert_eq!(ContractError::TemplateDoesNotExist {}, res.unwrap_err());
+        start_from_template(
+            deps.as_mut(),
+            "weekly",
+            Some(TemplateOverrides {
+                duration: Some(50000),
+                reserve_price: Some(Uint128::new(300)),
+                ..TemplateOverrides::default()
+            }),
+        )
+        .unwrap();
+        let auction = load_nft_auction_state(deps.as_ref().storage, 1).unwrap();
+        assert_eq!(Timestamp::from_seconds(100000), auction.start);
+        assert_eq!(Timestamp::from_seconds(150000), auction.end);
+        assert_eq!(Some(Uint128::new(50)), auction.min_bid);
+        assert_eq!(Some(Uint128::new(300)), auction.reserve_price);
+        assert_eq!("usd", auction.coin_denomination);
+        assert_eq!(Some(Uint128::new(25)), auction.bid_increment);
+        assert_eq!(Some(600), auction.extension);
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_TOKEN_OWNER, &[]),
+            ExecuteMsg::DeleteTemplate {
+                name: "weekly".to_owned(),
+            },
+        )
+        .unwrap();
+        let res = start_from_template(deps.as_mut(), "weekly", None);
+        assert_eq!(ContractError::TemplateDoesNotExist {}, res.unwrap_err());
+    }
+
+    #[test]
+    fn test_template_bid_increment_and_extension_apply_to_bids() {
+        let mut deps = custom_mock_dependencies(&[]);
+        let info = mock_info("owner", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+        let msg = ExecuteMsg::SaveTemplate {
+            name: "weekly".to_owned(),
+            params: AuctionTemplate {
+                duration: 100000,
+                coin_denomination: "usd".to_string(),
+                min_bid: None,
+                reserve_price: None,
+                access: None,
+                accepted_denoms: None,
+                bid_increment: Some(Uint128::zero()),
+                extension: None,
+            },
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MOCK_TOKEN_OWNER, &[]),
+            msg,
+        );
+        assert_eq!(ContractError::InvalidBidIncrement {}, res.unwrap_err());
+        save_template(deps.as_mut(), "weekly", 100000).unwrap();
+        // Bids come in at 150000, 50000 seconds before the end.
+        start_from_template(
+            deps.as_mut(),
+            "weekly",
+            Some(TemplateOverrides {
+                extension: Some(60000),
+                ..TemplateOverrides::default()
+            }),
+        )
+        .unwrap();
+
+        let res = BidBuilder::new("bidder", 300)
+            .submit(deps.as_mut())
+            .unwrap();
+        assert_eq!(
+            vec![
+                bid_event("bidder", "bidder", "300"),
+                Event::new("auction_extended")
+                    .add_attribute("auction_id", "1")
+                    .add_attribute("previous_end_time", "200000")
+                    .add_attribute("end_time", "210000"),
+            ],
+            res.events
+        );
+        let auction = load_nft_auction_state(deps.as_ref().storage, 1).unwrap();
+        assert_eq!(Timestamp::from_seconds(210000), auction.end);
+        let res = BidBuilder::new("other", 324).submit(deps.as_mut());
+        assert_eq!(ContractError::BidSmallerThanHighestBid {}, res.unwrap_err());
+        // A bid that would not move the end does not extend the auction.
+        let res = BidBuilder::new("other", 325).submit(deps.as_mut()).unwrap();
+        assert!(!res
+            .events
+            .iter()
+            .any(|event| event.ty == "auction_extended"));
+        let auction = load_nft_auction_state(deps.as_ref().storage, 1).unwrap();
+        assert_eq!(Timestamp::from_seconds(210000), auction.end);
+        assert_eq!(Uint128::new(325), auction.high_bidder_amount);
     }
 }